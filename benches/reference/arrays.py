@@ -0,0 +1,12 @@
+from functools import reduce
+total = 0
+i = 0
+while i < 20000:
+    row = [i, i + 1, i + 2, i + 3]
+    doubled = list(map(lambda x: x * 2, row))
+    total = total + reduce(lambda sum, x: sum + x, filter(lambda x: x > 0, doubled), 0)
+    match row:
+        case [first, *_]:
+            total = total + first
+    i = i + 1
+assert total == 1800150000
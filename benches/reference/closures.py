@@ -0,0 +1,13 @@
+def counter():
+    count = 0
+    def increment():
+        nonlocal count
+        count = count + 1
+        return count
+    return increment
+increment = counter()
+i = 0
+while i < 100000:
+    increment()
+    i = i + 1
+assert increment() == 100001
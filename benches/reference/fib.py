@@ -1,6 +1,5 @@
 def fib(n):
-    pred = n < 2
-    if pred:
+    if n < 2:
         return n
     return fib(n-2)+fib(n-1)
-print(fib(30))
\ No newline at end of file
+assert fib(25) == 75025
@@ -0,0 +1,18 @@
+class Counter:
+    def __init__(self):
+        self.count = 0
+    def step(self):
+        self.count = self.count + 1
+        return self.count
+class Named(Counter):
+    def name(self):
+        return "named"
+class Leaf(Named):
+    def kind(self):
+        return "leaf"
+leaf = Leaf()
+i = 0
+while i < 100000:
+    leaf.step()
+    i = i + 1
+assert leaf.step() == 100001
@@ -0,0 +1,6 @@
+built = ""
+i = 0
+while i < 2000:
+    built = built + "ab"
+    i = i + 1
+assert len(built) == 4000
@@ -0,0 +1,180 @@
+//! runs every script in `benches/scripts` through the library api and reports
+//! how long it took, how many instructions and allocations it needed, and how it
+//! compares to the matching script in `benches/reference` (if python3 is around)
+//!
+//! `cargo bench` runs all of them, `cargo bench -- fib` only runs the ones matching `fib`
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::RefCell,
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    rc::Rc,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+use limesherbet::{
+    backend::vm::VirtualMachine,
+    cli_helper::Diagnostics,
+    common::{closure::Closure, function::Function, value::Value},
+    frontend::compiler::{Compiler, FunctionType},
+};
+
+/// counts every allocation the benchmark makes, so regressions in the vm's
+/// allocation behaviour show up next to the timings
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+const SCRIPTS: &str = "benches/scripts";
+const REFERENCE: &str = "benches/reference";
+/// overridden with `LIMESHERBET_BENCH_ITERATIONS`
+const ITERATIONS: u32 = 5;
+
+struct Measurement {
+    compile: Duration,
+    run: Duration,
+    instructions: u64,
+    allocations: usize,
+    allocated_bytes: usize,
+}
+
+fn main() {
+    // cargo passes `--bench` along with any filter given after `--`
+    let filter = std::env::args().skip(1).find(|arg| !arg.starts_with('-'));
+    let iterations = std::env::var("LIMESHERBET_BENCH_ITERATIONS")
+        .ok()
+        .and_then(|iterations| iterations.parse().ok())
+        .unwrap_or(ITERATIONS);
+
+    let mut scripts: Vec<PathBuf> = fs::read_dir(SCRIPTS)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "mng"))
+        .collect();
+    scripts.sort();
+
+    println!(
+        "{:<12} {:>10} {:>12} {:>14} {:>12} {:>12} {:>12} {:>12}",
+        "bench", "compile", "run", "instructions", "instrs/s", "allocs", "alloc'd", "python3"
+    );
+    for script in scripts {
+        let name = script.file_stem().unwrap().to_str().unwrap().to_owned();
+        if filter.as_ref().is_some_and(|filter| !name.contains(filter)) {
+            continue;
+        }
+        let source = fs::read_to_string(&script).unwrap();
+        let measurements: Vec<Measurement> =
+            (0..iterations).map(|_| measure(&script, &source)).collect();
+        let mean = mean(&measurements);
+        let reference = reference(&name, iterations);
+
+        println!(
+            "{:<12} {:>10} {:>12} {:>14} {:>12} {:>12} {:>12} {:>12}",
+            name,
+            format_duration(mean.compile),
+            format_duration(mean.run),
+            mean.instructions,
+            format!(
+                "{:.1}M",
+                mean.instructions as f64 / mean.run.as_secs_f64() / 1_000_000.0
+            ),
+            mean.allocations,
+            format!("{}KiB", mean.allocated_bytes / 1024),
+            reference.map_or("-".to_owned(), format_duration),
+        );
+    }
+}
+
+fn measure(path: &Path, source: &str) -> Measurement {
+    let diagnostics = Rc::new(RefCell::new(Diagnostics::new(path)));
+
+    let start = Instant::now();
+    let mut compiler = Compiler::new(diagnostics, FunctionType::Script);
+    compiler.optimize = true;
+    let (compiled, _) = compiler.compile(source.to_owned()).unwrap();
+    let compile = start.elapsed();
+
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let allocated_bytes = ALLOCATED_BYTES.load(Ordering::Relaxed);
+    let start = Instant::now();
+    let instructions = run(compiled);
+    let run = start.elapsed();
+
+    Measurement {
+        compile,
+        run,
+        instructions,
+        allocations: ALLOCATIONS.load(Ordering::Relaxed) - allocations,
+        allocated_bytes: ALLOCATED_BYTES.load(Ordering::Relaxed) - allocated_bytes,
+    }
+}
+
+fn run(function: Function) -> u64 {
+    let mut closure = Closure {
+        func: Rc::new(function),
+        upvalues: Vec::new(),
+    };
+    let mut vm = VirtualMachine::new();
+    vm.stack.push(Value::Void);
+    vm.call(&mut closure, 0).unwrap();
+    vm.run().unwrap();
+    vm.instructions_executed
+}
+
+/// times the python version of a benchmark, if there is one
+fn reference(name: &str, iterations: u32) -> Option<Duration> {
+    let path = Path::new(REFERENCE).join(format!("{name}.py"));
+    if !path.exists() {
+        return None;
+    }
+    let mut total = Duration::ZERO;
+    for _ in 0..iterations {
+        let start = Instant::now();
+        let status = Command::new("python3")
+            .arg(&path)
+            .stdout(Stdio::null())
+            .status()
+            .ok()?;
+        if !status.success() {
+            return None;
+        }
+        total += start.elapsed();
+    }
+    Some(total / iterations)
+}
+
+fn mean(measurements: &[Measurement]) -> Measurement {
+    let count = measurements.len() as u32;
+    Measurement {
+        compile: measurements.iter().map(|m| m.compile).sum::<Duration>() / count,
+        run: measurements.iter().map(|m| m.run).sum::<Duration>() / count,
+        instructions: measurements.iter().map(|m| m.instructions).sum::<u64>() / count as u64,
+        allocations: measurements.iter().map(|m| m.allocations).sum::<usize>() / count as usize,
+        allocated_bytes: measurements
+            .iter()
+            .map(|m| m.allocated_bytes)
+            .sum::<usize>()
+            / count as usize,
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    format!("{:.3}ms", duration.as_secs_f64() * 1000.0)
+}
@@ -1,23 +1,68 @@
-use std::{cell::RefCell, ffi::OsString, fs::read_to_string, path::Path, rc::Rc, time::Instant};
+use std::{
+    cell::RefCell,
+    ffi::OsString,
+    fs::{read_dir, read_to_string, write},
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::Instant,
+};
 
-use clap::Parser;
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use limesherbet::{
     backend::vm::VirtualMachine,
     cli_helper::Diagnostics,
     common::{closure::Closure, debug::dissasemble_chunk, value::Value},
-    frontend::compiler::{Compiler, FunctionType},
+    frontend::{
+        compiler::{Compiler, FunctionType},
+        formatter::format_source,
+        linter::lint_source,
+    },
+    package::{Project, MANIFEST},
 };
 pub fn main() {
     let cli = Cli::parse();
-    let path = Path::new(&cli.path);
+    match cli.command {
+        Some(Command::Check(check_args)) => check(check_args),
+        Some(Command::Fmt(fmt_args)) => fmt(fmt_args),
+        Some(Command::Lint(lint_args)) => lint(lint_args),
+        Some(Command::Run(run_args)) => run(run_args),
+        Some(Command::Lsp) => {
+            if let Err(error) = limesherbet::lsp::run() {
+                eprintln!("language server failed: {error}");
+                std::process::exit(1);
+            }
+        }
+        None => run(cli.run),
+    }
+}
+
+fn run(cli: RunArgs) {
+    let path = script_path(cli.path.as_ref());
+    let path = path.as_path();
     let source = read_to_string(path).unwrap();
     let diagnostics = create_rc(Diagnostics::new(path));
-    let duration = Instant::now();
-    let compiler = Compiler::new(diagnostics.clone(), FunctionType::Script);
-    let elapsed = duration.elapsed();
-    let (compiled, file_node) = compiler.compile(source).unwrap();
-
-    println!("Compiled in {}s", elapsed.as_secs_f64() * 1000.0);
+    let start = Instant::now();
+    let mut compiler = Compiler::new(diagnostics.clone(), FunctionType::Script);
+    compiler.optimize = cli.optimize;
+    let (compiled, file_node) = match compiler.compile(source) {
+        Ok(compiled) => compiled,
+        Err(errors) => {
+            errors
+                .iter()
+                .for_each(|error| diagnostics.borrow().emit(error));
+            std::process::exit(1);
+        }
+    };
+    let warnings = diagnostics.borrow_mut().take();
+    warnings
+        .iter()
+        .for_each(|warning| diagnostics.borrow().emit(warning));
+    if cli.time {
+        println!(
+            "compiled in {:.3}ms",
+            start.elapsed().as_secs_f64() * 1000.0
+        );
+    }
     if cli.display_bytecode {
         dissasemble_chunk(&compiled.chunk, "main");
     }
@@ -34,8 +79,158 @@ pub fn main() {
         upvalues: Vec::new(),
     };
     vm.stack.push(Value::Void);
-    vm.call(&mut closure, 0);
-    vm.run();
+    let start = Instant::now();
+    if let Err(error) = vm.call(&mut closure, 0).and_then(|_| vm.run()) {
+        diagnostics.borrow().emit(&error.into());
+        std::process::exit(1);
+    }
+    if cli.time {
+        println!(
+            "ran {} instructions in {:.3}ms",
+            vm.instructions_executed,
+            start.elapsed().as_secs_f64() * 1000.0
+        );
+    }
+}
+
+/// the script given on the command line, or the entry point of the project the current
+/// directory is in
+fn script_path(path: Option<&OsString>) -> PathBuf {
+    let (directory, path) = match path {
+        Some(path) => {
+            let path = PathBuf::from(path);
+            let directory = match path.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+                _ => PathBuf::from("."),
+            };
+            (directory, Some(path))
+        }
+        None => (PathBuf::from("."), None),
+    };
+    let project = match Project::find(&directory) {
+        Ok(project) => project,
+        Err(error) => {
+            eprintln!("{error}");
+            std::process::exit(2);
+        }
+    };
+    match (path, project) {
+        (Some(path), _) => path,
+        (None, Some(project)) => project.entry(),
+        (None, None) => {
+            eprintln!(
+                "no script given and no `{MANIFEST}` found in this directory or its parents, see `limesherbet --help`"
+            );
+            std::process::exit(2);
+        }
+    }
+}
+
+/// compiles without running, reporting every diagnostic
+fn check(cli: CheckArgs) {
+    let path = Path::new(&cli.path);
+    let source = read_to_string(path).unwrap();
+    let diagnostics = create_rc(Diagnostics::new(path));
+    let compiler = Compiler::new(diagnostics.clone(), FunctionType::Script);
+    let (failed, reported) = match compiler.compile(source) {
+        Ok(_) => (false, diagnostics.borrow_mut().take()),
+        Err(errors) => (true, errors),
+    };
+    let diagnostics = diagnostics.borrow();
+    for diagnostic in &reported {
+        match cli.message_format {
+            MessageFormat::Human => diagnostics.emit(diagnostic),
+            // one diagnostic per line, so they can be streamed
+            MessageFormat::Json => println!("{}", diagnostics.to_json(diagnostic)),
+        }
+    }
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+/// reports code that compiles but is probably a mistake, failing only on syntax errors
+fn lint(cli: CheckArgs) {
+    let path = Path::new(&cli.path);
+    let source = read_to_string(path).unwrap();
+    let (failed, reported) = match lint_source(path, &source) {
+        Ok(warnings) => (false, warnings),
+        Err(errors) => (true, errors),
+    };
+    let mut diagnostics = Diagnostics::new(path);
+    diagnostics.source = source;
+    for diagnostic in &reported {
+        match cli.message_format {
+            MessageFormat::Human => diagnostics.emit(diagnostic),
+            MessageFormat::Json => println!("{}", diagnostics.to_json(diagnostic)),
+        }
+    }
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+/// formats scripts in place, or only reports the ones that aren't formatted with `--check`
+fn fmt(cli: FmtArgs) {
+    let roots = if cli.paths.is_empty() {
+        vec![PathBuf::from(".")]
+    } else {
+        cli.paths.iter().map(PathBuf::from).collect()
+    };
+    let mut paths = Vec::new();
+    roots
+        .iter()
+        .for_each(|root| collect_scripts(root, &mut paths));
+
+    let mut failed = false;
+    for path in &paths {
+        let source = read_to_string(path).unwrap();
+        let formatted = match format_source(path, &source) {
+            Ok(formatted) => formatted,
+            Err(errors) => {
+                let mut diagnostics = Diagnostics::new(path);
+                diagnostics.source = source;
+                errors.iter().for_each(|error| diagnostics.emit(error));
+                failed = true;
+                continue;
+            }
+        };
+        if formatted == source {
+            continue;
+        }
+        if cli.check {
+            println!("would reformat {}", path.display());
+            failed = true;
+        } else {
+            write(path, formatted).unwrap();
+        }
+    }
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+/// finds every `.mng` file under a directory, a file given directly is always used
+fn collect_scripts(path: &Path, paths: &mut Vec<PathBuf>) {
+    if !path.is_dir() {
+        paths.push(path.to_path_buf());
+        return;
+    }
+    let mut entries: Vec<PathBuf> = read_dir(path)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    entries.sort();
+    for entry in entries {
+        if entry.is_dir() {
+            collect_scripts(&entry, paths);
+        } else if entry
+            .extension()
+            .is_some_and(|extension| extension == "mng")
+        {
+            paths.push(entry);
+        }
+    }
 }
 
 pub type RcRf<T> = Rc<RefCell<T>>;
@@ -45,12 +240,69 @@ pub fn create_rc<T>(v: T) -> RcRf<T> {
 }
 #[derive(Parser)]
 #[clap(author, version, about)]
+#[command(args_conflicts_with_subcommands = true)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[command(flatten)]
+    run: RunArgs,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Runs a script, or the entry point from `limesherbet.toml` when no script is given
+    Run(RunArgs),
+    /// Compiles a script without running it and reports any diagnostics
+    Check(CheckArgs),
+    /// Warns about unused variables, shadowing, unreachable code and other likely mistakes
+    Lint(CheckArgs),
+    /// Formats scripts, every `.mng` file under the current directory by default
+    Fmt(FmtArgs),
+    /// Runs a language server for editors over stdio
+    Lsp,
+}
+
+#[derive(Args)]
+struct RunArgs {
     #[clap(value_parser)]
-    path: OsString,
+    path: Option<OsString>,
 
     #[arg(long = "dbc", help = "Displays the compiled bytecode")]
     display_bytecode: bool,
     #[arg(long = "dast", help = "Displays the ast")]
     display_ast: bool,
+    #[arg(short = 'O', long = "optimize", help = "Runs the ast optimizer")]
+    optimize: bool,
+    #[arg(long = "time", help = "Displays how long compiling and running took")]
+    time: bool,
+}
+
+#[derive(Args)]
+struct CheckArgs {
+    #[clap(value_parser)]
+    path: OsString,
+    #[arg(
+        long = "message-format",
+        value_enum,
+        default_value_t = MessageFormat::Human,
+        help = "How diagnostics are printed"
+    )]
+    message_format: MessageFormat,
+}
+
+#[derive(Args)]
+struct FmtArgs {
+    #[clap(value_parser)]
+    paths: Vec<OsString>,
+    #[arg(
+        long = "check",
+        help = "Lists the files that would change instead of writing them"
+    )]
+    check: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum MessageFormat {
+    Human,
+    Json,
 }
@@ -20,18 +20,40 @@ fn recurse_dir(path: &Path, stream: &mut Vec<TokenStream>, pre_pend: String) {
     for (_i, file) in fs::read_dir(path).unwrap().enumerate() {
         let file = file.unwrap();
         if file.file_type().unwrap().is_file() {
+            // i.e the `limesherbet.toml` of a test project
+            if file.path().extension().is_none_or(|extension| extension != "mng") {
+                continue;
+            }
             let path = file.path();
             let path_string = path.to_str().unwrap();
             let name = path.file_stem().unwrap().to_str().unwrap();
             let name = pre_pend.to_owned() + name;
             let tmp_name = format_ident!("{}", name);
             let source = fs::read_to_string(Path::new(path_string)).unwrap();
+            // scripts in `tests/scripts/optimize` run through the ast optimizer
+            let optimize = pre_pend.starts_with("optimize_");
+            // `// error[E0100]` comments list the diagnostics a script is expected to fail with
+            let expected_errors: Vec<String> = source
+                .lines()
+                .filter_map(|line| line.trim().strip_prefix("// error["))
+                .filter_map(|line| line.split(']').next())
+                .map(str::to_owned)
+                .collect();
+            // scripts in `tests/scripts/lint` are linted too, and list the lints they expect
+            // to trigger with `// warning[W0001]` comments
+            let lint = pre_pend.starts_with("lint_");
+            let expected_warnings: Vec<String> = source
+                .lines()
+                .filter_map(|line| line.trim().strip_prefix("// warning["))
+                .filter_map(|line| line.split(']').next())
+                .map(str::to_owned)
+                .collect();
             let token = quote! {
                 #[test]
                 fn #tmp_name() {
                     use limesherbet::{
                         backend::vm::VirtualMachine,
-                        cli_helper::{Diagnostics},
+                        cli_helper::{Diagnostic, Diagnostics},
                         frontend::compiler::{{Compiler, FunctionType}},
                         common::{value::Value, interner::StringInterner, closure::Closure, debug::dissasemble_chunk},
                     };
@@ -45,17 +67,42 @@ fn recurse_dir(path: &Path, stream: &mut Vec<TokenStream>, pre_pend: String) {
 
                     let mut diagnostics = Rc::new(RefCell::new(Diagnostics::new(Path::new(#path_string))));
 
-                    let compiler = Compiler::new(diagnostics, FunctionType::Script);
-                    let (compiled, _) = compiler.compile(#source.to_string()).unwrap();
-                    limesherbet::common::debug::dissasemble_chunk(&compiled.chunk, "test");
-                    let mut closure = Closure {
-                        func: Rc::new(compiled),
-                        upvalues: Vec::new()
+                    let mut compiler = Compiler::new(diagnostics.clone(), FunctionType::Script);
+                    compiler.optimize = #optimize;
+                    let errors: Vec<Diagnostic> = match compiler.compile(#source.to_string()) {
+                        Ok((compiled, _)) => {
+                            limesherbet::common::debug::dissasemble_chunk(&compiled.chunk, "test");
+                            let mut closure = Closure {
+                                func: Rc::new(compiled),
+                                upvalues: Vec::new()
+                            };
+                            let mut vm = VirtualMachine::new();
+                            vm.stack.push(Value::Void);
+                            match vm.call(&mut closure, 0).and_then(|_| vm.run()) {
+                                Ok(()) => Vec::new(),
+                                Err(error) => vec![error.into()],
+                            }
+                        }
+                        Err(errors) => errors,
                     };
-                    let mut vm = VirtualMachine::new();
-                    vm.stack.push(Value::Void);
-                    vm.call(&mut closure,0);
-                    vm.run();
+                    let rendered: String = errors
+                        .iter()
+                        .map(|error| diagnostics.borrow().render(error))
+                        .collect();
+                    let codes: Vec<&str> = errors.iter().map(|error| error.code.code()).collect();
+                    let expected: &[&str] = &[#(#expected_errors),*];
+                    assert_eq!(codes, expected, "\n{}", rendered);
+
+                    if #lint {
+                        let warnings = limesherbet::frontend::linter::lint_source(Path::new(#path_string), #source).unwrap();
+                        let rendered: String = warnings
+                            .iter()
+                            .map(|warning| diagnostics.borrow().render(warning))
+                            .collect();
+                        let codes: Vec<&str> = warnings.iter().map(|warning| warning.code.code()).collect();
+                        let expected: &[&str] = &[#(#expected_warnings),*];
+                        assert_eq!(codes, expected, "\n{}", rendered);
+                    }
                 }
             };
             stream.push(token.into())
@@ -6,3 +6,12 @@ pub struct CallFrame {
     pub ip: usize,
     pub slots: usize,
 }
+
+/// where a `try` catches errors, dropping every frame and value pushed since it started
+#[derive(Debug, Clone)]
+pub struct Handler {
+    pub frame_count: usize,
+    pub stack_len: usize,
+    /// the instruction the `catch` block starts at
+    pub catch: usize,
+}
@@ -0,0 +1,66 @@
+use std::rc::Rc;
+
+use crate::{
+    cli_helper::{Diagnostic, ErrorCode, SourceFile},
+    common::value::Value,
+    frontend::scanner::Span,
+};
+
+/// an error raised while running, pointing back at the source of the instruction
+/// that raised it so it can be rendered like any other diagnostic
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeError {
+    pub code: ErrorCode,
+    pub message: String,
+    pub span: Span,
+    pub notes: Vec<String>,
+    /// the module the span points into, `None` for the script being run
+    pub file: Option<Rc<SourceFile>>,
+    /// what a `throw` threw, which is what its `catch` gets instead of an error value
+    pub thrown: Option<Value>,
+}
+impl RuntimeError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> RuntimeError {
+        RuntimeError {
+            code,
+            message: message.into(),
+            span: Span::default(),
+            notes: Vec::new(),
+            file: None,
+            thrown: None,
+        }
+    }
+    pub fn with_note(mut self, note: impl Into<String>) -> RuntimeError {
+        self.notes.push(note.into());
+        self
+    }
+    /// errors raised outside of `run` don't know which instruction they came from
+    pub fn at(mut self, span: Span, file: Option<Rc<SourceFile>>) -> RuntimeError {
+        self.span = span;
+        self.file = file;
+        self
+    }
+    /// like `at`, but an error raised inside a callback keeps pointing at where it was
+    pub fn or_at(self, span: Span, file: Option<Rc<SourceFile>>) -> RuntimeError {
+        match self.span == Span::default() {
+            true => self.at(span, file),
+            false => self,
+        }
+    }
+}
+impl From<RuntimeError> for Diagnostic {
+    fn from(error: RuntimeError) -> Self {
+        let mut diagnostic =
+            Diagnostic::error(error.code, error.message).with_label(error.span, "raised here");
+        diagnostic.notes = error.notes;
+        diagnostic.in_file(error.file)
+    }
+}
+
+/// a runtime error caught by a `catch`, along with the calls it was raised in
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorValue {
+    pub error: RuntimeError,
+    /// the innermost call first
+    pub trace: Vec<String>,
+}
@@ -1,2 +1,3 @@
 pub mod callframe;
+pub mod error;
 pub mod vm;
@@ -1,41 +1,47 @@
-use std::{cell::RefCell, collections::HashMap, rc::Rc, time::Instant};
+use std::{cell::RefCell, rc::Rc};
 
 use crate::common::interner::InternedString;
 use crate::{
-    backend::vm::natives::NATIVES,
+    backend::{
+        error::{ErrorValue, RuntimeError},
+        vm::natives::NATIVES,
+    },
+    cli_helper::ErrorCode,
     common::{
-        chunk::Chunk,
+        class::{BoundMethod, Class, Instance},
         closure::Closure,
-        function::Function,
+        enums::{is_result, variant_name, Variant},
         natives::Native,
         opcode::OpCode,
-        value::{AsValue, RuntimeUpvalue, Value},
+        structs::Struct,
+        value::{rcrf, AsValue, RuntimeUpvalue, Value},
     },
 };
 
 use self::natives::NATIVES_LEN;
 
-use super::callframe::CallFrame;
+use super::callframe::{CallFrame, Handler};
 
 pub mod natives;
 pub mod ops;
-pub const FUNCTION: Function = Function {
-    chunk: Chunk {
-        code: Vec::new(),
-        constants: Vec::new(),
-    },
-    arity: 0,
-    name: String::new(),
-    upvalue_count: 0,
-};
 
 #[derive(Debug)]
 pub struct VirtualMachine {
     pub stack: Vec<Value>,
     pub callframes: [CallFrame; 2048],
     pub frame_count: usize,
-    pub globals: HashMap<usize, Value>,
+    /// the `try` blocks that are running, the innermost last
+    pub handlers: Vec<Handler>,
+    /// indexed by the slots the compiler gave each global
+    pub globals: Vec<Value>,
     pub natives: &'static [Native; NATIVES_LEN],
+    /// how many instructions `run` has dispatched, used by the benchmarks
+    pub instructions_executed: u64,
+    /// the name of the method that initializes a new instance
+    pub init: InternedString,
+    /// the frames below the call `run` was entered for, returning to them ends the run,
+    /// which is how `call_value` gets back what a callback returned
+    pub entry: usize,
 }
 
 impl VirtualMachine {
@@ -49,28 +55,183 @@ impl VirtualMachine {
             callframes: [CALLFRAME; 2048],
             stack: vec![],
             natives: &NATIVES,
-            globals: HashMap::new(),
+            globals: Vec::new(),
             frame_count: 0,
+            handlers: Vec::new(),
+            instructions_executed: 0,
+            init: InternedString::from("init"),
+            entry: 0,
         }
     }
     #[allow(clippy::not_unsafe_ptr_arg_deref, unsafe_code)]
-    pub fn call(&mut self, closure: *mut Closure, arg_count: usize) {
+    pub fn call(&mut self, closure: *mut Closure, arg_count: usize) -> Result<(), RuntimeError> {
         let function = unsafe { &(*closure).func };
-        let arity = function.arity;
-        if arg_count != arity as usize {
-            panic!("mismatched argument counts! expected {arity} got {arg_count}");
+        let arity = function.arity as usize;
+        let required = arity - function.optional as usize;
+        // `this` is passed to methods as an extra argument
+        let receiver = function.method as usize;
+        let given = arg_count - receiver;
+        if given < required || given > arity && !function.variadic {
+            let expected = match (function.optional, function.variadic) {
+                (_, true) => format!("at least {required}"),
+                (0, false) => format!("{arity}"),
+                _ => format!("{required} to {arity}"),
+            };
+            return Err(RuntimeError::new(
+                ErrorCode::ArityMismatch,
+                format!(
+                    "'{}' takes {expected} arguments but {given} were given",
+                    function.name,
+                ),
+            ));
+        }
+        if self.frame_count == self.callframes.len() {
+            return Err(RuntimeError::new(
+                ErrorCode::StackOverflow,
+                format!("stack overflow while calling '{}'", function.name),
+            )
+            .with_note(format!(
+                "calls can nest at most {} deep",
+                self.callframes.len()
+            )));
+        }
+
+        if self.frame_count == 0 {
+            self.globals
+                .resize(function.global_names.len(), Value::None);
+        }
+
+        // the parameters that were left out get their defaults once the function runs
+        for _ in given..arity {
+            self.stack.push(Value::None);
         }
+        if function.variadic {
+            let rest = self
+                .stack
+                .split_off(self.stack.len() - given.saturating_sub(arity));
+            self.stack.push(Value::Array(rcrf(rest)));
+        }
+        let parameters = arity + function.variadic as usize + receiver;
 
         let frame: &mut CallFrame = &mut self.callframes[self.frame_count];
 
         frame.closure = closure;
-        frame.slots = self.stack.len() - (arg_count + 1);
+        frame.ip = 0;
+        frame.slots = self.stack.len() - (parameters + 1);
 
         self.frame_count += 1;
+        Ok(())
+    }
+
+    /// calls a function from outside the dispatch loop and runs it to completion, so a
+    /// native can call back into the script
+    pub fn call_value(&mut self, callee: Value, args: Vec<Value>) -> Result<Value, RuntimeError> {
+        let (entry, stack_len) = (self.entry, self.stack.len());
+        let arg_count = args.len();
+        self.stack.push(Value::None);
+        self.stack.extend(args);
+        let called = match callee {
+            Value::Closure(closure) => self.call(Box::into_raw(closure), arg_count),
+            Value::BoundMethod(bound) => {
+                let receiver = Value::Instance(bound.receiver.clone());
+                self.stack.insert(stack_len + 1, receiver);
+                let method = Box::new(bound.method.clone());
+                self.call(Box::into_raw(method), arg_count + 1)
+            }
+            Value::VariantConstructor(kind, index) => {
+                let arity = kind.variants[index].fields.len();
+                let values: Vec<Value> = self.stack.drain(stack_len + 1..).collect();
+                self.stack.truncate(stack_len);
+                if arg_count != arity {
+                    return Err(RuntimeError::new(
+                        ErrorCode::ArityMismatch,
+                        format!(
+                            "'{}' takes {arity} arguments but {arg_count} were given",
+                            variant_name(&kind, index)
+                        ),
+                    ));
+                }
+                return Ok(Value::Variant(Rc::new(Variant {
+                    kind,
+                    index,
+                    values,
+                })));
+            }
+            callee => Err(RuntimeError::new(
+                ErrorCode::NotCallable,
+                format!("{} is not callable", callee.type_name()),
+            )),
+        };
+        if let Err(error) = called {
+            self.stack.truncate(stack_len);
+            return Err(error);
+        }
+        self.entry = self.frame_count - 1;
+        let ran = self.run();
+        match ran {
+            Ok(()) => {
+                self.entry = entry;
+                Ok(self.stack.pop().unwrap_or_default())
+            }
+            Err(error) => {
+                // the frames the error escaped from are abandoned, like a `catch` would
+                self.frame_count = self.entry;
+                self.entry = entry;
+                self.stack.truncate(stack_len);
+                Err(error)
+            }
+        }
+    }
+
+    pub fn run(&mut self) -> Result<(), RuntimeError> {
+        loop {
+            match self.dispatch() {
+                Ok(()) => return Ok(()),
+                Err(error) => self.catch(error)?,
+            }
+        }
+    }
+
+    /// unwinds to the innermost `try`, or gives the error back when nothing catches it
+    fn catch(&mut self, mut error: RuntimeError) -> Result<(), RuntimeError> {
+        // a `try` outside of the callback a native is running is for the native's caller
+        let entry = self.entry;
+        let Some(handler) = self.handlers.pop_if(|handler| handler.frame_count > entry) else {
+            return Err(error);
+        };
+        let value = match error.thrown.take() {
+            Some(value) => value,
+            None => {
+                let trace = self.trace();
+                Value::Error(Rc::new(ErrorValue { error, trace }))
+            }
+        };
+        // captured locals already live in the closures that captured them, so dropping
+        // the abandoned frames' values closes their upvalues too
+        self.frame_count = handler.frame_count;
+        self.stack.truncate(handler.stack_len);
+        self.stack.push(value);
+        self.callframes[self.frame_count - 1].ip = handler.catch;
+        Ok(())
+    }
+
+    /// the function and line each running call is at, the innermost first
+    fn trace(&self) -> Vec<String> {
+        self.callframes[..self.frame_count]
+            .iter()
+            .rev()
+            .map(|frame| {
+                #[allow(unsafe_code)]
+                let function = unsafe { &(*frame.closure).func };
+                let span = function.chunk.spans.get(frame.ip.saturating_sub(1));
+                let line = span.map_or(0, |span| span.position.line + 1);
+                format!("{} (line {line})", function.name)
+            })
+            .collect()
     }
 
-    pub fn run(mut self) {
-        let start = Instant::now();
+    /// runs from where the current frame is until the script ends or an error is raised
+    fn dispatch(&mut self) -> Result<(), RuntimeError> {
         let mut current_frame = &self.callframes[self.frame_count - 1] as *const CallFrame;
 
         macro_rules! current_frame {
@@ -108,6 +269,60 @@ impl VirtualMachine {
                 tmp
             }};
         }
+        macro_rules! peek {
+            () => {{
+                let tmp = self.stack.len() - 1;
+                &self.stack[tmp]
+            }};
+        }
+
+        let mut current_closure = read_current_closure!();
+        let mut function = read_current_frame_fn!();
+        let mut chunk = &function.chunk;
+        let mut ip = current_frame!().ip;
+
+        // the span of the instruction that was just dispatched
+        macro_rules! span {
+            () => {{
+                chunk.spans.get(ip - 1).cloned().unwrap_or_default()
+            }};
+        }
+        // the frame keeps where it failed, for the trace of the error
+        macro_rules! fail {
+            ($error:expr) => {{
+                self.callframes[self.frame_count - 1].ip = ip;
+                return Err($error);
+            }};
+        }
+        macro_rules! runtime_error {
+            ($code:expr, $($arg:tt)*) => {{
+                fail!(RuntimeError::new($code, format!($($arg)*))
+                    .at(span!(), function.file.clone()))
+            }};
+        }
+        macro_rules! condition {
+            ($value:expr) => {{
+                match $value {
+                    Value::Boolean(bool) => *bool,
+                    value => runtime_error!(
+                        ErrorCode::TypeMismatch,
+                        "condition must be a bool, not {}",
+                        value.type_name()
+                    ),
+                }
+            }};
+        }
+        macro_rules! type_mismatch {
+            ($op:tt, $lhs:expr, $rhs:expr) => {{
+                runtime_error!(
+                    ErrorCode::TypeMismatch,
+                    "cannot apply `{}` to {} and {}",
+                    stringify!($op),
+                    $lhs.type_name(),
+                    $rhs.type_name()
+                )
+            }};
+        }
         macro_rules! binary_op {
             ($op:tt) => {
                 #[allow(clippy::assign_op_pattern)]
@@ -116,14 +331,11 @@ impl VirtualMachine {
                 let tmp = self.stack.len() - 1;
                 let lhs = &mut self.stack[tmp];
 
-                match lhs {
-                    Value::Number(lhs) => {
-                        let Value::Number(rhs) = rhs else {
-                            panic!()
-                        };
+                match (lhs, &rhs) {
+                    (Value::Number(lhs), Value::Number(rhs)) => {
                         *lhs = *lhs $op rhs;
                     }
-                    x => unimplemented!("cannot apply binary operation to value {}", x),
+                    (lhs, rhs) => type_mismatch!($op, lhs, rhs),
                 }
             }};
         }
@@ -132,36 +344,26 @@ impl VirtualMachine {
                 let rhs = pop!();
                 let lhs = pop!();
 
-                match lhs {
-                    Value::Number(lhs) => {
-                        let Value::Number(rhs) = rhs else {
-                            panic!()
-                        };
+                match (&lhs, &rhs) {
+                    (Value::Number(lhs), Value::Number(rhs)) => {
                         self.stack.push(Value::Boolean(lhs $op rhs))
                     }
-                    x => unimplemented!("cannot apply binary operation to value {}", x),
+                    _ => type_mismatch!($op, lhs, rhs),
                 }
             }};
         }
-        macro_rules! peek {
-            () => {{
-                let tmp = self.stack.len() - 1;
-                &self.stack[tmp]
-            }};
-        }
-
-        let mut current_closure = read_current_closure!();
-        let mut function = read_current_frame_fn!();
-        let mut chunk = &function.chunk;
-        let mut ip: usize = 0;
 
         loop {
             let instruction = &chunk.code[ip];
+            self.instructions_executed += 1;
 
             ip += 1;
 
             match instruction.clone() {
-                OpCode::CloseUpvalue => {}
+                // the closures that captured the local share its value already
+                OpCode::CloseUpvalue => {
+                    pop!();
+                }
                 OpCode::Byte(_) => {}
                 OpCode::SetUpValue(u) => {
                     *current_closure.upvalues[u as usize].location.borrow_mut() = peek!().clone();
@@ -178,13 +380,334 @@ impl VirtualMachine {
                     self.closure_op((chunk, function, ip, current_frame!()), location);
                 }
                 OpCode::SetLocalConsumes(index) => {
-                    self.stack[index as usize + 1 + current_frame!().slots] = pop!();
+                    let value = pop!();
+                    self.set_local(index as usize + 1 + current_frame!().slots, value);
                 }
                 OpCode::Equal => {
-                    binary_op_bool!(==)
+                    let rhs = pop!();
+                    let lhs = pop!();
+                    self.stack.push(Value::Boolean(lhs == rhs))
                 }
                 OpCode::NotEqual => {
-                    binary_op_bool!(!=)
+                    let rhs = pop!();
+                    let lhs = pop!();
+                    self.stack.push(Value::Boolean(lhs != rhs))
+                }
+                OpCode::Class(location) => {
+                    let name = chunk.constants[location as usize].as_string();
+                    let class = Class::new((*name).into());
+                    self.stack.push(Value::Class(rcrf(class)))
+                }
+                OpCode::Method(location) => {
+                    let name = *chunk.constants[location as usize].as_string();
+                    let Value::Closure(method) = pop!() else {
+                        unreachable!("a method is always compiled into a closure")
+                    };
+                    let Value::Class(class) = peek!() else {
+                        unreachable!("methods are added right after their class is created")
+                    };
+                    class.borrow_mut().methods.insert(name, *method);
+                }
+                OpCode::GetProperty(location) => {
+                    let name = *chunk.constants[location as usize].as_string();
+                    let object = pop!();
+                    if let Value::Enum(kind) = &object {
+                        let Some(index) = kind.variant_index(name) else {
+                            runtime_error!(
+                                ErrorCode::UndefinedProperty,
+                                "'{}' has no variant '{}'",
+                                kind.name,
+                                String::from(name)
+                            )
+                        };
+                        // variants without values are values already
+                        let variant = match kind.variants[index].fields.is_empty() {
+                            true => Value::Variant(Rc::new(Variant {
+                                kind: kind.clone(),
+                                index,
+                                values: Vec::new(),
+                            })),
+                            false => Value::VariantConstructor(kind.clone(), index),
+                        };
+                        self.stack.push(variant);
+                        continue;
+                    }
+                    if let Value::Error(error) = &object {
+                        let value = match String::from(name).as_str() {
+                            "message" => error.error.message.as_str().to_value(),
+                            "code" => error.error.code.code().to_value(),
+                            "trace" => {
+                                let trace = error.trace.iter().map(|call| call.as_str().to_value());
+                                Value::Array(rcrf(trace.collect()))
+                            }
+                            _ => runtime_error!(
+                                ErrorCode::UndefinedProperty,
+                                "an error has no property '{}'",
+                                String::from(name)
+                            ),
+                        };
+                        self.stack.push(value);
+                        continue;
+                    }
+                    if let Value::Struct(value) = &object {
+                        let Some(field) = value.borrow().get(name).cloned() else {
+                            runtime_error!(
+                                ErrorCode::UndefinedProperty,
+                                "'{}' has no field '{}'",
+                                value.borrow().kind.name,
+                                String::from(name)
+                            )
+                        };
+                        self.stack.push(field);
+                        continue;
+                    }
+                    let Value::Instance(instance) = &object else {
+                        runtime_error!(
+                            ErrorCode::TypeMismatch,
+                            "cannot read property '{}' of {}",
+                            String::from(name),
+                            object.type_name()
+                        )
+                    };
+                    let borrowed = instance.borrow();
+                    // fields shadow methods
+                    let value = match borrowed.fields.get(&name) {
+                        Some(field) => field.clone(),
+                        None => match Self::cached_method(chunk, ip - 1, &borrowed.class, name) {
+                            Some(method) => Value::BoundMethod(Rc::new(BoundMethod {
+                                receiver: instance.clone(),
+                                method,
+                            })),
+                            None => runtime_error!(
+                                ErrorCode::UndefinedProperty,
+                                "'{}' has no property '{}'",
+                                borrowed.class.borrow().name,
+                                String::from(name)
+                            ),
+                        },
+                    };
+                    drop(borrowed);
+                    self.stack.push(value)
+                }
+                OpCode::SetProperty(location) => {
+                    let name = *chunk.constants[location as usize].as_string();
+                    let value = pop!();
+                    let object = pop!();
+                    if let Value::Struct(target) = &object {
+                        // a struct can't gain fields it wasn't declared with
+                        let mut target = target.borrow_mut();
+                        let Some(index) = target.kind.field_index(name) else {
+                            runtime_error!(
+                                ErrorCode::UndefinedProperty,
+                                "'{}' has no field '{}'",
+                                target.kind.name,
+                                String::from(name)
+                            )
+                        };
+                        target.values[index] = value.clone();
+                        drop(target);
+                        self.stack.push(value);
+                        continue;
+                    }
+                    let Value::Instance(instance) = &object else {
+                        runtime_error!(
+                            ErrorCode::TypeMismatch,
+                            "cannot set property '{}' on {}",
+                            String::from(name),
+                            object.type_name()
+                        )
+                    };
+                    instance.borrow_mut().fields.insert(name, value.clone());
+                    self.stack.push(value)
+                }
+                OpCode::Inherit => {
+                    let Value::Class(class) = pop!() else {
+                        unreachable!("only a class declaration inherits")
+                    };
+                    let Value::Class(superclass) = peek!() else {
+                        runtime_error!(
+                            ErrorCode::TypeMismatch,
+                            "'{}' can only inherit from a class, not {}",
+                            class.borrow().name,
+                            peek!().type_name()
+                        )
+                    };
+                    class.borrow_mut().superclass = Some(superclass.clone());
+                }
+                OpCode::GetSuper(location) => {
+                    let name = *chunk.constants[location as usize].as_string();
+                    let Value::Class(superclass) = pop!() else {
+                        unreachable!("`super` is always the superclass")
+                    };
+                    let Value::Instance(receiver) = pop!() else {
+                        unreachable!("`this` is always an instance")
+                    };
+                    let Some(method) = Self::cached_method(chunk, ip - 1, &superclass, name) else {
+                        runtime_error!(
+                            ErrorCode::UndefinedProperty,
+                            "'{}' has no method '{}'",
+                            superclass.borrow().name,
+                            String::from(name)
+                        )
+                    };
+                    self.stack.push(Value::BoundMethod(Rc::new(BoundMethod {
+                        receiver,
+                        method,
+                    })))
+                }
+                OpCode::Construct(field_count) => {
+                    let start = self.stack.len() - 2 * field_count as usize;
+                    let pairs: Vec<Value> = self.stack.drain(start..).collect();
+                    let kind = match pop!() {
+                        Value::StructType(kind) => kind,
+                        other => runtime_error!(
+                            ErrorCode::TypeMismatch,
+                            "cannot construct {}, it isn't a struct",
+                            other.type_name()
+                        ),
+                    };
+                    let mut values = vec![None; kind.fields.len()];
+                    for pair in pairs.chunks(2) {
+                        let name = *pair[0].as_string();
+                        let Some(index) = kind.field_index(name) else {
+                            runtime_error!(
+                                ErrorCode::UndefinedProperty,
+                                "'{}' has no field '{}'",
+                                kind.name,
+                                String::from(name)
+                            )
+                        };
+                        values[index] = Some(pair[1].clone());
+                    }
+                    if let Some(index) = values.iter().position(Option::is_none) {
+                        runtime_error!(
+                            ErrorCode::MissingField,
+                            "missing field '{}' of '{}'",
+                            String::from(kind.fields[index]),
+                            kind.name
+                        )
+                    }
+                    let values = values.into_iter().flatten().collect();
+                    self.stack
+                        .push(Value::Struct(rcrf(Struct { kind, values })))
+                }
+                OpCode::CheckStruct => {
+                    let kind = match pop!() {
+                        Value::StructType(kind) => kind,
+                        other => runtime_error!(
+                            ErrorCode::TypeMismatch,
+                            "cannot destructure into {}, it isn't a struct",
+                            other.type_name()
+                        ),
+                    };
+                    let found = match peek!() {
+                        Value::Struct(value) if Rc::ptr_eq(&value.borrow().kind, &kind) => None,
+                        Value::Struct(value) => Some(format!("'{}'", value.borrow().kind.name)),
+                        value => Some(value.type_name().to_string()),
+                    };
+                    if let Some(found) = found {
+                        runtime_error!(
+                            ErrorCode::TypeMismatch,
+                            "cannot destructure {found} as '{}'",
+                            kind.name
+                        )
+                    }
+                }
+                OpCode::Array(count) => {
+                    let start = self.stack.len() - count as usize;
+                    let elements = self.stack.drain(start..).collect();
+                    self.stack.push(Value::Array(rcrf(elements)))
+                }
+                OpCode::GetIndex(index) => {
+                    let value = match pop!() {
+                        Value::Array(array) => array.borrow().get(index as usize).cloned(),
+                        Value::Variant(variant) => variant.values.get(index as usize).cloned(),
+                        other => runtime_error!(
+                            ErrorCode::TypeMismatch,
+                            "cannot index into {}",
+                            other.type_name()
+                        ),
+                    };
+                    let Some(value) = value else {
+                        runtime_error!(ErrorCode::TypeMismatch, "index {index} is out of bounds")
+                    };
+                    self.stack.push(value)
+                }
+                OpCode::MatchVariant => {
+                    let (kind, index) = match pop!() {
+                        Value::Variant(variant) => (variant.kind.clone(), variant.index),
+                        Value::VariantConstructor(kind, index) => (kind, index),
+                        other => runtime_error!(
+                            ErrorCode::TypeMismatch,
+                            "cannot match against {}, it isn't a variant",
+                            other.type_name()
+                        ),
+                    };
+                    let matches = match pop!() {
+                        Value::Variant(variant) => {
+                            Rc::ptr_eq(&variant.kind, &kind) && variant.index == index
+                        }
+                        _ => false,
+                    };
+                    self.stack.push(Value::Boolean(matches))
+                }
+                OpCode::MatchArray(length, rest) => {
+                    let matches = match pop!() {
+                        Value::Array(array) if rest => array.borrow().len() >= length as usize,
+                        Value::Array(array) => array.borrow().len() == length as usize,
+                        _ => false,
+                    };
+                    self.stack.push(Value::Boolean(matches))
+                }
+                OpCode::NoMatch => {
+                    let value = pop!();
+                    let shown = match &value {
+                        Value::Void | Value::None => value.type_name().to_string(),
+                        _ => value.to_string(),
+                    };
+                    runtime_error!(
+                        ErrorCode::NoMatchingArm,
+                        "no arm of the match matched {shown}"
+                    )
+                }
+                OpCode::Try(catch) => self.handlers.push(Handler {
+                    frame_count: self.frame_count,
+                    stack_len: self.stack.len(),
+                    catch,
+                }),
+                OpCode::EndTry => {
+                    self.handlers.pop();
+                }
+                OpCode::Throw => {
+                    let thrown = pop!();
+                    let error = match &thrown {
+                        // rethrowing a caught error keeps where it was raised
+                        Value::Error(caught) => caught.error.clone(),
+                        _ => RuntimeError::new(
+                            ErrorCode::UncaughtException,
+                            format!("uncaught exception: {thrown}"),
+                        )
+                        .at(span!(), function.file.clone()),
+                    };
+                    fail!(RuntimeError {
+                        thrown: Some(thrown),
+                        ..error
+                    })
+                }
+                OpCode::IsErr => {
+                    let is_err = match peek!() {
+                        Value::Variant(variant) if is_result(&variant.kind) => variant.index == 1,
+                        other => runtime_error!(
+                            ErrorCode::TypeMismatch,
+                            "cannot apply `?` to {}, it isn't a result",
+                            other.type_name()
+                        ),
+                    };
+                    self.stack.push(Value::Boolean(is_err))
+                }
+                OpCode::Dup => {
+                    let value = peek!().clone();
+                    self.stack.push(value)
                 }
                 OpCode::CallFnArgPtr(location, args) => {
                     // generate code only for CallFnArgPtr
@@ -195,31 +718,42 @@ impl VirtualMachine {
                         .drain(self.stack.len() - args as usize..)
                         .collect();
                     let args = drain.to_vec();
-                    {
-                        (native.0)(&mut self, args);
+                    if let Err(error) = (native.0)(self, args) {
+                        fail!(error.or_at(span!(), function.file.clone()))
                     }
                 }
                 OpCode::CallNative(location) => {
                     let native = &self.natives[location as usize];
                     let args: Vec<Value> = vec![];
-                    {
-                        (native.0)(&mut self, args);
+                    if let Err(error) = (native.0)(self, args) {
+                        fail!(error.at(span!(), function.file.clone()))
                     }
                 }
                 OpCode::JumpTo(offset) => {
                     ip = offset;
                 }
+                // `call` leaves `None` where an argument was left out
+                OpCode::JumpToIfGiven(slot, offset) => {
+                    let slot = slot as usize + 1 + current_frame!().slots;
+                    if !matches!(self.stack[slot], Value::None) {
+                        ip = offset;
+                    }
+                }
                 OpCode::PopJumpToIfFalse(offset) => {
                     let popped = pop!();
-                    let condition = popped.as_bool();
-                    if !condition {
+                    if !condition!(&popped) {
+                        ip = offset;
+                    }
+                }
+                OpCode::PopJumpToIfTrue(offset) => {
+                    let popped = pop!();
+                    if condition!(&popped) {
                         ip = offset;
                     }
                 }
 
                 OpCode::JumpToIfFalse(offset) => {
-                    let condition = self.stack[self.stack.len() - 1].as_bool();
-                    if !condition {
+                    if !condition!(peek!()) {
                         ip = offset;
                     }
                 }
@@ -229,7 +763,11 @@ impl VirtualMachine {
                     if let Value::Boolean(bool) = pop {
                         self.stack.push((!bool).to_value());
                     } else {
-                        panic!("not cannot be applied to {pop} ")
+                        runtime_error!(
+                            ErrorCode::TypeMismatch,
+                            "cannot apply `!` to {}",
+                            pop.type_name()
+                        )
                     }
                 }
                 OpCode::Negate => {
@@ -237,7 +775,11 @@ impl VirtualMachine {
                     if let Value::Number(num) = pop {
                         self.stack.push((-num).to_value());
                     } else {
-                        panic!("negate cannot be applied to {pop} ")
+                        runtime_error!(
+                            ErrorCode::TypeMismatch,
+                            "cannot apply `-` to {}",
+                            pop.type_name()
+                        )
                     }
                 }
                 OpCode::True => self.stack.push(Value::Boolean(true)),
@@ -246,30 +788,38 @@ impl VirtualMachine {
                     self.stack.push(chunk.constants[location as usize].clone())
                 }
                 OpCode::GetLocal(index) => {
-                    let value = self.stack[index as usize + 1 + current_frame!().slots].clone();
+                    let value = match &self.stack[index as usize + 1 + current_frame!().slots] {
+                        // a captured local lives on in the closures that captured it
+                        Value::UpvalueLocation(location) => location.borrow().clone(),
+                        value => value.clone(),
+                    };
                     self.stack.push(value)
                 }
                 OpCode::SetLocal(index) => {
-                    self.stack[index as usize + 1 + current_frame!().slots] =
-                        self.stack.last().unwrap().clone();
+                    let value = self.stack.last().unwrap().clone();
+                    self.set_local(index as usize + 1 + current_frame!().slots, value);
                 }
                 OpCode::DefineLocal(location) => {
                     self.stack.push(chunk.constants[location as usize].clone())
                 }
-                OpCode::GetGlobal(name) => {
-                    let name = chunk.constants[name as usize].as_string();
-                    let value = self.globals.get(&name.0).unwrap().clone();
-                    self.stack.push(value)
+                OpCode::GetGlobal(slot) => {
+                    let value = &self.globals[slot as usize];
+                    if let Value::None = value {
+                        runtime_error!(
+                            ErrorCode::UndefinedVariable,
+                            "Undefined variable '{}'",
+                            self.global_name(slot)
+                        )
+                    }
+                    self.stack.push(value.clone())
                 }
-                OpCode::SetGlobal(name) => {
-                    let name = chunk.constants[name as usize].as_string();
+                OpCode::SetGlobal(slot) => {
                     let value = self.stack[self.stack.len() - 1].clone();
-                    self.globals.insert(name.0, value);
+                    self.globals[slot as usize] = value;
                 }
-                OpCode::DefineGlobal(name) => {
-                    let name = chunk.constants[name as usize].as_string();
+                OpCode::DefineGlobal(slot) => {
                     let value = pop!();
-                    self.globals.insert(name.0, value);
+                    self.globals[slot as usize] = value;
                 }
                 OpCode::Void => self.stack.push(Value::Void),
                 OpCode::Add => {
@@ -277,24 +827,55 @@ impl VirtualMachine {
                     let tmp = self.stack.len() - 1;
                     let lhs = &mut self.stack[tmp];
 
-                    match lhs {
-                        Value::Number(lhs) => {
-                            let Value::Number(rhs) = rhs else {
-                                panic!()
-                            };
+                    match (lhs, rhs) {
+                        (Value::Number(lhs), Value::Number(rhs)) => {
                             *lhs += rhs;
                         }
-                        Value::String(string_ref) => {
-                            let Value::String(rhs) = rhs else {
-                                panic!("lhs {lhs:?}\nrhs{rhs:?}");
-                            };
-
+                        (Value::String(string_ref), Value::String(rhs)) => {
                             let mut lhs: String = (*string_ref).into();
                             let rhs: String = rhs.into();
                             lhs.push_str(rhs.as_str());
                             *string_ref = InternedString::from(lhs.as_ref());
                         }
-                        _ => unimplemented!(),
+                        (lhs, rhs) => type_mismatch!(+, lhs, rhs),
+                    }
+                }
+                OpCode::AddNumbers => {
+                    let rhs = pop!();
+                    let tmp = self.stack.len() - 1;
+                    match (&mut self.stack[tmp], rhs) {
+                        (Value::Number(lhs), Value::Number(rhs)) => *lhs += rhs,
+                        (lhs, rhs) => type_mismatch!(+, lhs, rhs),
+                    }
+                }
+                OpCode::AddStrings => {
+                    let rhs = pop!();
+                    let tmp = self.stack.len() - 1;
+                    match (&mut self.stack[tmp], rhs) {
+                        (Value::String(string_ref), Value::String(rhs)) => {
+                            let mut lhs: String = (*string_ref).into();
+                            let rhs: String = rhs.into();
+                            lhs.push_str(rhs.as_str());
+                            *string_ref = InternedString::from(lhs.as_ref());
+                        }
+                        (lhs, rhs) => type_mismatch!(+, lhs, rhs),
+                    }
+                }
+                OpCode::CheckType(kind) => {
+                    let value = peek!();
+                    if !kind.matches(value) {
+                        runtime_error!(
+                            ErrorCode::TypeMismatch,
+                            "expected `{kind}`, found {}",
+                            value.type_name()
+                        )
+                    }
+                }
+                OpCode::AddConst(location) => {
+                    let tmp = self.stack.len() - 1;
+                    match (&mut self.stack[tmp], &chunk.constants[location as usize]) {
+                        (Value::Number(lhs), Value::Number(rhs)) => *lhs += rhs,
+                        (lhs, rhs) => type_mismatch!(+, lhs, rhs),
                     }
                 }
                 OpCode::Sub => {
@@ -323,22 +904,48 @@ impl VirtualMachine {
                     let rhs = pop!();
                     let lhs = pop!();
 
-                    assert_eq!(lhs, rhs);
+                    if lhs != rhs {
+                        runtime_error!(
+                            ErrorCode::AssertionFailed,
+                            "assertion failed: {} == {}",
+                            lhs.quoted(),
+                            rhs.quoted()
+                        )
+                    }
                 }
                 OpCode::AssertNe => {
                     let rhs = pop!();
                     let lhs = pop!();
 
-                    assert_ne!(lhs, rhs);
+                    if lhs == rhs {
+                        runtime_error!(
+                            ErrorCode::AssertionFailed,
+                            "assertion failed: {} != {}",
+                            lhs.quoted(),
+                            rhs.quoted()
+                        )
+                    }
                 }
                 OpCode::Exit => break,
                 OpCode::Return => {
                     let returning = pop!();
                     self.frame_count -= 1;
+                    // a `return` inside a `try` leaves it too
+                    while self
+                        .handlers
+                        .last()
+                        .is_some_and(|handler| handler.frame_count > self.frame_count)
+                    {
+                        self.handlers.pop();
+                    }
 
-                    if self.frame_count == 0 {
-                        println!("vm took {}", start.elapsed().as_secs_f64());
-                        return;
+                    if self.frame_count == self.entry {
+                        // a callback gives what it returned to the native that called it
+                        if self.frame_count > 0 {
+                            self.stack.truncate(self.callframes[self.frame_count].slots);
+                            self.stack.push(returning);
+                        }
+                        return Ok(());
                     }
 
                     current_frame = &self.callframes[self.frame_count - 1];
@@ -352,15 +959,81 @@ impl VirtualMachine {
                     self.stack.push(returning);
                 }
                 // room for improvement
-                OpCode::Call(arg_count) => {
+                call @ (OpCode::Call(_) | OpCode::CallNamed(..)) => {
+                    let arg_count = match call {
+                        OpCode::CallNamed(positional, names) => {
+                            let Value::Array(names) = &chunk.constants[names as usize] else {
+                                unreachable!("the names are always an array of strings")
+                            };
+                            let names: Vec<InternedString> = names
+                                .borrow()
+                                .iter()
+                                .map(|name| *name.as_string())
+                                .collect();
+                            match self.name_arguments(positional as usize, &names) {
+                                Ok(arg_count) => arg_count,
+                                Err(error) => fail!(error.at(span!(), function.file.clone())),
+                            }
+                        }
+                        OpCode::Call(arg_count) => arg_count,
+                        _ => unreachable!(),
+                    };
                     let tmp = self.stack.len() - (1 + arg_count);
                     let callee = std::mem::take(&mut self.stack[tmp]);
 
-                    let Value::Closure(callee) = callee else {
-                        panic!()
+                    let called = match callee {
+                        Value::Closure(callee) => self.call(Box::into_raw(callee), arg_count),
+                        // the instance goes in front of the arguments, where `this` is
+                        Value::BoundMethod(bound) => {
+                            let receiver = Value::Instance(bound.receiver.clone());
+                            self.stack.insert(tmp + 1, receiver);
+                            let method = Box::new(bound.method.clone());
+                            self.call(Box::into_raw(method), arg_count + 1)
+                        }
+                        Value::Class(class) => {
+                            let instance = Value::Instance(rcrf(Instance::new(class.clone())));
+                            let initializer = Self::cached_method(chunk, ip - 1, &class, self.init);
+                            let Some(initializer) = initializer else {
+                                if arg_count > 0 {
+                                    runtime_error!(
+                                        ErrorCode::ArityMismatch,
+                                        "'{}' takes 0 arguments but {arg_count} were given",
+                                        class.borrow().name
+                                    )
+                                }
+                                // without an `init` there is nothing to call
+                                self.stack[tmp] = instance;
+                                continue;
+                            };
+                            self.stack.insert(tmp + 1, instance);
+                            self.call(Box::into_raw(Box::new(initializer)), arg_count + 1)
+                        }
+                        Value::VariantConstructor(kind, index) => {
+                            let arity = kind.variants[index].fields.len();
+                            if arg_count != arity {
+                                runtime_error!(
+                                    ErrorCode::ArityMismatch,
+                                    "'{}' takes {arity} arguments but {arg_count} were given",
+                                    variant_name(&kind, index)
+                                )
+                            }
+                            let values = self.stack.drain(tmp + 1..).collect();
+                            self.stack[tmp] = Value::Variant(Rc::new(Variant {
+                                kind,
+                                index,
+                                values,
+                            }));
+                            continue;
+                        }
+                        callee => runtime_error!(
+                            ErrorCode::NotCallable,
+                            "{} is not callable",
+                            callee.type_name()
+                        ),
                     };
-
-                    self.call(Box::into_raw(callee), arg_count);
+                    if let Err(error) = called {
+                        fail!(error.at(span!(), function.file.clone()))
+                    }
                     self.callframes[self.frame_count - 2].ip = ip;
 
                     // prepares for the next callframe
@@ -387,6 +1060,21 @@ impl VirtualMachine {
                 }
             }
         }
+        Ok(())
+    }
+
+    /// the script's function is always at the bottom of the callframes
+    fn global_name(&self, slot: u16) -> &str {
+        #[allow(unsafe_code)]
+        let script = unsafe { &(*self.callframes[0].closure).func };
+        &script.global_names[slot as usize]
+    }
+
+    fn set_local(&mut self, slot: usize, value: Value) {
+        match &self.stack[slot] {
+            Value::UpvalueLocation(location) => *location.borrow_mut() = value,
+            _ => self.stack[slot] = value,
+        }
     }
 
     fn capture_upvalue(&mut self, index: usize, closure: &mut Closure, callframe: &CallFrame) {
@@ -1,8 +1,15 @@
+use std::cmp::Ordering;
+
 use macros::native_macro;
 
-use crate::common::{
-    natives::Native,
-    value::{AsValue, Value},
+use crate::{
+    backend::error::RuntimeError,
+    cli_helper::ErrorCode,
+    common::{
+        enums::{err, ok},
+        natives::Native,
+        value::{rcrf, AsValue, Value},
+    },
 };
 
 use super::VirtualMachine;
@@ -10,16 +17,265 @@ native_macro! {
     debug_stack => Native(debug_stack),
     assert_stack => Native(assert_stack),
     to_str => Native(to_str),
+    read_file => Native(read_file),
+    map => Native(map),
+    filter => Native(filter),
+    reduce => Native(reduce),
+    sort => Native(sort),
+    find => Native(find),
+    any => Native(any),
+    all => Native(all),
+    zip => Native(zip),
+}
+
+/// the natives a script can call by name
+pub fn native_index(name: &str) -> Option<usize> {
+    match name {
+        "to_str" => Some(MACROS::idx_to_str!()),
+        "read_file" => Some(MACROS::idx_read_file!()),
+        "map" => Some(MACROS::idx_map!()),
+        "filter" => Some(MACROS::idx_filter!()),
+        "reduce" => Some(MACROS::idx_reduce!()),
+        "sort" => Some(MACROS::idx_sort!()),
+        "find" => Some(MACROS::idx_find!()),
+        "any" => Some(MACROS::idx_any!()),
+        "all" => Some(MACROS::idx_all!()),
+        "zip" => Some(MACROS::idx_zip!()),
+        _ => None,
+    }
 }
 
-pub fn to_str(vm: &mut VirtualMachine, mut args: Vec<Value>) {
-    let arg = args.pop().unwrap();
+/// the arguments of a native that takes exactly `N`
+fn arguments<const N: usize>(name: &str, args: Vec<Value>) -> Result<[Value; N], RuntimeError> {
+    let given = args.len();
+    args.try_into().map_err(|_| {
+        RuntimeError::new(
+            ErrorCode::ArityMismatch,
+            format!("'{name}' takes {N} arguments but {given} were given"),
+        )
+    })
+}
+
+/// a copy of the elements of an array argument, so callbacks are free to change the array
+fn elements(name: &str, array: &Value) -> Result<Vec<Value>, RuntimeError> {
+    match array {
+        Value::Array(array) => Ok(array.borrow().clone()),
+        other => Err(RuntimeError::new(
+            ErrorCode::TypeMismatch,
+            format!("'{name}' takes an array, not {}", other.type_name()),
+        )),
+    }
+}
+
+/// calls a predicate, which has to answer with a bool
+fn test(
+    vm: &mut VirtualMachine,
+    name: &str,
+    predicate: &Value,
+    element: Value,
+) -> Result<bool, RuntimeError> {
+    match vm.call_value(predicate.clone(), vec![element])? {
+        Value::Boolean(bool) => Ok(bool),
+        other => Err(RuntimeError::new(
+            ErrorCode::TypeMismatch,
+            format!(
+                "the function given to '{name}' has to return a bool, not {}",
+                other.type_name()
+            ),
+        )),
+    }
+}
+
+/// a new array with the function applied to every element
+pub fn map(vm: &mut VirtualMachine, args: Vec<Value>) -> Result<(), RuntimeError> {
+    let [array, function] = arguments("map", args)?;
+    let mut mapped = Vec::new();
+    for element in elements("map", &array)? {
+        mapped.push(vm.call_value(function.clone(), vec![element])?);
+    }
+    vm.stack.push(Value::Array(rcrf(mapped)));
+    Ok(())
+}
+/// a new array with the elements the predicate is true for
+pub fn filter(vm: &mut VirtualMachine, args: Vec<Value>) -> Result<(), RuntimeError> {
+    let [array, predicate] = arguments("filter", args)?;
+    let mut kept = Vec::new();
+    for element in elements("filter", &array)? {
+        if test(vm, "filter", &predicate, element.clone())? {
+            kept.push(element);
+        }
+    }
+    vm.stack.push(Value::Array(rcrf(kept)));
+    Ok(())
+}
+/// folds the elements into one value, starting from `initial`
+pub fn reduce(vm: &mut VirtualMachine, args: Vec<Value>) -> Result<(), RuntimeError> {
+    let [array, function, initial] = arguments("reduce", args)?;
+    let mut accumulator = initial;
+    for element in elements("reduce", &array)? {
+        accumulator = vm.call_value(function.clone(), vec![accumulator, element])?;
+    }
+    vm.stack.push(accumulator);
+    Ok(())
+}
+/// a sorted copy of an array of numbers or strings, or of anything with a comparator
+/// that returns a negative number, zero or a positive number like `|a, b| a - b`
+pub fn sort(vm: &mut VirtualMachine, args: Vec<Value>) -> Result<(), RuntimeError> {
+    let (array, comparator) = match args.len() {
+        1 => (arguments::<1>("sort", args)?[0].clone(), None),
+        _ => {
+            let [array, comparator] = arguments("sort", args)
+                .map_err(|error| error.with_note("the comparator can be left out"))?;
+            (array, Some(comparator))
+        }
+    };
+    let elements = elements("sort", &array)?;
+    let sorted = merge_sort(elements, &mut |lhs, rhs| match &comparator {
+        Some(comparator) => {
+            match vm.call_value(comparator.clone(), vec![lhs.clone(), rhs.clone()])? {
+                Value::Number(order) => Ok(order.partial_cmp(&0.0).unwrap_or(Ordering::Equal)),
+                other => Err(RuntimeError::new(
+                    ErrorCode::TypeMismatch,
+                    format!(
+                        "the comparator given to 'sort' has to return a number, not {}",
+                        other.type_name()
+                    ),
+                )),
+            }
+        }
+        None => compare(lhs, rhs),
+    })?;
+    vm.stack.push(Value::Array(rcrf(sorted)));
+    Ok(())
+}
+/// how two elements are ordered without a comparator
+fn compare(lhs: &Value, rhs: &Value) -> Result<Ordering, RuntimeError> {
+    match (lhs, rhs) {
+        (Value::Number(lhs), Value::Number(rhs)) => {
+            Ok(lhs.partial_cmp(rhs).unwrap_or(Ordering::Equal))
+        }
+        (Value::String(_), Value::String(_)) => Ok(lhs.to_string().cmp(&rhs.to_string())),
+        _ => Err(RuntimeError::new(
+            ErrorCode::TypeMismatch,
+            format!("cannot compare {} and {}", lhs.type_name(), rhs.type_name()),
+        )
+        .with_note("only numbers and strings can be sorted without a comparator")),
+    }
+}
+/// a stable sort that gives up on the first comparison that fails
+fn merge_sort(
+    mut values: Vec<Value>,
+    compare: &mut impl FnMut(&Value, &Value) -> Result<Ordering, RuntimeError>,
+) -> Result<Vec<Value>, RuntimeError> {
+    if values.len() < 2 {
+        return Ok(values);
+    }
+    let right = values.split_off(values.len() / 2);
+    let left = merge_sort(values, compare)?;
+    let right = merge_sort(right, compare)?;
+    let mut merged = Vec::with_capacity(left.len() + right.len());
+    let (mut left, mut right) = (left.into_iter().peekable(), right.into_iter().peekable());
+    while let (Some(lhs), Some(rhs)) = (left.peek(), right.peek()) {
+        let next = match compare(lhs, rhs)? {
+            Ordering::Greater => right.next(),
+            _ => left.next(),
+        };
+        merged.extend(next);
+    }
+    merged.extend(left);
+    merged.extend(right);
+    Ok(merged)
+}
+/// `Ok` with the first element the predicate is true for, or `Err` if there's none
+pub fn find(vm: &mut VirtualMachine, args: Vec<Value>) -> Result<(), RuntimeError> {
+    let [array, predicate] = arguments("find", args)?;
+    for element in elements("find", &array)? {
+        if test(vm, "find", &predicate, element.clone())? {
+            vm.stack.push(ok(element));
+            return Ok(());
+        }
+    }
+    vm.stack.push(err("no element was found".to_value()));
+    Ok(())
+}
+/// whether the predicate is true for any element, stopping at the first one
+pub fn any(vm: &mut VirtualMachine, args: Vec<Value>) -> Result<(), RuntimeError> {
+    let [array, predicate] = arguments("any", args)?;
+    let mut found = false;
+    for element in elements("any", &array)? {
+        if test(vm, "any", &predicate, element)? {
+            found = true;
+            break;
+        }
+    }
+    vm.stack.push(found.to_value());
+    Ok(())
+}
+/// whether the predicate is true for every element, stopping at the first it isn't
+pub fn all(vm: &mut VirtualMachine, args: Vec<Value>) -> Result<(), RuntimeError> {
+    let [array, predicate] = arguments("all", args)?;
+    let mut every = true;
+    for element in elements("all", &array)? {
+        if !test(vm, "all", &predicate, element)? {
+            every = false;
+            break;
+        }
+    }
+    vm.stack.push(every.to_value());
+    Ok(())
+}
+/// pairs up the elements of two arrays, as long as the shorter one
+pub fn zip(vm: &mut VirtualMachine, args: Vec<Value>) -> Result<(), RuntimeError> {
+    let [lhs, rhs] = arguments("zip", args)?;
+    let pairs = elements("zip", &lhs)?
+        .into_iter()
+        .zip(elements("zip", &rhs)?)
+        .map(|(lhs, rhs)| Value::Array(rcrf(vec![lhs, rhs])))
+        .collect();
+    vm.stack.push(Value::Array(rcrf(pairs)));
+    Ok(())
+}
+
+pub fn to_str(vm: &mut VirtualMachine, args: Vec<Value>) -> Result<(), RuntimeError> {
+    let [arg] = args.as_slice() else {
+        return Err(RuntimeError::new(
+            ErrorCode::ArityMismatch,
+            format!("'to_str' takes 1 arguments but {} were given", args.len()),
+        ));
+    };
     vm.stack.push(arg.to_string().to_value());
+    Ok(())
+}
+/// `Ok` with the contents of the file, or `Err` with why it couldn't be read
+pub fn read_file(vm: &mut VirtualMachine, args: Vec<Value>) -> Result<(), RuntimeError> {
+    let [path] = args.as_slice() else {
+        return Err(RuntimeError::new(
+            ErrorCode::ArityMismatch,
+            format!(
+                "'read_file' takes 1 arguments but {} were given",
+                args.len()
+            ),
+        ));
+    };
+    let Value::String(_) = path else {
+        return Err(RuntimeError::new(
+            ErrorCode::TypeMismatch,
+            format!("'read_file' takes a string, not {}", path.type_name()),
+        ));
+    };
+    let path = path.to_string();
+    vm.stack.push(match std::fs::read_to_string(&path) {
+        Ok(contents) => ok(contents.as_str().to_value()),
+        Err(error) => err(format!("cannot read '{path}': {error}").as_str().to_value()),
+    });
+    Ok(())
 }
-pub fn debug_stack(vm: &mut VirtualMachine, _: Vec<Value>) {
+pub fn debug_stack(vm: &mut VirtualMachine, _: Vec<Value>) -> Result<(), RuntimeError> {
     println!("Stack: {:?}", vm.stack);
+    Ok(())
 }
-pub fn assert_stack(vm: &mut VirtualMachine, args: Vec<Value>) {
+pub fn assert_stack(vm: &mut VirtualMachine, args: Vec<Value>) -> Result<(), RuntimeError> {
     println!("stack comparison: {:?} == {:?}", args, &vm.stack);
     assert_eq!(args, vm.stack);
+    Ok(())
 }
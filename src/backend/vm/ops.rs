@@ -1,6 +1,17 @@
+use std::rc::Rc;
+
 use crate::{
-    backend::callframe::CallFrame,
-    common::{chunk::Chunk, closure::Closure, function::Function, opcode::OpCode, value::Value},
+    backend::{callframe::CallFrame, error::RuntimeError},
+    cli_helper::ErrorCode,
+    common::{
+        chunk::Chunk,
+        class::{Class, MethodCache},
+        closure::Closure,
+        function::Function,
+        interner::InternedString,
+        opcode::OpCode,
+        value::{Ptr, Value},
+    },
 };
 
 use super::VirtualMachine;
@@ -15,7 +26,9 @@ impl VirtualMachine {
             panic!("{function:?}")
         };
         let mut closure: Closure = function.into();
-        for x in 0..function.upvalue_count {
+        #[allow(unsafe_code)]
+        let enclosing = unsafe { &*current_callframe.closure };
+        for _ in 0..function.upvalue_count {
             let OpCode::Byte(is_local) = &chunk.code[ip] else {
                 panic!()
             };
@@ -31,10 +44,123 @@ impl VirtualMachine {
             if is_local {
                 self.capture_upvalue(index.into(), &mut closure, current_callframe)
             } else {
-                closure.upvalues[x] = closure.upvalues[index as usize].clone()
+                closure
+                    .upvalues
+                    .push(enclosing.upvalues[index as usize].clone())
             }
         }
 
         self.stack.push(Value::Closure(Box::new(closure)))
     }
+
+    /// moves the named arguments on top of the stack to where the callee's parameters are,
+    /// giving back how many arguments there are then
+    pub fn name_arguments(
+        &mut self,
+        positional: usize,
+        names: &[InternedString],
+    ) -> Result<usize, RuntimeError> {
+        let callee = &self.stack[self.stack.len() - (1 + positional + names.len())];
+        let function = match callee {
+            Value::Closure(closure) => Some(closure.func.clone()),
+            Value::BoundMethod(bound) => Some(bound.method.func.clone()),
+            Value::Class(class) => class
+                .borrow()
+                .find_method(self.init)
+                .map(|init| init.func.clone()),
+            _ => None,
+        };
+        let Some(function) = function else {
+            return Err(RuntimeError::new(
+                ErrorCode::InvalidArgument,
+                format!("{} doesn't take named arguments", callee.type_name()),
+            ));
+        };
+        let named = self.stack.split_off(self.stack.len() - names.len());
+        let parameters = &function.parameters;
+        let mut arguments: Vec<Option<Value>> =
+            vec![None; parameters.len().saturating_sub(positional)];
+        for (name, value) in names.iter().zip(named) {
+            let Some(index) = parameters.iter().position(|parameter| parameter == name) else {
+                return Err(RuntimeError::new(
+                    ErrorCode::InvalidArgument,
+                    format!(
+                        "'{}' has no parameter named '{}'",
+                        function.name,
+                        String::from(*name)
+                    ),
+                ));
+            };
+            if index < positional || arguments[index - positional].is_some() {
+                return Err(RuntimeError::new(
+                    ErrorCode::InvalidArgument,
+                    format!("'{}' is given more than once", String::from(*name)),
+                ));
+            }
+            arguments[index - positional] = Some(value);
+        }
+        let required = (function.arity - function.optional) as usize;
+        if let Some(missing) =
+            (positional..required).find(|&index| arguments[index - positional].is_none())
+        {
+            return Err(RuntimeError::new(
+                ErrorCode::ArityMismatch,
+                format!(
+                    "'{}' is missing the argument '{}'",
+                    function.name,
+                    String::from(parameters[missing])
+                ),
+            ));
+        }
+        // parameters left out before a named one get their defaults like trailing ones do
+        let given = arguments
+            .iter()
+            .rposition(Option::is_some)
+            .map_or(0, |last| last + 1);
+        self.stack.extend(
+            arguments
+                .into_iter()
+                .take(given)
+                .map(Option::unwrap_or_default),
+        );
+        Ok(positional + given)
+    }
+
+    /// finds a method for the instruction at `ip`, which only searches the superclass
+    /// chain again when it's used with a different class than last time
+    pub fn cached_method(
+        chunk: &Chunk,
+        ip: usize,
+        class: &Ptr<Class>,
+        name: InternedString,
+    ) -> Option<Closure> {
+        let mut caches = chunk.method_caches.borrow_mut();
+        if let Some(Some(cache)) = caches.get(ip) {
+            // the weak pointer keeps the allocation, so no other class can have its address
+            if cache.class.as_ptr() == Rc::as_ptr(class) {
+                if let Some(owner) = cache.owner.upgrade() {
+                    return owner.borrow().methods.get(&name).cloned();
+                }
+            }
+        }
+        let mut owner = class.clone();
+        let method = loop {
+            let superclass = {
+                let borrowed = owner.borrow();
+                if let Some(method) = borrowed.methods.get(&name) {
+                    break method.clone();
+                }
+                borrowed.superclass.clone()?
+            };
+            owner = superclass;
+        };
+        if caches.len() < chunk.code.len() {
+            caches.resize(chunk.code.len(), None);
+        }
+        caches[ip] = Some(MethodCache {
+            class: Rc::downgrade(class),
+            owner: Rc::downgrade(&owner),
+        });
+        Some(method)
+    }
 }
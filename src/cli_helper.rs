@@ -1,48 +1,373 @@
-use std::path::Path;
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
 
-use colored::Colorize;
+use colored::{ColoredString, Colorize};
+use serde_json::{json, Value as Json};
 
-use crate::frontend::scanner::Position;
+use crate::frontend::scanner::Span;
 
 #[derive(Debug, Clone)]
 pub struct Diagnostics<'a> {
     pub file_path: &'a Path,
+    /// the source the spans of every diagnostic point into
+    pub source: String,
+    /// everything reported so far, in the order it was reported
+    pub entries: Vec<Diagnostic>,
+    /// set while an imported module is compiled, everything reported meanwhile is in it
+    pub current_file: Option<Rc<SourceFile>>,
 }
 impl Default for Diagnostics<'_> {
     fn default() -> Self {
         Self {
             file_path: Path::new(""),
+            source: String::new(),
+            entries: Vec::new(),
+            current_file: None,
         }
     }
 }
+
+/// a file other than the one being compiled, i.e. an imported module
+#[derive(Debug, PartialEq)]
+pub struct SourceFile {
+    pub path: PathBuf,
+    pub source: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+impl Severity {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+        }
+    }
+    fn paint(&self, text: &str) -> ColoredString {
+        match self {
+            Severity::Error => text.red().bold(),
+            Severity::Warning => text.yellow().bold(),
+            Severity::Note => text.cyan().bold(),
+        }
+    }
+}
+
+/// stable identifiers for every kind of diagnostic, so they can be searched for and
+/// matched on by tools without depending on the wording of the message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    // parsing
+    ExpectedToken,
+    ExpectedExpression,
+    UnknownAttribute,
+    ReturnOutsideFunction,
+    InvalidToken,
+    InvalidAssignmentTarget,
+    UseOutsideTopLevel,
+    ThisOutsideClass,
+    InheritsFromItself,
+    SuperOutsideSubclass,
+    DuplicateField,
+    UnknownType,
+    InvalidParameter,
+    // compiling
+    UndefinedVariable,
+    TooManyConstants,
+    ModuleNotFound,
+    CyclicImport,
+    UnknownImport,
+    ReturnFromInitializer,
+    MismatchedTypes,
+    UnknownArgument,
+    MissingArgument,
+    ModuleNameCollision,
+    TooManyGlobals,
+    // running
+    TypeMismatch,
+    NotCallable,
+    ArityMismatch,
+    AssertionFailed,
+    StackOverflow,
+    UndefinedProperty,
+    MissingField,
+    NoMatchingArm,
+    UncaughtException,
+    InvalidArgument,
+    // linting
+    UnusedVariable,
+    ShadowedVariable,
+    UnreachableCode,
+    BoolComparison,
+    UndeclaredAssignment,
+    UnknownLint,
+    NonExhaustiveMatch,
+}
+impl ErrorCode {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ErrorCode::ExpectedToken => "E0001",
+            ErrorCode::ExpectedExpression => "E0002",
+            ErrorCode::UnknownAttribute => "E0003",
+            ErrorCode::ReturnOutsideFunction => "E0004",
+            ErrorCode::InvalidToken => "E0005",
+            ErrorCode::InvalidAssignmentTarget => "E0006",
+            ErrorCode::UseOutsideTopLevel => "E0007",
+            ErrorCode::ThisOutsideClass => "E0008",
+            ErrorCode::InheritsFromItself => "E0009",
+            ErrorCode::SuperOutsideSubclass => "E0010",
+            ErrorCode::DuplicateField => "E0011",
+            ErrorCode::UnknownType => "E0012",
+            ErrorCode::InvalidParameter => "E0013",
+            ErrorCode::UndefinedVariable => "E0100",
+            ErrorCode::TooManyConstants => "E0101",
+            ErrorCode::ModuleNotFound => "E0102",
+            ErrorCode::CyclicImport => "E0103",
+            ErrorCode::UnknownImport => "E0104",
+            ErrorCode::ReturnFromInitializer => "E0105",
+            ErrorCode::MismatchedTypes => "E0106",
+            ErrorCode::UnknownArgument => "E0107",
+            ErrorCode::MissingArgument => "E0108",
+            ErrorCode::ModuleNameCollision => "E0109",
+            ErrorCode::TooManyGlobals => "E0110",
+            ErrorCode::TypeMismatch => "E0200",
+            ErrorCode::NotCallable => "E0201",
+            ErrorCode::ArityMismatch => "E0202",
+            ErrorCode::AssertionFailed => "E0203",
+            ErrorCode::StackOverflow => "E0204",
+            ErrorCode::UndefinedProperty => "E0205",
+            ErrorCode::MissingField => "E0206",
+            ErrorCode::NoMatchingArm => "E0207",
+            ErrorCode::UncaughtException => "E0208",
+            ErrorCode::InvalidArgument => "E0209",
+            ErrorCode::UnusedVariable => "W0001",
+            ErrorCode::ShadowedVariable => "W0002",
+            ErrorCode::UnreachableCode => "W0003",
+            ErrorCode::BoolComparison => "W0004",
+            ErrorCode::UndeclaredAssignment => "W0005",
+            ErrorCode::UnknownLint => "W0006",
+            ErrorCode::NonExhaustiveMatch => "W0007",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+    /// the primary label is underlined with `^`, every other one with `-`
+    pub primary: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: ErrorCode,
+    pub message: String,
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+    pub help: Vec<String>,
+    /// the module the spans point into, `None` for the file being compiled
+    pub file: Option<Rc<SourceFile>>,
+}
+impl Diagnostic {
+    pub fn new(severity: Severity, code: ErrorCode, message: impl Into<String>) -> Diagnostic {
+        Diagnostic {
+            severity,
+            code,
+            message: message.into(),
+            labels: Vec::new(),
+            notes: Vec::new(),
+            help: Vec::new(),
+            file: None,
+        }
+    }
+    pub fn error(code: ErrorCode, message: impl Into<String>) -> Diagnostic {
+        Diagnostic::new(Severity::Error, code, message)
+    }
+    pub fn warning(code: ErrorCode, message: impl Into<String>) -> Diagnostic {
+        Diagnostic::new(Severity::Warning, code, message)
+    }
+    pub fn with_label(mut self, span: Span, message: impl Into<String>) -> Diagnostic {
+        self.labels.push(Label {
+            span,
+            message: message.into(),
+            primary: true,
+        });
+        self
+    }
+    pub fn with_secondary_label(mut self, span: Span, message: impl Into<String>) -> Diagnostic {
+        self.labels.push(Label {
+            span,
+            message: message.into(),
+            primary: false,
+        });
+        self
+    }
+    pub fn with_note(mut self, note: impl Into<String>) -> Diagnostic {
+        self.notes.push(note.into());
+        self
+    }
+    pub fn with_help(mut self, help: impl Into<String>) -> Diagnostic {
+        self.help.push(help.into());
+        self
+    }
+    pub fn in_file(mut self, file: Option<Rc<SourceFile>>) -> Diagnostic {
+        self.file = file;
+        self
+    }
+    pub fn primary_span(&self) -> Option<&Span> {
+        self.labels
+            .iter()
+            .find(|label| label.primary)
+            .map(|label| &label.span)
+    }
+}
+
 impl<'a> Diagnostics<'a> {
     pub fn new(path: &'a Path) -> Self {
-        Diagnostics { file_path: path }
+        Diagnostics {
+            file_path: path,
+            source: String::new(),
+            entries: Vec::new(),
+            current_file: None,
+        }
     }
 
     pub fn file_path(&self) -> &str {
         self.file_path.to_str().unwrap()
     }
-    pub fn log(&mut self, position: Option<&Position>, title: &str, msg: String) {
-        let mut location: String = String::new();
-        if let Some(position) = position {
-            location = format!("{}:{}", position.line + 1, position.start_in_line + 1);
+    /// the path and source of the file a diagnostic's spans point into
+    fn file_of<'d>(&'d self, diagnostic: &'d Diagnostic) -> (&'d str, &'d str) {
+        match &diagnostic.file {
+            Some(file) => (file.path.to_str().unwrap(), &file.source),
+            None => (self.file_path(), &self.source),
         }
-        print!(
-            "[ {} ] - {title} \n\t{} - {}",
-            "rottenmangos".bold(),
-            format!("{}:{location}", self.file_path()).bold().yellow(),
-            msg
-        )
     }
-    pub fn log_wall(&mut self, title: &str, msg: &[String]) {
-        println!(
-            "[ {} ] - {title}\n\t{}",
-            "rottenmangos".bold().black(),
-            format!("{}", self.file_path().bold()).yellow()
-        );
-        msg.iter().for_each(|line| {
-            println!("\t\t{line}");
-        });
+    /// records a diagnostic, it's up to whoever is driving the compiler to print it
+    pub fn push(&mut self, mut diagnostic: Diagnostic) {
+        if diagnostic.file.is_none() {
+            diagnostic.file = self.current_file.clone();
+        }
+        self.entries.push(diagnostic);
+    }
+    pub fn has_errors(&self) -> bool {
+        self.entries
+            .iter()
+            .any(|diagnostic| diagnostic.severity == Severity::Error)
+    }
+    /// hands over everything reported so far
+    pub fn take(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.entries)
+    }
+    pub fn emit(&self, diagnostic: &Diagnostic) {
+        eprint!("{}", self.render(diagnostic));
+    }
+    /// the machine readable form of a diagnostic, used by `check --message-format=json`
+    pub fn to_json(&self, diagnostic: &Diagnostic) -> Json {
+        let span = |span: &Span| {
+            let start = span.position.start_in_source;
+            json!({
+                "byte_start": start,
+                "byte_end": start + span.length,
+                "line": span.position.line + 1,
+                "column": span.position.start_in_line + 1,
+            })
+        };
+        json!({
+            "file": self.file_of(diagnostic).0,
+            "severity": diagnostic.severity.name(),
+            "code": diagnostic.code.code(),
+            "message": diagnostic.message,
+            "span": diagnostic.primary_span().map(span),
+            "labels": diagnostic.labels.iter().map(|label| json!({
+                "span": span(&label.span),
+                "message": label.message,
+                "primary": label.primary,
+            })).collect::<Vec<_>>(),
+            "notes": diagnostic.notes,
+            "help": diagnostic.help,
+        })
+    }
+    /// renders a diagnostic the way rustc does, with the offending lines of source
+    /// underneath the message and every label underlining the part it's about
+    pub fn render(&self, diagnostic: &Diagnostic) -> String {
+        let severity = diagnostic.severity;
+        let mut out = String::new();
+        writeln!(
+            out,
+            "{}{} {}",
+            severity.paint(&format!("{}[{}]", severity.name(), diagnostic.code.code())),
+            ":".bold(),
+            diagnostic.message.bold()
+        )
+        .unwrap();
+
+        let mut labels: Vec<&Label> = diagnostic.labels.iter().collect();
+        labels.sort_by_key(|label| (label.span.position.line, !label.primary));
+        let gutter = labels
+            .iter()
+            .map(|label| (label.span.position.line + 1).to_string().len())
+            .max()
+            .unwrap_or(0);
+        let bar = format!("{} |", " ".repeat(gutter)).blue().bold();
+
+        let (path, source) = self.file_of(diagnostic);
+        let location = match diagnostic.primary_span() {
+            Some(span) => format!(
+                "{path}:{}:{}",
+                span.position.line + 1,
+                span.position.start_in_line + 1
+            ),
+            None => path.to_string(),
+        };
+        writeln!(
+            out,
+            "{}{} {location}",
+            " ".repeat(gutter),
+            "-->".blue().bold()
+        )
+        .unwrap();
+
+        if !labels.is_empty() {
+            writeln!(out, "{bar}").unwrap();
+        }
+        let mut previous_line = None;
+        for label in labels {
+            let line = label.span.position.line;
+            if previous_line != Some(line) {
+                let text = source.lines().nth(line).unwrap_or("");
+                let number = format!("{:>gutter$} |", line + 1).blue().bold();
+                writeln!(out, "{number} {text}").unwrap();
+                previous_line = Some(line);
+            }
+            let column = label.span.position.start_in_line;
+            let underline = if label.primary { "^" } else { "-" }.repeat(label.span.length.max(1));
+            let underline = if label.primary {
+                severity.paint(&format!("{underline} {}", label.message))
+            } else {
+                format!("{underline} {}", label.message).blue().bold()
+            };
+            writeln!(out, "{bar} {}{underline}", " ".repeat(column)).unwrap();
+        }
+        let has_footer = !diagnostic.notes.is_empty() || !diagnostic.help.is_empty();
+        if !diagnostic.labels.is_empty() && has_footer {
+            writeln!(out, "{bar}").unwrap();
+        }
+        for note in &diagnostic.notes {
+            writeln!(out, "{} {} {note}", " ".repeat(gutter), "= note:".bold()).unwrap();
+        }
+        for help in &diagnostic.help {
+            writeln!(out, "{} {} {help}", " ".repeat(gutter), "= help:".bold()).unwrap();
+        }
+        out.push('\n');
+        out
     }
 }
@@ -1,8 +1,52 @@
-use super::{opcode::OpCode, value::Value};
+use std::{cell::RefCell, collections::HashMap};
+
+use crate::frontend::scanner::{Span, Token};
+
+use super::{
+    class::MethodCache,
+    opcode::{ConstantLocation, OpCode},
+    value::Value,
+};
+
+/// the most constants a single chunk can address with a `ConstantLocation`
+pub const MAX_CONSTANTS: usize = ConstantLocation::MAX as usize + 1;
+
 #[derive(Debug, Clone, Default)]
 pub struct Chunk {
     pub code: Vec<OpCode>,
     pub constants: Vec<Value>,
+    /// where each interned constant already lives in `constants`
+    pub interned: HashMap<ConstantKey, ConstantLocation>,
+    /// the source each instruction in `code` was compiled from
+    pub spans: Vec<Span>,
+    /// the span newly emitted instructions are attributed to
+    pub current_span: Span,
+    /// indexed like `code`, filled in by the vm as instructions look methods up, so a
+    /// method inherited from far up the superclass chain is only searched for once
+    pub method_caches: RefCell<Vec<Option<MethodCache>>>,
+    /// set by the compiler once a constant didn't fit in the pool
+    pub overflowed: bool,
+}
+
+/// the identity of a constant, values with the same key share a slot in the pool
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConstantKey {
+    Number(u64),
+    Boolean(bool),
+    String(usize),
+    Void,
+}
+impl ConstantKey {
+    /// functions (and anything else with identity of its own) are never shared
+    pub fn of(value: &Value) -> Option<ConstantKey> {
+        match value {
+            Value::Number(number) => Some(ConstantKey::Number(number.to_bits())),
+            Value::Boolean(bool) => Some(ConstantKey::Boolean(*bool)),
+            Value::String(string) => Some(ConstantKey::String(string.0)),
+            Value::Void => Some(ConstantKey::Void),
+            _ => None,
+        }
+    }
 }
 
 impl Chunk {
@@ -10,22 +54,46 @@ impl Chunk {
         Chunk {
             code: vec![],
             constants: vec![],
+            interned: HashMap::new(),
+            spans: vec![],
+            current_span: Span::default(),
+            method_caches: RefCell::new(Vec::new()),
+            overflowed: false,
         }
     }
     pub fn emit_op(&mut self, op: OpCode) {
-        self.code.push(op)
+        self.code.push(op);
+        self.spans.push(self.current_span.clone());
     }
-    pub fn emit_many(&mut self, mut ops: Vec<OpCode>) {
-        self.code.append(&mut ops);
+    pub fn emit_many(&mut self, ops: Vec<OpCode>) {
+        for op in ops {
+            self.emit_op(op)
+        }
     }
-    pub fn emit_constant(&mut self, value: Value) -> u16 {
-        let pos = self.emit_value(value);
+    /// attributes the instructions emitted after this to `token`
+    pub fn set_span(&mut self, token: &Token) {
+        // synthesized tokens (like the `void` of a bare `return`) don't point anywhere
+        if token.length > 0 {
+            self.current_span = Span::from(token);
+        }
+    }
+    pub fn emit_constant(&mut self, value: Value) -> Option<ConstantLocation> {
+        let pos = self.emit_value(value)?;
         self.emit_op(OpCode::Constant(pos));
-        pos
+        Some(pos)
     }
-    pub fn emit_value(&mut self, value: Value) -> u16 {
-        let pos = self.constants.len();
+    /// adds a value to the constant pool, reusing the slot of an identical constant,
+    /// `None` when the pool is already full
+    pub fn emit_value(&mut self, value: Value) -> Option<ConstantLocation> {
+        let key = ConstantKey::of(&value);
+        if let Some(pos) = key.and_then(|key| self.interned.get(&key)) {
+            return Some(*pos);
+        }
+        let pos = ConstantLocation::try_from(self.constants.len()).ok()?;
         self.constants.push(value);
-        pos as u16
+        if let Some(key) = key {
+            self.interned.insert(key, pos);
+        }
+        Some(pos)
     }
 }
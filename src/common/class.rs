@@ -0,0 +1,72 @@
+use std::{cell::RefCell, collections::HashMap, rc::Weak};
+
+use super::{
+    closure::Closure,
+    interner::InternedString,
+    value::{Ptr, Value},
+};
+
+#[derive(Debug, Clone)]
+pub struct Class {
+    pub name: String,
+    pub methods: HashMap<InternedString, Closure>,
+    pub superclass: Option<Ptr<Class>>,
+}
+impl Class {
+    pub fn new(name: String) -> Class {
+        Class {
+            name,
+            methods: HashMap::new(),
+            superclass: None,
+        }
+    }
+    /// looks for a method in the class, then in every class it inherits from
+    pub fn find_method(&self, name: InternedString) -> Option<Closure> {
+        if let Some(method) = self.methods.get(&name) {
+            return Some(method.clone());
+        }
+        self.superclass.as_ref()?.borrow().find_method(name)
+    }
+}
+
+/// the class an instruction looked a method up on last time, and the class the method was
+/// found in. both are weak, a chunk holding on to a class would hold on to the chunks of its
+/// methods, which is a cycle when one of them is the chunk itself
+#[derive(Debug, Clone)]
+pub struct MethodCache {
+    pub class: Weak<RefCell<Class>>,
+    /// `class` itself, or the superclass up its chain that declares the method
+    pub owner: Weak<RefCell<Class>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Instance {
+    pub class: Ptr<Class>,
+    /// fields are created the first time they're assigned, usually in `init`
+    pub fields: HashMap<InternedString, Value>,
+}
+impl Instance {
+    pub fn new(class: Ptr<Class>) -> Instance {
+        Instance {
+            class,
+            fields: HashMap::new(),
+        }
+    }
+    /// the fields sorted by name, so printing an instance is deterministic
+    pub fn sorted_fields(&self) -> Vec<(String, &Value)> {
+        let mut fields: Vec<(String, &Value)> = self
+            .fields
+            .iter()
+            .map(|(name, value)| ((*name).into(), value))
+            .collect();
+        fields.sort_by(|(lhs, _), (rhs, _)| lhs.cmp(rhs));
+        fields
+    }
+}
+
+/// a method read off an instance, which remembers the instance to call it with
+#[derive(Debug, Clone)]
+pub struct BoundMethod {
+    pub receiver: Ptr<Instance>,
+    pub method: Closure,
+}
@@ -37,15 +37,21 @@ pub fn diassasemble_instruction(
     instruction_ptr += 1;
 
     match instruction {
-        OpCode::DefineGlobal(pos)
-        | OpCode::Constant(pos)
-        | OpCode::SetGlobal(pos)
-        | OpCode::GetGlobal(pos)
-        | OpCode::DefineLocal(pos) => {
+        OpCode::Constant(pos)
+        | OpCode::AddConst(pos)
+        | OpCode::DefineLocal(pos)
+        | OpCode::Class(pos)
+        | OpCode::Method(pos)
+        | OpCode::GetProperty(pos)
+        | OpCode::SetProperty(pos)
+        | OpCode::GetSuper(pos) => {
             let constant = &chunk.constants[*pos as usize];
 
             println!("{instruction} <{constant:?}>")
         }
+        OpCode::DefineGlobal(slot) | OpCode::SetGlobal(slot) | OpCode::GetGlobal(slot) => {
+            println!("{instruction} <slot:{slot}>")
+        }
         OpCode::GetUpValue(pos) | OpCode::SetUpValue(pos) => {
             println!("{instruction} <{pos}>")
         }
@@ -59,7 +65,7 @@ pub fn diassasemble_instruction(
             println!("{instruction} <idx:{location}>")
         }
         OpCode::Closure(closure) => {
-            let constant = &chunk.constants[(*closure as usize)];
+            let constant = &chunk.constants[*closure as usize];
             let Value::Function(function) = constant else {
                 dbg!(&chunk.constants);
                 dbg!(closure);
@@ -76,9 +82,7 @@ pub fn diassasemble_instruction(
                 instruction_ptr += 1;
                 let index = chunk.code[instruction_ptr].clone();
                 instruction_ptr += 1;
-                let OpCode::Byte(index) = index else {
-                    panic!()
-                };
+                let OpCode::Byte(index) = index else { panic!() };
 
                 println!(
                     "{:0>4}\t|\t\t{} {}",
@@ -91,12 +95,26 @@ pub fn diassasemble_instruction(
         OpCode::JumpTo(offset)
         | OpCode::JumpToIfFalse(offset)
         | OpCode::PopJumpToIfFalse(offset)
+        | OpCode::PopJumpToIfTrue(offset)
+        | OpCode::Try(offset)
         | OpCode::Call(offset) => {
             println!("{instruction} {offset}")
         }
-        OpCode::GetLocal(pos) | OpCode::SetLocal(pos) => {
+        OpCode::GetLocal(pos) | OpCode::SetLocal(pos) | OpCode::SetLocalConsumes(pos) => {
             println!("{instruction} {pos}")
         }
+        OpCode::Construct(count) | OpCode::Array(count) | OpCode::GetIndex(count) => {
+            println!("{instruction} {count}")
+        }
+        OpCode::MatchArray(length, rest) => println!("{instruction} {length} rest:{rest}"),
+        OpCode::CheckType(kind) => println!("{instruction} {kind}"),
+        OpCode::JumpToIfGiven(slot, offset) => println!("{instruction} {slot} {offset}"),
+        OpCode::CallNamed(positional, names) => {
+            println!(
+                "{instruction} {positional} {:?}",
+                chunk.constants[*names as usize]
+            )
+        }
 
         _ => println!("{instruction}"),
     }
@@ -0,0 +1,102 @@
+use std::rc::Rc;
+
+use super::{interner::InternedString, value::Value};
+
+/// what an `enum` declaration creates
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnumType {
+    /// empty for the built-in `Ok` and `Err`, which are written without one
+    pub name: String,
+    pub variants: Vec<VariantType>,
+}
+impl EnumType {
+    pub fn variant_index(&self, name: InternedString) -> Option<usize> {
+        self.variants
+            .iter()
+            .position(|variant| variant.name == name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VariantType {
+    pub name: InternedString,
+    /// the names of the values the variant carries, only used for its arity
+    pub fields: Vec<InternedString>,
+}
+
+/// one of the variants of an enum, and the values it was made with
+#[derive(Debug, Clone)]
+pub struct Variant {
+    pub kind: Rc<EnumType>,
+    pub index: usize,
+    pub values: Vec<Value>,
+}
+impl Variant {
+    /// `Shape.Circle`, which is how the variant is written in scripts too
+    pub fn name(&self) -> String {
+        variant_name(&self.kind, self.index)
+    }
+}
+impl PartialEq for Variant {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.kind, &other.kind)
+            && self.index == other.index
+            && self.values == other.values
+    }
+}
+
+pub fn variant_name(kind: &EnumType, index: usize) -> String {
+    let name = String::from(kind.variants[index].name);
+    match kind.name.is_empty() {
+        true => name,
+        false => format!("{}.{name}", kind.name),
+    }
+}
+
+thread_local! {
+    static RESULT: Rc<EnumType> = Rc::new(EnumType {
+        name: String::new(),
+        variants: vec![
+            VariantType {
+                name: InternedString::from("Ok"),
+                fields: vec![InternedString::from("value")],
+            },
+            VariantType {
+                name: InternedString::from("Err"),
+                fields: vec![InternedString::from("error")],
+            },
+        ],
+    });
+}
+
+/// the enum of `Ok` and `Err`, which every script has without declaring it
+pub fn result_type() -> Rc<EnumType> {
+    RESULT.with(Rc::clone)
+}
+pub fn is_result(kind: &Rc<EnumType>) -> bool {
+    RESULT.with(|result| Rc::ptr_eq(result, kind))
+}
+/// which variant of the result a name is
+pub fn result_variant(name: &str) -> Option<usize> {
+    match name {
+        "Ok" => Some(0),
+        "Err" => Some(1),
+        _ => None,
+    }
+}
+/// `Ok(value)`, for natives that succeeded
+pub fn ok(value: Value) -> Value {
+    Value::Variant(Rc::new(Variant {
+        kind: result_type(),
+        index: 0,
+        values: vec![value],
+    }))
+}
+/// `Err(error)`, for natives that failed in a way the script should handle
+pub fn err(error: Value) -> Value {
+    Value::Variant(Rc::new(Variant {
+        kind: result_type(),
+        index: 1,
+        values: vec![error],
+    }))
+}
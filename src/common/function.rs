@@ -1,10 +1,27 @@
-use super::chunk::Chunk;
+use std::rc::Rc;
+
+use crate::cli_helper::SourceFile;
+
+use super::{chunk::Chunk, interner::InternedString};
 #[derive(Debug, Clone, Default)]
 pub struct Function {
     pub chunk: Chunk,
+    /// how many parameters there are, not counting the rest parameter
     pub arity: u8,
+    /// how many of the last parameters have defaults and can be left out
+    pub optional: u8,
+    /// whether the arguments after the parameters are collected into an array
+    pub variadic: bool,
+    /// the names of the parameters before the rest parameter, for calls that name arguments
+    pub parameters: Vec<InternedString>,
     pub name: String,
     pub upvalue_count: usize,
+    /// names of the global slots, only set on the script's function
+    pub global_names: Vec<String>,
+    /// the module the function was compiled from, `None` for the script being run
+    pub file: Option<Rc<SourceFile>>,
+    /// methods are passed the instance they're called on before their arguments, as `this`
+    pub method: bool,
 }
 
 impl Function {
@@ -12,8 +29,14 @@ impl Function {
         Function {
             chunk: Chunk::new(),
             arity: 0,
+            optional: 0,
+            variadic: false,
+            parameters: Vec::new(),
             name: String::from("main"),
             upvalue_count: 0,
+            global_names: Vec::new(),
+            file: None,
+            method: false,
         }
     }
 }
@@ -1,6 +1,6 @@
 use std::{collections::HashMap, sync::Mutex};
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct InternedString(pub usize);
 impl From<InternedString> for String {
     fn from(value: InternedString) -> Self {
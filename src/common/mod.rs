@@ -1,9 +1,13 @@
 pub mod chunk;
+pub mod class;
 pub mod closure;
 pub mod debug;
+pub mod enums;
 pub mod function;
 pub mod interner;
 pub mod linkedlist;
 pub mod natives;
 pub mod opcode;
+pub mod structs;
+pub mod types;
 pub mod value;
@@ -1,10 +1,11 @@
 use std::fmt::Debug;
 
-use crate::backend::vm::VirtualMachine;
+use crate::backend::{error::RuntimeError, vm::VirtualMachine};
 
 use super::value::Value;
 
-pub struct Native(pub fn(vm: &mut VirtualMachine, args: Vec<Value>));
+/// a function written in rust, which pushes its result or fails with a runtime error
+pub struct Native(pub fn(vm: &mut VirtualMachine, args: Vec<Value>) -> Result<(), RuntimeError>);
 impl Debug for Native {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "")
@@ -1,8 +1,9 @@
-use crate::frontend::bytecode::Bytecode;
+use crate::{common::types::Type, frontend::bytecode::Bytecode};
 use macros::ExpandOpCode;
 use strum::Display;
 
 pub type ConstantLocation = u16;
+pub type GlobalSlot = u16;
 pub type SlotIndex = u8;
 pub type Offset = usize;
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -31,14 +32,16 @@ pub enum OpCode {
     #[stack(pop = 1)]
     SetLocalConsumes(u16),
     #[stack(pop = 1)]
-    DefineGlobal(ConstantLocation),
+    DefineGlobal(GlobalSlot),
     #[stack(push = 1)]
-    GetGlobal(ConstantLocation),
+    GetGlobal(GlobalSlot),
     #[stack(pop = 1, push = 0)]
-    SetGlobal(ConstantLocation),
+    SetGlobal(GlobalSlot),
     Exit,
     #[stack(pop = 1)]
     PopJumpToIfFalse(Offset),
+    #[stack(pop = 1)]
+    PopJumpToIfTrue(Offset),
     JumpToIfFalse(Offset),
     JumpTo(Offset),
 
@@ -71,6 +74,15 @@ pub enum OpCode {
     Print,
     #[stack(pop = 2, push = 1)]
     Add,
+    /// adds a number from the constant pool to the top of the stack
+    #[stack(pop = 1, push = 1)]
+    AddConst(ConstantLocation),
+    /// an `Add` the type checker knows is between two numbers
+    #[stack(pop = 2, push = 1)]
+    AddNumbers,
+    /// an `Add` the type checker knows is between two strings
+    #[stack(pop = 2, push = 1)]
+    AddStrings,
     #[stack(pop = 2, push = 1)]
     Sub,
     #[stack(pop = 2, push = 1)]
@@ -88,5 +100,63 @@ pub enum OpCode {
     CallNative(u16),
     CallFnArgPtr(u8, u8),
     Byte(u8),
+    /// pops a local that a closure captured, the closure keeps the value alive
+    #[stack(pop = 1)]
     CloseUpvalue,
+    /// creates a class named by the string constant
+    #[stack(push = 1)]
+    Class(ConstantLocation),
+    /// adds the closure on top of the stack to the class below it
+    #[stack(pop = 1)]
+    Method(ConstantLocation),
+    #[stack(pop = 1, push = 1)]
+    GetProperty(ConstantLocation),
+    /// sets a field of the instance below the value, leaving the value
+    #[stack(pop = 2, push = 1)]
+    SetProperty(ConstantLocation),
+    /// makes the class on top of the stack inherit from the class below it
+    #[stack(pop = 1)]
+    Inherit,
+    /// looks a method up in the superclass on top of the stack, bound to the instance below
+    #[stack(pop = 2, push = 1)]
+    GetSuper(ConstantLocation),
+    /// builds a struct from the struct type and the name, value pairs above it
+    Construct(u8),
+    /// fails unless the value below the struct type on top of the stack is one of its structs
+    #[stack(pop = 1)]
+    CheckStruct,
+    #[stack(push = 1)]
+    Dup,
+    /// makes an array of the values on top of the stack
+    Array(u8),
+    /// reads an element of the array, or a value of the variant, on top of the stack
+    #[stack(pop = 1, push = 1)]
+    GetIndex(u8),
+    /// whether the value below the variant on top of the stack is that variant
+    #[stack(pop = 2, push = 1)]
+    MatchVariant,
+    /// whether the value on top of the stack is an array of that length, or at least that
+    /// long if the pattern ends with `..`
+    #[stack(pop = 1, push = 1)]
+    MatchArray(u8, bool),
+    /// fails because no arm of a `match` matched the value on top of the stack
+    #[stack(pop = 1)]
+    NoMatch,
+    /// starts a `try`, an error until the matching `EndTry` jumps to the `catch` at the offset
+    Try(Offset),
+    EndTry,
+    /// throws the value on top of the stack to the innermost `try`
+    #[stack(pop = 1)]
+    Throw,
+    /// whether the result on top of the stack is an `Err`, failing if it isn't a result
+    #[stack(push = 1)]
+    IsErr,
+    /// fails unless the value on top of the stack is of the annotated type, leaving it there
+    CheckType(Type),
+    /// jumps to the offset if the parameter in the slot was given an argument, otherwise
+    /// what follows computes its default
+    JumpToIfGiven(u16, Offset),
+    /// a `Call` with that many positional arguments, then a value for each name in the
+    /// array constant, which are moved to where the callee's parameters are
+    CallNamed(u8, ConstantLocation),
 }
@@ -0,0 +1,41 @@
+use std::rc::Rc;
+
+use super::{interner::InternedString, value::Value};
+
+/// what a `struct` declaration creates, its fields are fixed when it's declared
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructType {
+    pub name: String,
+    pub fields: Vec<InternedString>,
+}
+impl StructType {
+    pub fn field_index(&self, name: InternedString) -> Option<usize> {
+        self.fields.iter().position(|field| *field == name)
+    }
+}
+
+/// a value of a struct type, its values are in the order the fields were declared in
+#[derive(Debug, Clone)]
+pub struct Struct {
+    pub kind: Rc<StructType>,
+    pub values: Vec<Value>,
+}
+impl Struct {
+    pub fn get(&self, name: InternedString) -> Option<&Value> {
+        Some(&self.values[self.kind.field_index(name)?])
+    }
+    /// the fields paired with their values, for printing
+    pub fn fields(&self) -> impl Iterator<Item = (String, &Value)> {
+        self.kind
+            .fields
+            .iter()
+            .map(|name| String::from(*name))
+            .zip(self.values.iter())
+    }
+}
+impl PartialEq for Struct {
+    /// structs are equal when they're of the same type and all their fields are
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.kind, &other.kind) && self.values == other.values
+    }
+}
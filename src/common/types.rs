@@ -0,0 +1,64 @@
+use std::fmt::Display;
+
+use super::value::Value;
+
+/// what a type annotation says a value is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Type {
+    /// anything, which is what every value without an annotation is
+    #[default]
+    Any,
+    Number,
+    String,
+    Bool,
+    Array,
+    /// what a function that returns nothing returns
+    Void,
+}
+impl Type {
+    pub const ALL: [Type; 6] = [
+        Type::Number,
+        Type::String,
+        Type::Bool,
+        Type::Array,
+        Type::Void,
+        Type::Any,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Type> {
+        Type::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+    /// how the type is written in an annotation
+    pub fn name(&self) -> &'static str {
+        match self {
+            Type::Any => "any",
+            Type::Number => "num",
+            Type::String => "str",
+            Type::Bool => "bool",
+            Type::Array => "array",
+            Type::Void => "void",
+        }
+    }
+    pub fn matches(&self, value: &Value) -> bool {
+        match (self, value) {
+            (Type::Any, _) => true,
+            (Type::Number, Value::Number(_)) => true,
+            (Type::String, Value::String(_)) => true,
+            (Type::Bool, Value::Boolean(_)) => true,
+            (Type::Array, Value::Array(_)) => true,
+            (Type::Void, Value::Void) => true,
+            (kind, Value::UpvalueLocation(location)) => kind.matches(&location.borrow()),
+            _ => false,
+        }
+    }
+    /// whether a value of the other type can be used where this one is expected, which is
+    /// always the case if either isn't known
+    pub fn accepts(&self, other: Type) -> bool {
+        *self == Type::Any || other == Type::Any || *self == other
+    }
+}
+impl Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
@@ -7,12 +7,15 @@ use std::{
     rc::Rc,
 };
 
-
+use crate::backend::error::ErrorValue;
 
 use super::{
+    class::{BoundMethod, Class, Instance},
     closure::Closure,
+    enums::{variant_name, EnumType, Variant},
     function::Function,
     interner::{InternedString, STRING_INTERNER},
+    structs::{Struct, StructType},
 };
 
 #[repr(u8)]
@@ -24,6 +27,17 @@ pub enum Value {
     Function(Rc<Function>),
     Array(Ptr<Vec<Value>>),
     Closure(Box<Closure>),
+    Class(Ptr<Class>),
+    Instance(Ptr<Instance>),
+    BoundMethod(Rc<BoundMethod>),
+    StructType(Rc<StructType>),
+    Struct(Ptr<Struct>),
+    Enum(Rc<EnumType>),
+    Variant(Rc<Variant>),
+    /// a variant that carries values, which is called with them to make one
+    VariantConstructor(Rc<EnumType>, usize),
+    /// a runtime error that was caught
+    Error(Rc<ErrorValue>),
     Void,
     #[default]
     None,
@@ -52,21 +66,107 @@ impl Debug for Value {
             Self::Void => write!(f, "Void"),
             Self::None => write!(f, "None"),
             Self::Closure(closure) => write!(f, "<closure {:?}>", closure.func),
+            Self::Class(class) => f.debug_tuple("Class").field(&class.borrow().name).finish(),
+            Self::Instance(instance) => {
+                let instance = instance.borrow();
+                let mut fields = f.debug_struct(&instance.class.borrow().name);
+                for (name, value) in instance.sorted_fields() {
+                    fields.field(&name, value);
+                }
+                fields.finish()
+            }
+            Self::BoundMethod(bound) => write!(f, "<bound method {}>", bound.method.func.name),
+            Self::StructType(kind) => f.debug_tuple("StructType").field(&kind.name).finish(),
+            Self::Struct(value) => {
+                let value = value.borrow();
+                let mut fields = f.debug_struct(&value.kind.name);
+                for (name, field) in value.fields() {
+                    fields.field(&name, field);
+                }
+                fields.finish()
+            }
+            Self::Enum(kind) => f.debug_tuple("Enum").field(&kind.name).finish(),
+            Self::Variant(variant) => {
+                let mut tuple = f.debug_tuple(&variant.name());
+                for value in &variant.values {
+                    tuple.field(value);
+                }
+                tuple.finish()
+            }
+            Self::VariantConstructor(kind, index) => {
+                write!(f, "<variant {}>", variant_name(kind, *index))
+            }
+            Self::Error(error) => f.debug_tuple("Error").field(&error.error.message).finish(),
         }
     }
 }
 impl PartialEq for Value {
     fn eq(&self, other: &Self) -> bool {
-        match (self, other) {
-            (Self::Number(l0), Self::Number(r0)) => l0 == r0,
-            (Self::Boolean(l0), Self::Boolean(r0)) => l0 == r0,
-            (Self::String(l0), Self::String(r0)) => l0.eq(r0),
-            (Self::Void, Self::Void) => true,
-            (Self::None, Self::None) => true,
-            _ => false,
+        equal(self, other, &mut Vec::new())
+    }
+}
+
+/// the structs and arrays already being compared, which are taken to be equal when they're
+/// reached again so comparing ones that contain themselves ends
+type Comparing = Vec<(*const (), *const ())>;
+
+fn equal(lhs: &Value, rhs: &Value, comparing: &mut Comparing) -> bool {
+    match (lhs, rhs) {
+        (Value::Number(l0), Value::Number(r0)) => l0 == r0,
+        (Value::Boolean(l0), Value::Boolean(r0)) => l0 == r0,
+        (Value::String(l0), Value::String(r0)) => l0.eq(r0),
+        (Value::Void, Value::Void) => true,
+        (Value::None, Value::None) => true,
+        // classes and instances are only equal to themselves
+        (Value::Class(l0), Value::Class(r0)) => Rc::ptr_eq(l0, r0),
+        (Value::Instance(l0), Value::Instance(r0)) => Rc::ptr_eq(l0, r0),
+        // structs and arrays are equal when their contents are
+        (Value::StructType(l0), Value::StructType(r0)) => Rc::ptr_eq(l0, r0),
+        (Value::Struct(l0), Value::Struct(r0)) => {
+            if Rc::ptr_eq(l0, r0) || !start_comparing(l0, r0, comparing) {
+                return true;
+            }
+            let (l0, r0) = (l0.borrow(), r0.borrow());
+            Rc::ptr_eq(&l0.kind, &r0.kind) && all_equal(&l0.values, &r0.values, comparing)
         }
+        (Value::Array(l0), Value::Array(r0)) => {
+            if Rc::ptr_eq(l0, r0) || !start_comparing(l0, r0, comparing) {
+                return true;
+            }
+            all_equal(&l0.borrow(), &r0.borrow(), comparing)
+        }
+        (Value::Enum(l0), Value::Enum(r0)) => Rc::ptr_eq(l0, r0),
+        (Value::Variant(l0), Value::Variant(r0)) => {
+            Rc::ptr_eq(&l0.kind, &r0.kind)
+                && l0.index == r0.index
+                && all_equal(&l0.values, &r0.values, comparing)
+        }
+        (Value::VariantConstructor(l0, l1), Value::VariantConstructor(r0, r1)) => {
+            Rc::ptr_eq(l0, r0) && l1 == r1
+        }
+        (Value::Error(l0), Value::Error(r0)) => Rc::ptr_eq(l0, r0),
+        _ => false,
+    }
+}
+
+/// records the pair as being compared, false if it already is
+fn start_comparing<T>(lhs: &Ptr<T>, rhs: &Ptr<T>, comparing: &mut Comparing) -> bool {
+    let pair = (Rc::as_ptr(lhs) as *const (), Rc::as_ptr(rhs) as *const ());
+    if comparing.contains(&pair) {
+        return false;
     }
+    comparing.push(pair);
+    true
 }
+
+fn all_equal(lhs: &[Value], rhs: &[Value], comparing: &mut Comparing) -> bool {
+    lhs.len() == rhs.len()
+        && lhs
+            .iter()
+            .zip(rhs)
+            .all(|(lhs, rhs)| equal(lhs, rhs, comparing))
+}
+
 pub trait AsValue {
     fn to_value(self) -> Value;
 }
@@ -92,6 +192,35 @@ impl Value {
             unreachable!()
         }
     }
+    /// what the value is called in runtime errors
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Number(_) => "number",
+            Value::Boolean(_) => "bool",
+            Value::String(_) => "string",
+            Value::Function(_) | Value::Closure(_) => "function",
+            Value::Array(_) => "array",
+            Value::Class(_) => "class",
+            Value::Instance(_) => "instance",
+            Value::BoundMethod(_) => "function",
+            Value::StructType(_) => "struct type",
+            Value::Struct(_) => "struct",
+            Value::Enum(_) => "enum",
+            Value::Variant(_) => "variant",
+            Value::VariantConstructor(..) => "function",
+            Value::Error(_) => "error",
+            Value::Void => "void",
+            Value::None => "none",
+            Value::UpvalueLocation(location) => location.borrow().type_name(),
+        }
+    }
+    /// how the value is shown in runtime errors, strings are quoted so `1` and `"1"` differ
+    pub fn quoted(&self) -> String {
+        match self {
+            Value::None | Value::Void => self.type_name().to_string(),
+            _ => nested(self, &mut Vec::new()),
+        }
+    }
 }
 impl AsValue for &str {
     #[inline(always)]
@@ -135,10 +264,78 @@ impl Display for Value {
             Value::Closure(function) => {
                 write!(f, "<closure {:?}>", addr_of!(function))
             }
-            Value::Array(array) => {
-                let tmp = array.as_ref().borrow();
-                write!(f, "{:?}", tmp)
+            Value::Array(_) | Value::Struct(_) | Value::Variant(_) => {
+                write!(f, "{}", compound(self, &mut Vec::new()))
+            }
+            Value::Class(class) => write!(f, "<class {}>", class.borrow().name),
+            Value::Instance(instance) => {
+                write!(f, "<{} instance>", instance.borrow().class.borrow().name)
+            }
+            Value::BoundMethod(bound) => write!(
+                f,
+                "<method {}.{}>",
+                bound.receiver.borrow().class.borrow().name,
+                bound.method.func.name
+            ),
+            Value::StructType(kind) => write!(f, "<struct {}>", kind.name),
+            Value::Enum(kind) => write!(f, "<enum {}>", kind.name),
+            Value::VariantConstructor(kind, index) => {
+                write!(f, "<variant {}>", variant_name(kind, *index))
             }
+            Value::Error(error) => write!(f, "{}", error.error.message),
+        }
+    }
+}
+
+/// how a value is printed inside another one, where strings are quoted
+fn nested(value: &Value, printing: &mut Vec<*const ()>) -> String {
+    match value {
+        Value::String(_) => format!("\"{value}\""),
+        Value::Array(_) | Value::Struct(_) | Value::Variant(_) => compound(value, printing),
+        _ => value.to_string(),
+    }
+}
+
+/// prints a value that holds others, a struct or array that's already being printed is shown
+/// as `<cycle>` instead of being printed again
+fn compound(value: &Value, printing: &mut Vec<*const ()>) -> String {
+    match value {
+        Value::Array(array) => {
+            let Some(array) = start_printing(array, printing) else {
+                return "<cycle>".to_string();
+            };
+            let elements: Vec<String> = array.iter().map(|e| nested(e, printing)).collect();
+            printing.pop();
+            format!("[{}]", elements.join(", "))
         }
+        Value::Struct(value) => {
+            let Some(value) = start_printing(value, printing) else {
+                return "<cycle>".to_string();
+            };
+            let fields: Vec<String> = value
+                .fields()
+                .map(|(name, field)| format!("{name}: {}", nested(field, printing)))
+                .collect();
+            printing.pop();
+            format!("{} {{ {} }}", value.kind.name, fields.join(", "))
+        }
+        Value::Variant(variant) if variant.values.is_empty() => variant.name(),
+        Value::Variant(variant) => {
+            let values: Vec<String> = variant.values.iter().map(|v| nested(v, printing)).collect();
+            format!("{}({})", variant.name(), values.join(", "))
+        }
+        _ => value.to_string(),
+    }
+}
+
+fn start_printing<'a, T>(
+    value: &'a Ptr<T>,
+    printing: &mut Vec<*const ()>,
+) -> Option<std::cell::Ref<'a, T>> {
+    let pointer = Rc::as_ptr(value) as *const ();
+    if printing.contains(&pointer) {
+        return None;
     }
+    printing.push(pointer);
+    Some(value.borrow())
 }
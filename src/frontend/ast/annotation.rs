@@ -0,0 +1,21 @@
+use crate::{
+    common::types::Type,
+    frontend::{compiler::Compiler, scanner::Token},
+};
+
+/// the `: num` after a parameter or variable, or the `-> num` of a function
+#[derive(Debug, Clone, PartialEq)]
+pub struct Annotation {
+    pub name: Token,
+    pub kind: Type,
+}
+impl Annotation {
+    /// fails at runtime unless the value on top of the stack is of the annotated type
+    pub fn check(&self, compiler: &mut Compiler) {
+        if self.kind == Type::Any {
+            return;
+        }
+        compiler.bytecode.function.chunk.set_span(&self.name);
+        compiler.bytecode.write_check_type_op(self.kind);
+    }
+}
@@ -0,0 +1,78 @@
+use crate::{
+    common::{opcode::OpCode, value::AsValue},
+    frontend::{
+        ast::{identifier::Identifier, CompileToBytecode},
+        compiler::{symbols::SymbolKind, Compiler, FunctionType},
+        scanner::{Token, TokenKind},
+    },
+};
+
+use super::{function::FunctionDeclaration, AsDeclaration};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassDeclaration {
+    pub name: Identifier,
+    /// the class after `<`, if there is one
+    pub superclass: Option<Identifier>,
+    pub methods: Vec<FunctionDeclaration>,
+}
+impl CompileToBytecode for ClassDeclaration {
+    fn to_bytecode(&self, compiler: &mut Compiler) {
+        let name = &self.name.value;
+        let definition = compiler.declare_symbol(name, SymbolKind::Class);
+        let location = compiler
+            .bytecode
+            .make_constant(name.lexeme.as_str().to_value());
+        compiler.bytecode.function.chunk.set_span(name);
+        compiler.bytecode.write_class_op(location);
+        if compiler.bytecode.scope_depth > 0 {
+            compiler.add_local(name.clone(), definition);
+        } else {
+            let slot = compiler.define_global(name, definition);
+            compiler
+                .bytecode
+                .function
+                .chunk
+                .emit_op(OpCode::DefineGlobal(slot))
+        }
+
+        if let Some(superclass) = &self.superclass {
+            // methods capture the superclass as `super`, from a scope around the class body
+            compiler.begin_scope();
+            superclass.to_bytecode(compiler);
+            let token = Token {
+                kind: TokenKind::Identifier,
+                lexeme: "super".to_string(),
+                ..superclass.value.clone()
+            };
+            compiler.add_local(token, None);
+            self.name.to_bytecode(compiler);
+            compiler.bytecode.function.chunk.set_span(&superclass.value);
+            compiler.bytecode.function.chunk.emit_op(OpCode::Inherit);
+        }
+
+        // the class is pushed again for the methods to be added to
+        self.name.to_bytecode(compiler);
+        for method in &self.methods {
+            let function_type = if method.name.value.lexeme == "init" {
+                FunctionType::Initializer
+            } else {
+                FunctionType::Method
+            };
+            method.compile_closure(compiler, function_type);
+            let location = compiler
+                .bytecode
+                .make_constant(method.name.value.lexeme.as_str().to_value());
+            compiler.bytecode.write_method_op(location);
+        }
+        compiler.bytecode.function.chunk.emit_op(OpCode::Pop);
+        if self.superclass.is_some() {
+            compiler.end_scope();
+        }
+    }
+}
+impl AsDeclaration for ClassDeclaration {
+    fn to_declaration(self) -> super::Declaration {
+        super::Declaration::Class(self)
+    }
+}
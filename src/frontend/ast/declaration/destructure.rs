@@ -0,0 +1,76 @@
+use crate::{
+    common::{opcode::OpCode, value::AsValue},
+    frontend::{
+        ast::{expression::Expression, identifier::Identifier, CompileToBytecode},
+        compiler::{symbols::SymbolKind, Compiler},
+        scanner::Token,
+    },
+};
+
+use super::AsDeclaration;
+
+/// `let Point { x, y } = point;`, which declares a variable for each field
+#[derive(Debug, Clone, PartialEq)]
+pub struct Destructure {
+    pub kind: Identifier,
+    pub fields: Vec<Token>,
+    pub initializer: Expression,
+}
+impl CompileToBytecode for Destructure {
+    fn to_bytecode(&self, compiler: &mut Compiler) {
+        self.initializer.to_bytecode(compiler);
+        self.kind.to_bytecode(compiler);
+        compiler.bytecode.function.chunk.set_span(&self.kind.value);
+        compiler.bytecode.write_check_struct_op();
+
+        if compiler.bytecode.scope_depth > 0 {
+            // the struct stays in a slot no name can reach, the fields are read off it
+            let hidden = Token {
+                lexeme: String::new(),
+                ..self.kind.value.clone()
+            };
+            compiler.add_local(hidden, None);
+            let slot = compiler.bytecode.local_count as u16 - 1;
+            for field in &self.fields {
+                compiler
+                    .bytecode
+                    .function
+                    .chunk
+                    .emit_op(OpCode::GetLocal(slot));
+                emit_get_field(compiler, field);
+                let definition = compiler.declare_symbol(field, SymbolKind::Variable);
+                compiler.add_local(field.clone(), definition);
+            }
+            return;
+        }
+
+        for (index, field) in self.fields.iter().enumerate() {
+            if index + 1 < self.fields.len() {
+                compiler.bytecode.write_dup_op();
+            }
+            emit_get_field(compiler, field);
+            let definition = compiler.declare_symbol(field, SymbolKind::Variable);
+            let slot = compiler.define_global(field, definition);
+            compiler
+                .bytecode
+                .function
+                .chunk
+                .emit_op(OpCode::DefineGlobal(slot))
+        }
+        if self.fields.is_empty() {
+            compiler.bytecode.function.chunk.emit_op(OpCode::Pop);
+        }
+    }
+}
+fn emit_get_field(compiler: &mut Compiler, field: &Token) {
+    let location = compiler
+        .bytecode
+        .make_constant(field.lexeme.as_str().to_value());
+    compiler.bytecode.function.chunk.set_span(field);
+    compiler.bytecode.write_get_property_op(location);
+}
+impl AsDeclaration for Destructure {
+    fn to_declaration(self) -> super::Declaration {
+        super::Declaration::Destructure(self)
+    }
+}
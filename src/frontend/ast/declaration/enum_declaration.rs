@@ -0,0 +1,65 @@
+use std::rc::Rc;
+
+use crate::{
+    common::{
+        enums::{EnumType, VariantType},
+        interner::InternedString,
+        opcode::OpCode,
+        value::Value,
+    },
+    frontend::{
+        ast::{identifier::Identifier, CompileToBytecode},
+        compiler::{symbols::SymbolKind, Compiler},
+        scanner::Token,
+    },
+};
+
+use super::AsDeclaration;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnumDeclaration {
+    pub name: Identifier,
+    pub variants: Vec<VariantDeclaration>,
+}
+/// `Circle(radius)`, or just `Empty` for a variant without values
+#[derive(Debug, Clone, PartialEq)]
+pub struct VariantDeclaration {
+    pub name: Token,
+    pub fields: Vec<Token>,
+}
+impl CompileToBytecode for EnumDeclaration {
+    fn to_bytecode(&self, compiler: &mut Compiler) {
+        let name = &self.name.value;
+        let definition = compiler.declare_symbol(name, SymbolKind::Enum);
+        let intern = |token: &Token| InternedString::from(token.lexeme.as_str());
+        // like a struct, the whole type is known while compiling
+        let kind = EnumType {
+            name: name.lexeme.clone(),
+            variants: self
+                .variants
+                .iter()
+                .map(|variant| VariantType {
+                    name: intern(&variant.name),
+                    fields: variant.fields.iter().map(intern).collect(),
+                })
+                .collect(),
+        };
+        let location = compiler.bytecode.make_constant(Value::Enum(Rc::new(kind)));
+        compiler.bytecode.write_constant_op(location);
+        if compiler.bytecode.scope_depth > 0 {
+            compiler.add_local(name.clone(), definition);
+        } else {
+            let slot = compiler.define_global(name, definition);
+            compiler
+                .bytecode
+                .function
+                .chunk
+                .emit_op(OpCode::DefineGlobal(slot))
+        }
+    }
+}
+impl AsDeclaration for EnumDeclaration {
+    fn to_declaration(self) -> super::Declaration {
+        super::Declaration::Enum(self)
+    }
+}
@@ -1,13 +1,19 @@
-use std::{rc::Rc};
+use std::rc::Rc;
 
 use crate::{
-    common::{
-        opcode::OpCode,
-        value::{AsValue, Value},
-    },
+    common::{interner::InternedString, opcode::OpCode, value::Value},
     frontend::{
-        ast::{expression::block::Block, identifier::Identifier, CompileToBytecode},
-        compiler::{Compiler, Enclosing, FunctionType},
+        ast::{
+            annotation::Annotation,
+            expression::{block::Block, Expression},
+            identifier::Identifier,
+            node::Node,
+            statement::Statement,
+            CompileToBytecode,
+        },
+        compiler::{symbols::SymbolKind, Compiler, Enclosing, FunctionType},
+        optimizer::peephole,
+        scanner::{Token, TokenKind},
     },
 };
 
@@ -17,60 +23,129 @@ use super::AsDeclaration;
 pub struct FunctionDeclaration {
     pub name: Identifier,
     pub block: Block,
-    pub parameters: Vec<Identifier>,
+    pub parameters: Vec<Parameter>,
+    /// the type after `->`
+    pub returns: Option<Annotation>,
+}
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parameter {
+    pub name: Identifier,
+    pub annotation: Option<Annotation>,
+    /// what the parameter is when its argument is left out, `b = 2`
+    pub default: Option<Expression>,
+    /// `...rest`, which collects the arguments after the others into an array
+    pub rest: bool,
 }
 impl<'a> Compiler<'a> {
     fn in_scope(&self) -> bool {
         self.bytecode.scope_depth > 0
     }
 }
-impl CompileToBytecode for FunctionDeclaration {
-    fn to_bytecode(&self, compiler: &mut crate::frontend::compiler::Compiler) {
+impl FunctionDeclaration {
+    /// compiles the body into a function of its own, and emits the closure that wraps it
+    pub fn compile_closure(&self, compiler: &mut Compiler, function_type: FunctionType) {
         // uses the current compiler's compilation context for the function
         // which is returned later
-        let lexeme = self.name.value.lexeme.clone();
-        let _name = compiler
-            .bytecode
-            .function
-            .chunk
-            .emit_value(lexeme.to_value());
-        compiler.bytecode.globals.push(lexeme);
-        let mut temp_compiler = Compiler::new(compiler.diagnostics.clone(), FunctionType::Function);
+        let mut temp_compiler = Compiler::new(compiler.diagnostics.clone(), function_type);
         temp_compiler.enclosing = Some(Enclosing(compiler));
-        temp_compiler
-            .bytecode
-            .globals
-            .extend(compiler.bytecode.globals.clone());
+        temp_compiler.optimize = compiler.optimize;
+        temp_compiler.symbols = compiler.symbols.clone();
         let function = {
             // sets the function name and arity
-            temp_compiler.bytecode.function.arity = self.parameters.len() as u8;
+            let variadic = self
+                .parameters
+                .last()
+                .is_some_and(|parameter| parameter.rest);
+            let optional = self
+                .parameters
+                .iter()
+                .filter(|parameter| parameter.default.is_some());
+            temp_compiler.bytecode.function.arity =
+                (self.parameters.len() - variadic as usize) as u8;
+            temp_compiler.bytecode.function.optional = optional.count() as u8;
+            temp_compiler.bytecode.function.variadic = variadic;
+            temp_compiler.bytecode.function.parameters = self
+                .parameters
+                .iter()
+                .filter(|parameter| !parameter.rest)
+                .map(|parameter| InternedString::from(parameter.name.value.lexeme.as_str()))
+                .collect();
             temp_compiler.bytecode.function.name = self.name.value.lexeme.clone();
+            temp_compiler.bytecode.function.file = compiler.bytecode.function.file.clone();
 
+            // the instance a method is called on sits before its parameters
+            if temp_compiler.in_method() {
+                temp_compiler.bytecode.function.method = true;
+                let this = Token {
+                    kind: TokenKind::Identifier,
+                    lexeme: String::from("this"),
+                    ..Default::default()
+                };
+                temp_compiler.add_local(this, None);
+            }
             // tells the compiler to recongize any parameters
             for param in &self.parameters {
-                temp_compiler.add_local(param.value.clone())
+                let definition =
+                    temp_compiler.declare_symbol(&param.name.value, SymbolKind::Parameter);
+                temp_compiler.add_local(param.name.value.clone(), definition)
+            }
+            // the arguments that were left out are computed in the function, where the
+            // parameters before them can be used
+            for (index, param) in self.parameters.iter().enumerate() {
+                if let Some(default) = &param.default {
+                    let slot = (index + temp_compiler.in_method() as usize) as u16;
+                    let jump = temp_compiler.bytecode.function.chunk.code.len();
+                    temp_compiler
+                        .bytecode
+                        .write_jump_to_if_given_op(slot, 0xfff);
+                    default.to_bytecode(&mut temp_compiler);
+                    temp_compiler.bytecode.write_set_local_consumes_op(slot);
+                    let after = temp_compiler.bytecode.function.chunk.code.len();
+                    temp_compiler.bytecode.function.chunk.code[jump] =
+                        OpCode::JumpToIfGiven(slot, after);
+                }
+            }
+            // callers aren't checked, so the annotated parameters are checked on the way in
+            for (index, param) in self.parameters.iter().enumerate() {
+                if let Some(annotation) = &param.annotation {
+                    let slot = index + temp_compiler.in_method() as usize;
+                    temp_compiler.bytecode.write_get_local_op(slot as u16);
+                    annotation.check(&mut temp_compiler);
+                    temp_compiler.bytecode.write_pop_op();
+                }
             }
 
             // finally compiles the block
             self.block.to_bytecode(&mut temp_compiler);
-            // unecessary return if the source code for the function already includes one
-            // i.e `func x() {return 1;}` will have two return ops
-            if !temp_compiler.bytecode.returned {
-                temp_compiler.bytecode.write_void_op();
-                temp_compiler
-                    .bytecode
-                    .function
-                    .chunk
-                    .emit_op(OpCode::Return);
+            // unecessary return if the function already ends with one
+            // i.e `func x() {return 1;}` would have two return ops, but a `return` that
+            // is only reached sometimes still needs one after it
+            let returns = matches!(
+                self.block.declarations.last(),
+                Some(Node::Statement(Statement::Return(_)))
+            );
+            if !returns {
+                let initializer = temp_compiler.bytecode.function_type == FunctionType::Initializer;
+                match &self.returns {
+                    // running off the end returns nothing, which has to be what was promised
+                    Some(annotation) if !initializer => {
+                        temp_compiler.bytecode.write_void_op();
+                        annotation.check(&mut temp_compiler);
+                        temp_compiler.bytecode.write_return_op();
+                    }
+                    _ => temp_compiler.write_implicit_return(),
+                }
             }
+            if temp_compiler.optimize {
+                peephole::optimize_chunk(&mut temp_compiler.bytecode.function.chunk);
+            }
+            temp_compiler.check_constant_pool();
             temp_compiler.bytecode.function
         };
         let function = Rc::new(function);
         let location = compiler
             .bytecode
-            .function
-            .chunk
-            .emit_value(Value::Function(function.clone()));
+            .make_constant(Value::Function(function.clone()));
         compiler.bytecode.write_closure_op(location);
 
         let count = function.upvalue_count;
@@ -79,24 +154,50 @@ impl CompileToBytecode for FunctionDeclaration {
             compiler.bytecode.write_byte(upvalue.is_local as u8);
             compiler.bytecode.write_byte(upvalue.index)
         }
+        // compilation context is returned
+    }
+}
+impl<'a> Compiler<'a> {
+    pub fn in_method(&self) -> bool {
+        matches!(
+            self.bytecode.function_type,
+            FunctionType::Method | FunctionType::Initializer
+        )
+    }
+    /// what a function returns when it runs off its end, or hits a bare `return;`
+    pub fn write_implicit_return(&mut self) {
+        if self.bytecode.function_type == FunctionType::Initializer {
+            // `this`
+            self.bytecode.function.chunk.emit_op(OpCode::GetLocal(0));
+        } else {
+            self.bytecode.write_void_op();
+        }
+        self.bytecode.function.chunk.emit_op(OpCode::Return);
+    }
+}
+impl CompileToBytecode for FunctionDeclaration {
+    fn to_bytecode(&self, compiler: &mut crate::frontend::compiler::Compiler) {
+        let parameters: Vec<Token> = self
+            .parameters
+            .iter()
+            .map(|parameter| parameter.name.value.clone())
+            .collect();
+        let definition = compiler.declare_function_symbol(
+            &self.name.value,
+            SymbolKind::Function,
+            Some(&parameters),
+        );
+        self.compile_closure(compiler, FunctionType::Function);
         if compiler.in_scope() {
-            compiler.add_local(self.name.value.clone());
+            compiler.add_local(self.name.value.clone(), definition);
         } else {
-            // location of the name in the constant pool
-            let lexeme = self.name.value.lexeme.clone();
-            let name = compiler
-                .bytecode
-                .function
-                .chunk
-                .emit_value(lexeme.to_value());
-            compiler.bytecode.globals.push(lexeme);
+            let slot = compiler.define_global(&self.name.value, definition);
             compiler
                 .bytecode
                 .function
                 .chunk
-                .emit_op(OpCode::DefineGlobal(name))
+                .emit_op(OpCode::DefineGlobal(slot))
         };
-        // compilation context is returned
     }
 }
 impl AsDeclaration for FunctionDeclaration {
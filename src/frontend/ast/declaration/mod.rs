@@ -1,15 +1,29 @@
 use crate::frontend::compiler::Compiler;
 
-use self::{function::FunctionDeclaration, variable_declaration::VariableDeclaration};
+use self::{
+    class::ClassDeclaration, destructure::Destructure, enum_declaration::EnumDeclaration,
+    function::FunctionDeclaration, struct_declaration::StructDeclaration,
+    use_declaration::UseDeclaration, variable_declaration::VariableDeclaration,
+};
 
 use super::{node::AsNode, CompileToBytecode};
 
+pub mod class;
+pub mod destructure;
+pub mod enum_declaration;
 pub mod function;
+pub mod struct_declaration;
+pub mod use_declaration;
 pub mod variable_declaration;
 #[derive(Debug, Clone, PartialEq)]
 pub enum Declaration {
     VariableDeclaration(VariableDeclaration),
     FunctionDeclaration(FunctionDeclaration),
+    Use(UseDeclaration),
+    Class(ClassDeclaration),
+    Struct(StructDeclaration),
+    Destructure(Destructure),
+    Enum(EnumDeclaration),
 }
 
 impl CompileToBytecode for Declaration {
@@ -20,6 +34,11 @@ impl CompileToBytecode for Declaration {
             Declaration::FunctionDeclaration(function_declaration) => {
                 function_declaration.to_bytecode(compiler)
             }
+            Declaration::Use(use_declaration) => use_declaration.to_bytecode(compiler),
+            Declaration::Class(class) => class.to_bytecode(compiler),
+            Declaration::Struct(declaration) => declaration.to_bytecode(compiler),
+            Declaration::Destructure(destructure) => destructure.to_bytecode(compiler),
+            Declaration::Enum(declaration) => declaration.to_bytecode(compiler),
         }
     }
 }
@@ -0,0 +1,52 @@
+use std::rc::Rc;
+
+use crate::{
+    common::{interner::InternedString, opcode::OpCode, structs::StructType, value::Value},
+    frontend::{
+        ast::{identifier::Identifier, CompileToBytecode},
+        compiler::{symbols::SymbolKind, Compiler},
+        scanner::Token,
+    },
+};
+
+use super::AsDeclaration;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructDeclaration {
+    pub name: Identifier,
+    pub fields: Vec<Token>,
+}
+impl CompileToBytecode for StructDeclaration {
+    fn to_bytecode(&self, compiler: &mut Compiler) {
+        let name = &self.name.value;
+        let definition = compiler.declare_symbol(name, SymbolKind::Struct);
+        // the fields are known already, so the whole type is a constant
+        let kind = StructType {
+            name: name.lexeme.clone(),
+            fields: self
+                .fields
+                .iter()
+                .map(|field| InternedString::from(field.lexeme.as_str()))
+                .collect(),
+        };
+        let location = compiler
+            .bytecode
+            .make_constant(Value::StructType(Rc::new(kind)));
+        compiler.bytecode.write_constant_op(location);
+        if compiler.bytecode.scope_depth > 0 {
+            compiler.add_local(name.clone(), definition);
+        } else {
+            let slot = compiler.define_global(name, definition);
+            compiler
+                .bytecode
+                .function
+                .chunk
+                .emit_op(OpCode::DefineGlobal(slot))
+        }
+    }
+}
+impl AsDeclaration for StructDeclaration {
+    fn to_declaration(self) -> super::Declaration {
+        super::Declaration::Struct(self)
+    }
+}
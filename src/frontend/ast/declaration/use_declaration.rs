@@ -0,0 +1,29 @@
+use crate::frontend::{
+    ast::{identifier::Identifier, CompileToBytecode},
+    compiler::Compiler,
+    scanner::Token,
+};
+
+use super::AsDeclaration;
+
+/// `use "path/to/lib.mng";` or `use lib::{a, b};`, either can end with `as other_name`
+#[derive(Debug, Clone, PartialEq)]
+pub struct UseDeclaration {
+    pub keyword: Token,
+    /// a string with the path of the module, or the name of one next to the file
+    pub module: Token,
+    /// the names brought into scope unqualified
+    pub names: Vec<Identifier>,
+    /// what the module is called in the importing file instead of its own name
+    pub alias: Option<Token>,
+}
+impl CompileToBytecode for UseDeclaration {
+    fn to_bytecode(&self, compiler: &mut Compiler) {
+        compiler.import(self)
+    }
+}
+impl AsDeclaration for UseDeclaration {
+    fn to_declaration(self) -> super::Declaration {
+        super::Declaration::Use(self)
+    }
+}
@@ -1,13 +1,14 @@
 use crate::{
-    common::{opcode::OpCode, value::AsValue},
+    common::opcode::OpCode,
     frontend::{
         ast::{
+            annotation::Annotation,
             expression::Expression,
             identifier::Identifier,
             node::{AsNode, Node},
             CompileToBytecode,
         },
-        compiler::{local::Local, Compiler},
+        compiler::{local::Local, symbols::SymbolKind, Compiler},
         scanner::Token,
     },
 };
@@ -18,28 +19,40 @@ use super::AsDeclaration;
 pub struct VariableDeclaration {
     pub identifier: Identifier,
     pub intializer: Expression,
+    pub annotation: Option<Annotation>,
     // pub mutable: bool,
 }
 impl CompileToBytecode for VariableDeclaration {
     fn to_bytecode(&self, compiler: &mut Compiler) {
         self.intializer.to_bytecode(compiler);
+        let name = &self.identifier.value;
+        let definition = compiler.declare_symbol(name, SymbolKind::Variable);
         if compiler.bytecode.scope_depth > 0 {
-            compiler.add_local(self.identifier.value.clone());
+            compiler.add_local(name.clone(), definition);
             return;
         }
-        let function = &mut compiler.bytecode.function;
-        let lexeme = self.identifier.value.lexeme.clone();
-        let name = function.chunk.emit_value(lexeme.to_value());
-        compiler.bytecode.globals.push(lexeme);
-        function.chunk.emit_op(OpCode::DefineGlobal(name))
+        let slot = compiler.define_global(name, definition);
+        compiler
+            .bytecode
+            .function
+            .chunk
+            .emit_op(OpCode::DefineGlobal(slot))
     }
 }
 impl<'a> Compiler<'a> {
-    pub fn add_local(&mut self, name: Token) {
+    pub fn define_global(&mut self, name: &Token, definition: Option<usize>) -> u16 {
+        let slot = self.resolve_global(name);
+        let global = &mut self.globals()[slot as usize];
+        global.defined = true;
+        global.definition = global.definition.or(definition);
+        slot
+    }
+    pub fn add_local(&mut self, name: Token, definition: Option<usize>) {
         self.bytecode.locals.push(Local {
             name,
             depth: self.bytecode.scope_depth,
             is_captured: false,
+            definition,
         });
         self.bytecode.local_count += 1;
     }
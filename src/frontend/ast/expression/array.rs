@@ -0,0 +1,23 @@
+use crate::frontend::{ast::CompileToBytecode, compiler::Compiler, scanner::Token};
+
+use super::{AsExpr, Expression};
+
+/// `[1, 2, 3]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArrayLiteral {
+    pub bracket: Token,
+    pub elements: Vec<Expression>,
+}
+impl CompileToBytecode for ArrayLiteral {
+    fn to_bytecode(&self, compiler: &mut Compiler) {
+        for element in &self.elements {
+            element.to_bytecode(compiler);
+        }
+        compiler.bytecode.write_array_op(self.elements.len() as u8);
+    }
+}
+impl AsExpr for ArrayLiteral {
+    fn to_expr(self) -> Expression {
+        Expression::Array(self)
+    }
+}
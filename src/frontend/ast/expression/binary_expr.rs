@@ -1,7 +1,5 @@
 use crate::{
-    common::{
-        value::{AsValue, Value},
-    },
+    common::types::Type,
     frontend::{
         ast::{node::Node, CompileToBytecode},
         compiler::Compiler,
@@ -16,6 +14,8 @@ pub struct BinaryExpr {
     pub lhs: Box<Node>,
     pub rhs: Box<Node>,
     pub op: Token,
+    /// what the type checker proved both operands are, if they're the same
+    pub operands: Type,
 }
 impl AsExpr for BinaryExpr {
     fn to_expr(self) -> Expression {
@@ -26,31 +26,40 @@ impl BinaryExpr {
     pub fn compile_assignment(&self, compiler: &mut Compiler) {
         let _lhs = &self.lhs;
         let initializer = &self.rhs;
+        if let Node::Expression(Expression::Property(property)) = self.lhs.as_ref() {
+            property.object.to_bytecode(compiler);
+            initializer.to_bytecode(compiler);
+            let location = property.name_constant(compiler);
+            compiler.bytecode.function.chunk.set_span(&property.name);
+            compiler.bytecode.write_set_property_op(location);
+            return;
+        }
         initializer.to_bytecode(compiler);
         let Node::Identifier(name) = self.lhs.as_ref() else {
             panic!("{:?}", self)
         };
         let name = &name.value;
+        compiler.reference_symbol(name);
         let local = compiler.resolve_local(name);
         if let Some(local) = local {
-            if compiler.bytecode.compiling_statement {
-                compiler.bytecode.write_set_local_consumes_op(local as u16);
-            } else {
-                compiler.bytecode.write_set_local_op(local as u16);
-            }
-            return;
+            // `SetLocal` + `Pop` is fused into `SetLocalConsumes` by the peephole pass
+            compiler.bytecode.write_set_local_op(local as u16);
         } else if let Some(arg) = compiler.resolve_up_value(name) {
             compiler.bytecode.write_set_up_value_op(arg as u16);
         } else {
-            let string: Value = (&name.lexeme).to_value();
-            let location = compiler.bytecode.function.chunk.emit_value(string);
-            compiler.bytecode.write_set_global_op(location);
+            let slot = compiler.define_global(name, None);
+            compiler.bytecode.write_set_global_op(slot);
         }
     }
 }
 impl CompileToBytecode for BinaryExpr {
     fn to_bytecode(&self, compiler: &mut crate::frontend::compiler::Compiler) {
-        let BinaryExpr { lhs, rhs, op } = self;
+        let BinaryExpr {
+            lhs,
+            rhs,
+            op,
+            operands,
+        } = self;
         if op.kind.eq(&TokenKind::Equal) {
             self.compile_assignment(compiler);
             return;
@@ -58,9 +67,13 @@ impl CompileToBytecode for BinaryExpr {
         lhs.to_bytecode(compiler);
         rhs.to_bytecode(compiler);
 
-        let _chunk = &mut compiler.bytecode.function.chunk;
+        compiler.bytecode.function.chunk.set_span(op);
         match op.kind {
-            TokenKind::Plus => compiler.bytecode.write_add_op(),
+            TokenKind::Plus => match operands {
+                Type::Number => compiler.bytecode.write_add_numbers_op(),
+                Type::String => compiler.bytecode.write_add_strings_op(),
+                _ => compiler.bytecode.write_add_op(),
+            },
             TokenKind::Dash => compiler.bytecode.write_sub_op(),
             TokenKind::Star => compiler.bytecode.write_mul_op(),
             TokenKind::Slash => compiler.bytecode.write_div_op(),
@@ -41,6 +41,7 @@ impl<'a> Compiler<'a> {
             }
             self.bytecode.local_count -= 1;
         }
+        self.bytecode.locals.truncate(self.bytecode.local_count);
 
         self.bytecode
             .function
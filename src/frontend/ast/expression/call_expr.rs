@@ -1,7 +1,10 @@
 use crate::{
-    backend::vm::natives::MACROS::idx_to_str,
-    common::opcode::OpCode,
-    frontend::ast::{CompileToBytecode},
+    backend::vm::natives::native_index,
+    common::{
+        opcode::OpCode,
+        value::{rcrf, AsValue, Value},
+    },
+    frontend::{ast::CompileToBytecode, scanner::Token},
 };
 
 use super::{AsExpr, Expression};
@@ -9,17 +12,23 @@ use super::{AsExpr, Expression};
 pub struct Call {
     pub expr: Box<Expression>,
     pub parameters: Box<Vec<Expression>>,
+    /// the `name: value` arguments after the positional ones, which the type checker
+    /// puts in order when it knows the function being called
+    pub named: Vec<(Token, Expression)>,
 }
 impl CompileToBytecode for Call {
     fn to_bytecode(&self, compiler: &mut crate::frontend::compiler::Compiler) {
         if let Expression::Identifier(expr) = self.expr.as_ref() {
-            if "to_str" == expr.value.lexeme.as_str() {
+            // natives don't take named arguments, the type checker reports them
+            if let Some(native) = native_index(&expr.value.lexeme).filter(|_| self.named.is_empty())
+            {
                 self.parameters
                     .iter()
                     .for_each(|param| param.clone().to_bytecode(compiler));
+                compiler.bytecode.function.chunk.set_span(&expr.value);
                 compiler
                     .bytecode
-                    .write_call_fn_arg_ptr_op(idx_to_str!() as u8, self.parameters.len() as u8);
+                    .write_call_fn_arg_ptr_op(native as u8, self.parameters.len() as u8);
                 return;
             }
         }
@@ -28,11 +37,33 @@ impl CompileToBytecode for Call {
         self.parameters
             .iter()
             .for_each(|param| param.clone().to_bytecode(compiler));
-        compiler
-            .bytecode
-            .function
-            .chunk
-            .emit_op(OpCode::Call(self.parameters.len()));
+        self.named
+            .iter()
+            .for_each(|(_, value)| value.to_bytecode(compiler));
+        match self.expr.as_ref() {
+            Expression::Identifier(callee) => {
+                compiler.bytecode.function.chunk.set_span(&callee.value)
+            }
+            Expression::Property(method) => compiler.bytecode.function.chunk.set_span(&method.name),
+            _ => {}
+        }
+        if self.named.is_empty() {
+            compiler
+                .bytecode
+                .function
+                .chunk
+                .emit_op(OpCode::Call(self.parameters.len()));
+            return;
+        }
+        // the callee is only known when it's run
+        let names: Vec<Value> = self
+            .named
+            .iter()
+            .map(|(name, _)| name.lexeme.as_str().to_value())
+            .collect();
+        let names = compiler.bytecode.make_constant(Value::Array(rcrf(names)));
+        let chunk = &mut compiler.bytecode.function.chunk;
+        chunk.emit_op(OpCode::CallNamed(self.parameters.len() as u8, names));
     }
 }
 impl AsExpr for Call {
@@ -0,0 +1,26 @@
+use crate::frontend::{
+    ast::{declaration::function::FunctionDeclaration, CompileToBytecode},
+    compiler::{Compiler, FunctionType},
+    scanner::Token,
+};
+
+use super::{AsExpr, Expression};
+
+/// `func(x) { x * 2 }` or `|x| x * 2`, a function without a name that evaluates to its closure
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lambda {
+    /// the `func` or the first `|`
+    pub keyword: Token,
+    pub function: FunctionDeclaration,
+}
+impl CompileToBytecode for Lambda {
+    fn to_bytecode(&self, compiler: &mut Compiler) {
+        self.function
+            .compile_closure(compiler, FunctionType::Function);
+    }
+}
+impl AsExpr for Lambda {
+    fn to_expr(self) -> Expression {
+        Expression::Lambda(self)
+    }
+}
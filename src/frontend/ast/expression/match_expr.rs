@@ -0,0 +1,233 @@
+use crate::{
+    common::{
+        enums::{result_type, result_variant},
+        opcode::OpCode,
+        value::{AsValue, Value},
+    },
+    frontend::{
+        ast::{identifier::Identifier, literal::Literal, CompileToBytecode},
+        compiler::{symbols::SymbolKind, Compiler},
+        scanner::Token,
+    },
+};
+
+use super::{AsExpr, Expression};
+
+/// `match value { pattern => arm, .. }`, which is the value of the first arm that matched
+#[derive(Debug, Clone, PartialEq)]
+pub struct Match {
+    pub keyword: Token,
+    pub value: Box<Expression>,
+    pub arms: Vec<MatchArm>,
+}
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    /// the `if` after the pattern, the arm is skipped unless it's true
+    pub guard: Option<Expression>,
+    pub body: Expression,
+}
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pattern {
+    /// `_`, which matches anything
+    Wildcard(Token),
+    /// a name, which matches anything and binds it
+    Binding(Token),
+    Literal(Literal),
+    /// `Shape.Circle(radius)`, the values are left out for variants without any,
+    /// and `Ok` and `Err` have no enum in front of them
+    Variant {
+        kind: Option<Identifier>,
+        name: Token,
+        values: Vec<Pattern>,
+    },
+    /// `[first, second]`, or `[first, ..]` for arrays at least that long
+    Array {
+        bracket: Token,
+        elements: Vec<Pattern>,
+        rest: bool,
+    },
+}
+impl Pattern {
+    /// matches anything, so no arm after it can match
+    pub fn is_irrefutable(&self) -> bool {
+        matches!(self, Pattern::Wildcard(_) | Pattern::Binding(_))
+    }
+    /// emits the checks of the pattern against the value at `path` in the matched value,
+    /// each one jumps away if it fails
+    fn test(&self, compiler: &mut Compiler, slot: u16, path: &mut Vec<u8>, fails: &mut Vec<usize>) {
+        match self {
+            Pattern::Wildcard(_) | Pattern::Binding(_) => {}
+            Pattern::Literal(literal) => {
+                emit_path(compiler, slot, path);
+                literal.to_bytecode(compiler);
+                compiler.bytecode.function.chunk.emit_op(OpCode::Equal);
+                fails.push(compiler.emit_pop_jump_if_false());
+            }
+            Pattern::Variant { kind, name, values } => {
+                emit_path(compiler, slot, path);
+                match (kind, result_variant(&name.lexeme)) {
+                    (Some(kind), _) => {
+                        kind.to_bytecode(compiler);
+                        let location = compiler
+                            .bytecode
+                            .make_constant(name.lexeme.as_str().to_value());
+                        compiler.bytecode.function.chunk.set_span(name);
+                        compiler.bytecode.write_get_property_op(location);
+                    }
+                    (None, index) => {
+                        let constructor = Value::VariantConstructor(
+                            result_type(),
+                            index.expect("only `Ok` and `Err` are parsed without an enum"),
+                        );
+                        let location = compiler.bytecode.make_constant(constructor);
+                        compiler.bytecode.write_constant_op(location);
+                    }
+                }
+                compiler.bytecode.write_match_variant_op();
+                fails.push(compiler.emit_pop_jump_if_false());
+                Self::test_all(values, compiler, slot, path, fails);
+            }
+            Pattern::Array { elements, rest, .. } => {
+                emit_path(compiler, slot, path);
+                compiler
+                    .bytecode
+                    .write_match_array_op(elements.len() as u8, *rest);
+                fails.push(compiler.emit_pop_jump_if_false());
+                Self::test_all(elements, compiler, slot, path, fails);
+            }
+        }
+    }
+    fn test_all(
+        patterns: &[Pattern],
+        compiler: &mut Compiler,
+        slot: u16,
+        path: &mut Vec<u8>,
+        fails: &mut Vec<usize>,
+    ) {
+        for (index, pattern) in patterns.iter().enumerate() {
+            path.push(index as u8);
+            pattern.test(compiler, slot, path, fails);
+            path.pop();
+        }
+    }
+    /// the names the pattern binds, and where their values are in the matched value
+    fn bindings(&self, path: &mut Vec<u8>, bindings: &mut Vec<(Token, Vec<u8>)>) {
+        let patterns = match self {
+            Pattern::Binding(name) => {
+                bindings.push((name.clone(), path.clone()));
+                return;
+            }
+            Pattern::Variant { values, .. } => values,
+            Pattern::Array { elements, .. } => elements,
+            Pattern::Wildcard(_) | Pattern::Literal(_) => return,
+        };
+        for (index, pattern) in patterns.iter().enumerate() {
+            path.push(index as u8);
+            pattern.bindings(path, bindings);
+            path.pop();
+        }
+    }
+}
+/// pushes the value at `path`, each step is an index into an array or a variant's values
+fn emit_path(compiler: &mut Compiler, slot: u16, path: &[u8]) {
+    compiler
+        .bytecode
+        .function
+        .chunk
+        .emit_op(OpCode::GetLocal(slot));
+    for index in path {
+        compiler.bytecode.write_get_index_op(*index);
+    }
+}
+
+impl CompileToBytecode for Match {
+    fn to_bytecode(&self, compiler: &mut Compiler) {
+        // the matched value gets a slot no name can reach, which the arm's value replaces
+        compiler.begin_scope();
+        self.value.to_bytecode(compiler);
+        let hidden = Token {
+            lexeme: String::new(),
+            ..self.keyword.clone()
+        };
+        compiler.add_local(hidden, None);
+        let slot = compiler.bytecode.local_count as u16 - 1;
+
+        let mut exits = Vec::new();
+        for arm in &self.arms {
+            let mut fails = Vec::new();
+            arm.pattern
+                .test(compiler, slot, &mut Vec::new(), &mut fails);
+
+            compiler.begin_scope();
+            let mut bindings = Vec::new();
+            arm.pattern.bindings(&mut Vec::new(), &mut bindings);
+            for (name, path) in &bindings {
+                emit_path(compiler, slot, path);
+                let definition = compiler.declare_symbol(name, SymbolKind::Variable);
+                compiler.add_local(name.clone(), definition);
+            }
+            let guard_fail = arm.guard.as_ref().map(|guard| {
+                guard.to_bytecode(compiler);
+                compiler.emit_pop_jump_if_false()
+            });
+            match &arm.body {
+                // blocks don't leave a value behind
+                body @ (Expression::Block(_) | Expression::If(_) | Expression::While(_)) => {
+                    body.to_bytecode(compiler);
+                    compiler.bytecode.write_void_op();
+                }
+                body => body.to_bytecode(compiler),
+            }
+            compiler
+                .bytecode
+                .function
+                .chunk
+                .emit_op(OpCode::SetLocalConsumes(slot));
+            compiler.end_scope();
+            exits.push(compiler.bytecode.function.chunk.code.len());
+            compiler
+                .bytecode
+                .function
+                .chunk
+                .emit_op(OpCode::JumpTo(0xfff));
+
+            if let Some(guard_fail) = guard_fail {
+                let here = compiler.bytecode.function.chunk.code.len();
+                compiler.bytecode.function.chunk.code[guard_fail] = OpCode::PopJumpToIfFalse(here);
+                for _ in &bindings {
+                    compiler.bytecode.function.chunk.emit_op(OpCode::Pop);
+                }
+            }
+            let next_arm = compiler.bytecode.function.chunk.code.len();
+            for fail in fails {
+                compiler.bytecode.function.chunk.code[fail] = OpCode::PopJumpToIfFalse(next_arm);
+            }
+        }
+
+        compiler
+            .bytecode
+            .function
+            .chunk
+            .emit_op(OpCode::GetLocal(slot));
+        compiler.bytecode.function.chunk.set_span(&self.keyword);
+        compiler.bytecode.write_no_match_op();
+
+        let end = compiler.bytecode.function.chunk.code.len();
+        for exit in exits {
+            compiler.bytecode.function.chunk.code[exit] = OpCode::JumpTo(end);
+        }
+        // the slot holds the value of the match now, so it stays on the stack
+        compiler.bytecode.scope_depth -= 1;
+        compiler.bytecode.local_count -= 1;
+        compiler
+            .bytecode
+            .locals
+            .truncate(compiler.bytecode.local_count);
+    }
+}
+impl AsExpr for Match {
+    fn to_expr(self) -> Expression {
+        Expression::Match(self)
+    }
+}
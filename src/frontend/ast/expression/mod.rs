@@ -2,11 +2,14 @@ use crate::{
     common::opcode::OpCode,
     frontend::{
         compiler::Compiler,
+        scanner::{Token, TokenKind},
     },
 };
 
 use self::{
-    binary_expr::BinaryExpr, block::Block, call_expr::Call, if_expr::If, while_expr::While,
+    array::ArrayLiteral, binary_expr::BinaryExpr, block::Block, call_expr::Call, if_expr::If,
+    lambda::Lambda, match_expr::Match, propagate::Propagate, property::Property,
+    struct_literal::StructLiteral, super_expr::Super, type_check::TypeCheck, while_expr::While,
 };
 
 use super::{
@@ -18,10 +21,18 @@ use super::{
 pub trait AsExpr {
     fn to_expr(self) -> Expression;
 }
+pub mod array;
 pub mod binary_expr;
 pub mod block;
 pub mod call_expr;
 pub mod if_expr;
+pub mod lambda;
+pub mod match_expr;
+pub mod propagate;
+pub mod property;
+pub mod struct_literal;
+pub mod super_expr;
+pub mod type_check;
 pub mod while_expr;
 
 #[derive(Debug, PartialEq, Clone)]
@@ -36,6 +47,16 @@ pub enum Expression {
     If(If),
     While(While),
     CallExpr(Call),
+    Property(Property),
+    /// the instance a method was called on
+    This(Token),
+    Super(Super),
+    StructLiteral(StructLiteral),
+    Array(ArrayLiteral),
+    Match(Match),
+    Propagate(Propagate),
+    TypeCheck(TypeCheck),
+    Lambda(Lambda),
     None
 }
 impl AsNode for Expression {
@@ -72,6 +93,22 @@ impl CompileToBytecode for Expression {
                 compiler.bytecode.write_void_op()
             }
             Expression::CallExpr(call_expr) => call_expr.to_bytecode(compiler),
+            Expression::Property(property) => property.to_bytecode(compiler),
+            // a hidden local of every method, see `FunctionDeclaration::compile_closure`
+            Expression::This(keyword) => Identifier {
+                value: Token {
+                    kind: TokenKind::Identifier,
+                    ..keyword.clone()
+                },
+            }
+            .to_bytecode(compiler),
+            Expression::Super(super_expr) => super_expr.to_bytecode(compiler),
+            Expression::StructLiteral(literal) => literal.to_bytecode(compiler),
+            Expression::Array(array) => array.to_bytecode(compiler),
+            Expression::Match(match_expr) => match_expr.to_bytecode(compiler),
+            Expression::Propagate(propagate) => propagate.to_bytecode(compiler),
+            Expression::TypeCheck(check) => check.to_bytecode(compiler),
+            Expression::Lambda(lambda) => lambda.to_bytecode(compiler),
             Expression::While(while_expr) => while_expr.to_bytecode(compiler),
             Expression::Grouping(inner) => inner.to_bytecode(compiler),
             Expression::Literal(literal) => literal.to_bytecode(compiler),
@@ -0,0 +1,53 @@
+use crate::{
+    cli_helper::{Diagnostic, ErrorCode},
+    common::opcode::OpCode,
+    frontend::{
+        ast::CompileToBytecode,
+        compiler::{Compiler, FunctionType},
+        scanner::{Span, Token},
+    },
+};
+
+use super::{AsExpr, Expression};
+
+/// `result?`, the value of an `Ok`, or the `Err` returned from the function
+#[derive(Debug, Clone, PartialEq)]
+pub struct Propagate {
+    pub value: Box<Expression>,
+    pub operator: Token,
+}
+impl CompileToBytecode for Propagate {
+    fn to_bytecode(&self, compiler: &mut Compiler) {
+        self.value.to_bytecode(compiler);
+        compiler.bytecode.function.chunk.set_span(&self.operator);
+        compiler.bytecode.write_is_err_op();
+        let ok = compiler.emit_pop_jump_if_false();
+        match compiler.bytecode.function_type {
+            // there is no caller to give the `Err` to, so it's thrown instead
+            FunctionType::Script => {
+                compiler.bytecode.function.chunk.set_span(&self.operator);
+                compiler.bytecode.write_throw_op();
+            }
+            FunctionType::Initializer => {
+                let diagnostic =
+                    Diagnostic::error(ErrorCode::ReturnFromInitializer, "Cannot use '?' in 'init'")
+                        .with_label(Span::from(&self.operator), "would return the `Err`")
+                        .with_help(
+                            "`init` always returns the new instance, match on the result instead",
+                        );
+                compiler.diagnostics.borrow_mut().push(diagnostic);
+            }
+            FunctionType::Function | FunctionType::Method => {
+                compiler.bytecode.write_return_op();
+            }
+        }
+        let here = compiler.bytecode.function.chunk.code.len();
+        compiler.bytecode.function.chunk.code[ok] = OpCode::PopJumpToIfFalse(here);
+        compiler.bytecode.write_get_index_op(0);
+    }
+}
+impl AsExpr for Propagate {
+    fn to_expr(self) -> Expression {
+        Expression::Propagate(self)
+    }
+}
@@ -0,0 +1,34 @@
+use crate::{
+    common::value::AsValue,
+    frontend::{ast::CompileToBytecode, compiler::Compiler, scanner::Token},
+};
+
+use super::{AsExpr, Expression};
+
+/// `object.name`, a field or a method of an instance
+#[derive(Debug, Clone, PartialEq)]
+pub struct Property {
+    pub object: Box<Expression>,
+    pub name: Token,
+}
+impl Property {
+    /// the property's name in the constant pool, which is what the property ops look it up by
+    pub fn name_constant(&self, compiler: &mut Compiler) -> u16 {
+        compiler
+            .bytecode
+            .make_constant(self.name.lexeme.as_str().to_value())
+    }
+}
+impl CompileToBytecode for Property {
+    fn to_bytecode(&self, compiler: &mut Compiler) {
+        self.object.to_bytecode(compiler);
+        let location = self.name_constant(compiler);
+        compiler.bytecode.function.chunk.set_span(&self.name);
+        compiler.bytecode.write_get_property_op(location);
+    }
+}
+impl AsExpr for Property {
+    fn to_expr(self) -> Expression {
+        Expression::Property(self)
+    }
+}
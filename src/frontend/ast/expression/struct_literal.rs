@@ -0,0 +1,39 @@
+use crate::{
+    common::value::AsValue,
+    frontend::{
+        ast::{identifier::Identifier, CompileToBytecode},
+        compiler::Compiler,
+        scanner::Token,
+    },
+};
+
+use super::{AsExpr, Expression};
+
+/// `Point { x: 1, y: 2 }`
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructLiteral {
+    pub name: Identifier,
+    pub fields: Vec<(Token, Expression)>,
+}
+impl CompileToBytecode for StructLiteral {
+    fn to_bytecode(&self, compiler: &mut Compiler) {
+        self.name.to_bytecode(compiler);
+        // the fields can be given in any order, so each value is pushed after its name
+        for (field, value) in &self.fields {
+            let location = compiler
+                .bytecode
+                .make_constant(field.lexeme.as_str().to_value());
+            compiler.bytecode.write_constant_op(location);
+            value.to_bytecode(compiler);
+        }
+        compiler.bytecode.function.chunk.set_span(&self.name.value);
+        compiler
+            .bytecode
+            .write_construct_op(self.fields.len() as u8);
+    }
+}
+impl AsExpr for StructLiteral {
+    fn to_expr(self) -> Expression {
+        Expression::StructLiteral(self)
+    }
+}
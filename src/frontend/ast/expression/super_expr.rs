@@ -0,0 +1,42 @@
+use crate::{
+    common::value::AsValue,
+    frontend::{
+        ast::{identifier::Identifier, CompileToBytecode},
+        compiler::Compiler,
+        scanner::{Token, TokenKind},
+    },
+};
+
+use super::{AsExpr, Expression};
+
+/// `super.method`, a method of the superclass bound to `this`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Super {
+    pub keyword: Token,
+    pub method: Token,
+}
+impl CompileToBytecode for Super {
+    fn to_bytecode(&self, compiler: &mut Compiler) {
+        // both are hidden locals, `super` is captured from the class declaration
+        for name in ["this", "super"] {
+            Identifier {
+                value: Token {
+                    kind: TokenKind::Identifier,
+                    lexeme: name.to_string(),
+                    ..self.keyword.clone()
+                },
+            }
+            .to_bytecode(compiler);
+        }
+        let location = compiler
+            .bytecode
+            .make_constant(self.method.lexeme.as_str().to_value());
+        compiler.bytecode.function.chunk.set_span(&self.method);
+        compiler.bytecode.write_get_super_op(location);
+    }
+}
+impl AsExpr for Super {
+    fn to_expr(self) -> Expression {
+        Expression::Super(self)
+    }
+}
@@ -0,0 +1,25 @@
+use crate::frontend::{
+    ast::{annotation::Annotation, CompileToBytecode},
+    compiler::Compiler,
+};
+
+use super::{AsExpr, Expression};
+
+/// a value the type checker couldn't prove is of the annotated type, so it's checked when
+/// it's run instead
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeCheck {
+    pub value: Box<Expression>,
+    pub annotation: Annotation,
+}
+impl CompileToBytecode for TypeCheck {
+    fn to_bytecode(&self, compiler: &mut Compiler) {
+        self.value.to_bytecode(compiler);
+        self.annotation.check(compiler);
+    }
+}
+impl AsExpr for TypeCheck {
+    fn to_expr(self) -> Expression {
+        Expression::TypeCheck(self)
+    }
+}
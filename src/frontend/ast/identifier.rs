@@ -1,11 +1,14 @@
-
-
-use colored::Colorize;
+use std::rc::Rc;
 
 use crate::{
-    common::{opcode::OpCode, value::AsValue},
+    cli_helper::SourceFile,
+    common::{
+        enums::{result_type, result_variant},
+        opcode::{GlobalSlot, OpCode},
+        value::Value,
+    },
     frontend::{
-        compiler::Compiler,
+        compiler::{global::Global, Compiler},
         scanner::{Token, TokenKind},
     },
 };
@@ -22,33 +25,24 @@ pub struct Identifier {
 
 impl CompileToBytecode for Identifier {
     fn to_bytecode(&self, compiler: &mut Compiler) {
+        compiler.reference_symbol(&self.value);
         let local = compiler.resolve_local(&self.value);
         #[allow(unused_assignments)]
         let mut op: OpCode = OpCode::Nop;
         if let Some(arg) = local {
             op = OpCode::GetLocal(arg as u16);
-        } else if let Some(arg) = {
-            let tmp = compiler.resolve_up_value(&self.value);
-            tmp
-        } {
+        } else if let Some(arg) = compiler.resolve_up_value(&self.value) {
             op = OpCode::GetUpValue(arg as u16);
+        } else if let Some(index) = result_variant(&self.value.lexeme) {
+            // `Ok` and `Err` are built in, only a local can shadow them
+            let constructor = Value::VariantConstructor(result_type(), index);
+            op = OpCode::Constant(compiler.bytecode.make_constant(constructor));
         } else {
-            let function = &mut compiler.bytecode.function;
-            let lexeme = self.value.lexeme.clone();
-            if compiler.bytecode.globals.contains(&lexeme) {
-                let arg = function.chunk.emit_value(lexeme.to_value());
-                op = OpCode::GetGlobal(arg)
-            } else {
-                compiler.diagnostics.borrow_mut().log(
-                    Some(&self.value.position),
-                    "Compiler",
-                    format!("Undefined variable '{}' \n", self.value.lexeme)
-                        .bright_red()
-                        .to_string(),
-                )
-            }
+            // late bound, `Compiler::check_globals` reports it if it's never declared
+            op = OpCode::GetGlobal(compiler.resolve_global(&self.value));
         }
         let function = &mut compiler.bytecode.function;
+        function.chunk.set_span(&self.value);
         function.chunk.emit_op(op);
     }
 }
@@ -61,8 +55,6 @@ impl<'a> Compiler<'a> {
             .enumerate()
         {
             if up_value.index == index as u8 && up_value.is_local == is_local {
-                dbg!(up_value);
-
                 return Some(i);
             }
         }
@@ -81,6 +73,10 @@ impl<'a> Compiler<'a> {
         }
     }
     pub fn resolve_up_value(&mut self, token: &Token) -> Option<usize> {
+        // a module's top level can't capture the locals of whoever imported it
+        if self.namespace.is_some() {
+            return None;
+        }
         let enclosing = self.enclosing.as_mut()?.get_compiler();
         let local = enclosing.resolve_local(token);
 
@@ -92,12 +88,44 @@ impl<'a> Compiler<'a> {
 
         let upvalue = enclosing.resolve_up_value(token);
         if let Some(upvalue) = upvalue {
-            dbg!(upvalue);
             return self.add_up_value(upvalue, false);
         }
 
         None
     }
+    /// globals live in the outermost compiler, so every function agrees on their slots
+    pub fn globals(&mut self) -> &mut Vec<Global> {
+        match self.enclosing.as_mut() {
+            Some(enclosing) => enclosing.get_compiler().globals(),
+            None => &mut self.bytecode.globals,
+        }
+    }
+    /// returns the slot of a global, giving it one if this is the first time it's used,
+    /// past `MAX_GLOBALS` this gives back 0 but `Compiler::check_globals` fails the compilation
+    pub fn resolve_global(&mut self, name: &Token) -> GlobalSlot {
+        let name = self.qualify(name);
+        let file = self.namespace().file.clone();
+        let slot = self.global_slot(name, file);
+        GlobalSlot::try_from(slot).unwrap_or(0)
+    }
+    fn global_slot(&mut self, name: Token, file: Option<Rc<SourceFile>>) -> usize {
+        if let Some(enclosing) = self.enclosing.as_mut() {
+            return enclosing.get_compiler().global_slot(name, file);
+        }
+        let bytecode = &mut self.bytecode;
+        if let Some(slot) = bytecode.global_slots.get(&name.lexeme) {
+            return *slot;
+        }
+        let slot = bytecode.globals.len();
+        bytecode.global_slots.insert(name.lexeme.clone(), slot);
+        bytecode.globals.push(Global {
+            name,
+            defined: false,
+            definition: None,
+            file,
+        });
+        slot
+    }
     pub fn resolve_local(&mut self, name: &Token) -> Option<usize> {
         assert_eq!(name.kind, TokenKind::Identifier);
         for (i, token) in self.bytecode.locals[0..self.bytecode.local_count]
@@ -49,16 +49,17 @@ impl From<Literal> for Value {
 
 impl CompileToBytecode for Literal {
     fn to_bytecode(&self, compiler: &mut Compiler) {
-        let function = &mut compiler.bytecode.function;
+        let bytecode = &mut compiler.bytecode;
+        bytecode.function.chunk.set_span(&self.1);
         let pos = match &self.0 {
-            Literals::Void => function.chunk.emit_value(Value::Void),
-            Literals::Number(number) => function.chunk.emit_value(Value::Number(*number)),
-            Literals::String(string) => function.chunk.emit_value(string.to_value()),
+            Literals::Void => bytecode.make_constant(Value::Void),
+            Literals::Number(number) => bytecode.make_constant(Value::Number(*number)),
+            Literals::String(string) => bytecode.make_constant(string.to_value()),
             Literals::Bool(bool) => {
                 if *bool {
-                    function.chunk.emit_op(OpCode::True)
+                    bytecode.function.chunk.emit_op(OpCode::True)
                 } else {
-                    function.chunk.emit_op(OpCode::False)
+                    bytecode.function.chunk.emit_op(OpCode::False)
                 };
                 return;
             }
@@ -1,6 +1,7 @@
 use self::expression::Expression;
 
 use super::compiler::Compiler;
+pub mod annotation;
 pub mod declaration;
 pub mod expression;
 pub mod identifier;
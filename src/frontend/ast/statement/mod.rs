@@ -1,6 +1,8 @@
 use crate::{common::opcode::OpCode, frontend::compiler::Compiler};
 pub mod return_stmt;
-use self::return_stmt::ReturnStmt;
+pub mod throw_stmt;
+pub mod try_stmt;
+use self::{return_stmt::ReturnStmt, throw_stmt::ThrowStmt, try_stmt::TryStmt};
 
 use super::{
     expression::{AsExpr, Expression},
@@ -14,6 +16,8 @@ pub enum Statement {
     AssertEq(Expression, Expression),
     AssertNe(Expression, Expression),
     Return(ReturnStmt),
+    Throw(ThrowStmt),
+    Try(TryStmt),
 }
 
 impl AsNode for Statement {
@@ -31,9 +35,10 @@ impl AsExpr for Statement {
 }
 impl CompileToBytecode for Statement {
     fn to_bytecode(&self, compiler: &mut Compiler) {
-        compiler.bytecode.compiling_statement = true;
         match self {
             Statement::Return(return_stmt) => return_stmt.to_bytecode(compiler),
+            Statement::Throw(throw_stmt) => throw_stmt.to_bytecode(compiler),
+            Statement::Try(try_stmt) => try_stmt.to_bytecode(compiler),
             Statement::Expression(expr) => {
                 compiler.bytecode.start_expr(expr);
                 match &expr {
@@ -68,7 +73,6 @@ impl CompileToBytecode for Statement {
                 compiler.bytecode.function.chunk.emit_op(OpCode::AssertNe)
             }
         }
-        compiler.bytecode.compiling_statement = false;
     }
 }
 
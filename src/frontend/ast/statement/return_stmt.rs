@@ -1,35 +1,39 @@
 use crate::{
+    cli_helper::{Diagnostic, ErrorCode},
     common::opcode::OpCode,
     frontend::{
-        ast::{
-            expression::Expression,
-            literal::{Literal, Literals},
-            CompileToBytecode,
-        },
-        scanner::Token,
+        ast::{expression::Expression, CompileToBytecode},
+        compiler::FunctionType,
+        scanner::{Span, Token},
     },
 };
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct ReturnStmt {
     pub expr: Option<Expression>,
+    pub keyword: Token,
 }
 
 impl CompileToBytecode for ReturnStmt {
     fn to_bytecode(&self, compiler: &mut crate::frontend::compiler::Compiler) {
-        self.expr
-            .as_ref()
-            .unwrap_or({
-                let this = Literal(
-                    Literals::Void,
-                    Token {
-                        ..Default::default()
-                    },
-                );
-                &Expression::Literal(this)
-            })
-            .to_bytecode(compiler);
-        compiler.bytecode.function.chunk.emit_op(OpCode::Return);
-        compiler.bytecode.returned = true;
+        let initializer = compiler.bytecode.function_type == FunctionType::Initializer;
+        match &self.expr {
+            Some(expr) if !initializer => {
+                expr.to_bytecode(compiler);
+                compiler.bytecode.function.chunk.emit_op(OpCode::Return);
+            }
+            _ => {
+                if self.expr.is_some() {
+                    let diagnostic = Diagnostic::error(
+                        ErrorCode::ReturnFromInitializer,
+                        "Cannot return a value from 'init'",
+                    )
+                    .with_label(Span::from(&self.keyword), "returns a value")
+                    .with_help("`init` always returns the new instance, use a bare `return;`");
+                    compiler.diagnostics.borrow_mut().push(diagnostic);
+                }
+                compiler.write_implicit_return();
+            }
+        }
     }
 }
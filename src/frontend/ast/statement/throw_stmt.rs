@@ -0,0 +1,20 @@
+use crate::frontend::{
+    ast::{expression::Expression, CompileToBytecode},
+    compiler::Compiler,
+    scanner::Token,
+};
+
+/// `throw value;`, which unwinds to the innermost `try`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThrowStmt {
+    pub keyword: Token,
+    pub value: Expression,
+}
+
+impl CompileToBytecode for ThrowStmt {
+    fn to_bytecode(&self, compiler: &mut Compiler) {
+        self.value.to_bytecode(compiler);
+        compiler.bytecode.function.chunk.set_span(&self.keyword);
+        compiler.bytecode.write_throw_op();
+    }
+}
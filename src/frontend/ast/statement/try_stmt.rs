@@ -0,0 +1,55 @@
+use crate::{
+    common::opcode::OpCode,
+    frontend::{
+        ast::{expression::block::Block, CompileToBytecode},
+        compiler::{symbols::SymbolKind, Compiler},
+        scanner::Token,
+    },
+};
+
+/// `try { } catch error { }`, the name of the error can be left out
+#[derive(Debug, Clone, PartialEq)]
+pub struct TryStmt {
+    pub keyword: Token,
+    pub block: Block,
+    pub name: Option<Token>,
+    pub catch: Block,
+}
+
+impl CompileToBytecode for TryStmt {
+    fn to_bytecode(&self, compiler: &mut Compiler) {
+        let handler = compiler.bytecode.function.chunk.code.len();
+        compiler.bytecode.function.chunk.emit_op(OpCode::Try(0xfff));
+        self.block.to_bytecode(compiler);
+        compiler.bytecode.write_end_try_op();
+        let skip_catch = compiler.bytecode.function.chunk.code.len();
+        compiler
+            .bytecode
+            .function
+            .chunk
+            .emit_op(OpCode::JumpTo(0xfff));
+
+        // the vm pushes the error right where the `try` started, which is the next local
+        let catch = compiler.bytecode.function.chunk.code.len();
+        compiler.bytecode.function.chunk.code[handler] = OpCode::Try(catch);
+        compiler.begin_scope();
+        match &self.name {
+            Some(name) => {
+                let definition = compiler.declare_symbol(name, SymbolKind::Variable);
+                compiler.add_local(name.clone(), definition);
+            }
+            None => {
+                let hidden = Token {
+                    lexeme: String::new(),
+                    ..self.keyword.clone()
+                };
+                compiler.add_local(hidden, None);
+            }
+        }
+        self.catch.to_bytecode(compiler);
+        compiler.end_scope();
+
+        let end = compiler.bytecode.function.chunk.code.len();
+        compiler.bytecode.function.chunk.code[skip_catch] = OpCode::JumpTo(end);
+    }
+}
@@ -1,5 +1,7 @@
 
 
+use std::collections::HashMap;
+
 use crate::common::{
     function::Function,
     opcode::{OpCode, StackInfo},
@@ -7,7 +9,7 @@ use crate::common::{
 
 use super::{
     ast::expression::Expression,
-    compiler::{local::Local, FunctionType},
+    compiler::{global::Global, local::Local, FunctionType},
 };
 #[derive(Debug, Default, Clone, Copy)]
 pub struct Upvalue {
@@ -24,18 +26,20 @@ pub struct Bytecode {
     pub local_count: usize,
     pub emit_after_block: Vec<OpCode>,
     pub function_type: FunctionType,
-    pub compiling_statement: bool,
     pub current_expr: Option<*const Expression>,
-    pub returned: bool,
     pub eliminated: bool,
     pub upvalues: Vec<Upvalue>,
-    pub globals: Vec<String>,
+    /// only filled in for the outermost compiler, see `Compiler::globals`
+    pub globals: Vec<Global>,
+    /// the slot of each global in `globals`, by name
+    pub global_slots: HashMap<String, usize>,
 }
 
 impl Default for Bytecode {
     fn default() -> Self {
         Self {
             globals: Vec::new(),
+            global_slots: HashMap::new(),
             stack_info: Vec::new(),
             function: Default::default(),
             scope_depth: Default::default(),
@@ -43,8 +47,6 @@ impl Default for Bytecode {
             local_count: Default::default(),
             emit_after_block: Default::default(),
             function_type: Default::default(),
-            compiling_statement: Default::default(),
-            returned: Default::default(),
             current_expr: Default::default(),
 
             eliminated: Default::default(),
@@ -0,0 +1,599 @@
+/// the type checker, which compares the annotations with what it can infer before any
+/// bytecode is emitted
+use std::{collections::HashSet, mem};
+
+use crate::{
+    backend::vm::natives::native_index,
+    cli_helper::{Diagnostic, ErrorCode},
+    common::types::Type,
+};
+
+use super::{
+    ast::{
+        annotation::Annotation,
+        declaration::{function::FunctionDeclaration, Declaration},
+        expression::{
+            block::Block, call_expr::Call, match_expr::Pattern, type_check::TypeCheck, AsExpr,
+            Expression,
+        },
+        literal::{Literal, Literals},
+        node::Node,
+        statement::Statement,
+    },
+    file::FileNode,
+    linter::expr_token,
+    scanner::{Token, TokenKind},
+};
+
+/// reports every value that can't be what its annotation says, and marks the ones that
+/// might not be to be checked when they're run
+pub fn check_file(file: &mut FileNode) -> Vec<Diagnostic> {
+    // a first pass finds every name that's assigned to, whose type can change
+    let mut first = Checker {
+        dry_run: true,
+        ..Default::default()
+    };
+    first.nodes(&mut file.nodes);
+    let mut checker = Checker {
+        assigned: first.assigned,
+        ..Default::default()
+    };
+    checker.nodes(&mut file.nodes);
+    checker.diagnostics
+}
+
+#[derive(Default)]
+struct Checker {
+    /// the innermost scope is last, and the file's top level first
+    scopes: Vec<Vec<Variable>>,
+    /// a variable without an annotation only keeps the type of its initializer if it's
+    /// never assigned to
+    assigned: HashSet<String>,
+    /// what each function being checked says it returns, innermost last
+    returns: Vec<Option<Annotation>>,
+    diagnostics: Vec<Diagnostic>,
+    /// only finds the names that are assigned to, without changing the ast
+    dry_run: bool,
+}
+
+struct Variable {
+    name: String,
+    kind: Type,
+    /// every assignment has to match the annotation, if there is one
+    annotation: Option<Annotation>,
+    /// only set for a function that is never assigned to, so calls can be checked
+    signature: Option<Signature>,
+}
+
+#[derive(Clone)]
+struct Signature {
+    parameters: Vec<Option<Annotation>>,
+    /// the names of the parameters, so named arguments can be put in order
+    names: Vec<String>,
+    /// how many parameters don't have a default
+    required: usize,
+    returns: Type,
+}
+
+impl Checker {
+    fn declare(&mut self, name: &Token, kind: Type, annotation: Option<Annotation>) {
+        if self.scopes.is_empty() {
+            self.scopes.push(Vec::new());
+        }
+        let scope = self.scopes.last_mut().unwrap();
+        scope.push(Variable {
+            name: name.lexeme.clone(),
+            kind,
+            annotation,
+            signature: None,
+        });
+    }
+
+    fn lookup(&self, name: &Token) -> Option<&Variable> {
+        self.scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.iter().rev())
+            .find(|variable| variable.name == name.lexeme)
+    }
+
+    fn mismatch(&mut self, annotation: &Annotation, found: Type, token: Option<&Token>) {
+        let mut diagnostic = Diagnostic::error(ErrorCode::MismatchedTypes, "Mismatched types");
+        if let Some(token) = token {
+            diagnostic = diagnostic.with_label(
+                token.into(),
+                format!("expected `{}`, found `{found}`", annotation.kind),
+            );
+        }
+        let diagnostic =
+            diagnostic.with_secondary_label((&annotation.name).into(), "expected because of this");
+        self.diagnostics.push(diagnostic);
+    }
+
+    /// a value that can't be of the annotated type is an error, and one that might not be is
+    /// checked when it's run
+    fn expect(&mut self, annotation: &Annotation, value: &mut Expression, found: Type) {
+        if self.dry_run {
+            return;
+        }
+        if !annotation.kind.accepts(found) {
+            let token = expr_token(value).cloned();
+            self.mismatch(annotation, found, token.as_ref());
+        } else if found == Type::Any && annotation.kind != Type::Any {
+            let unchecked = mem::replace(value, Expression::None);
+            *value = TypeCheck {
+                value: Box::new(unchecked),
+                annotation: annotation.clone(),
+            }
+            .to_expr();
+        }
+    }
+
+    fn nodes(&mut self, nodes: &mut [Node]) {
+        for node in nodes {
+            self.node(node);
+        }
+    }
+
+    fn block(&mut self, block: &mut Block) {
+        self.scopes.push(Vec::new());
+        self.nodes(&mut block.declarations);
+        self.scopes.pop();
+    }
+
+    fn node(&mut self, node: &mut Node) -> Type {
+        match node {
+            Node::Expression(expr) => self.expr(expr),
+            Node::Literal(literal) => literal_type(literal),
+            Node::Identifier(identifier) => self
+                .lookup(&identifier.value)
+                .map_or(Type::Any, |variable| variable.kind),
+            Node::Statement(statement) => {
+                self.statement(statement);
+                Type::Any
+            }
+            Node::Declaration(declaration) => {
+                self.declaration(declaration);
+                Type::Any
+            }
+            Node::None | Node::Empty | Node::Emit(_) => Type::Any,
+        }
+    }
+
+    fn statement(&mut self, statement: &mut Statement) {
+        match statement {
+            Statement::Expression(expr) => {
+                self.expr(expr);
+            }
+            Statement::Print(node) => {
+                self.node(node);
+            }
+            Statement::AssertEq(lhs, rhs) | Statement::AssertNe(lhs, rhs) => {
+                self.expr(lhs);
+                self.expr(rhs);
+            }
+            Statement::Return(return_stmt) => {
+                let returns = self.returns.last().cloned().flatten();
+                match (&mut return_stmt.expr, returns) {
+                    (Some(expr), Some(annotation)) => {
+                        let found = self.expr(expr);
+                        self.expect(&annotation, expr, found);
+                    }
+                    (Some(expr), None) => {
+                        self.expr(expr);
+                    }
+                    (None, Some(annotation)) if !annotation.kind.accepts(Type::Void) => {
+                        self.mismatch(&annotation, Type::Void, Some(&return_stmt.keyword));
+                    }
+                    (None, _) => {}
+                }
+            }
+            Statement::Throw(throw_stmt) => {
+                self.expr(&mut throw_stmt.value);
+            }
+            Statement::Try(try_stmt) => {
+                self.block(&mut try_stmt.block);
+                self.scopes.push(Vec::new());
+                if let Some(name) = &try_stmt.name {
+                    self.declare(name, Type::Any, None);
+                }
+                self.nodes(&mut try_stmt.catch.declarations);
+                self.scopes.pop();
+            }
+        }
+    }
+
+    fn declaration(&mut self, declaration: &mut Declaration) {
+        match declaration {
+            Declaration::VariableDeclaration(declaration) => {
+                let found = self.expr(&mut declaration.intializer);
+                let name = &declaration.identifier.value;
+                let kind = match &declaration.annotation {
+                    // `let x: num;` starts out without a value
+                    Some(annotation) if declaration.intializer == Expression::None => {
+                        annotation.kind
+                    }
+                    Some(annotation) => {
+                        self.expect(annotation, &mut declaration.intializer, found);
+                        annotation.kind
+                    }
+                    None if self.assigned.contains(&name.lexeme) => Type::Any,
+                    None => found,
+                };
+                self.declare(name, kind, declaration.annotation.clone());
+            }
+            Declaration::FunctionDeclaration(function) => {
+                // declared before the body, so it can call itself
+                self.declare(&function.name.value, Type::Any, None);
+                if !self.assigned.contains(&function.name.value.lexeme) {
+                    let variable = self.scopes.last_mut().unwrap().last_mut().unwrap();
+                    variable.signature = Some(Signature {
+                        // the arguments collected by a rest parameter aren't checked
+                        parameters: function
+                            .parameters
+                            .iter()
+                            .filter(|parameter| !parameter.rest)
+                            .map(|parameter| parameter.annotation.clone())
+                            .collect(),
+                        names: function
+                            .parameters
+                            .iter()
+                            .filter(|parameter| !parameter.rest)
+                            .map(|parameter| parameter.name.value.lexeme.clone())
+                            .collect(),
+                        required: function
+                            .parameters
+                            .iter()
+                            .filter(|parameter| !parameter.rest && parameter.default.is_none())
+                            .count(),
+                        returns: function
+                            .returns
+                            .as_ref()
+                            .map_or(Type::Any, |annotation| annotation.kind),
+                    });
+                }
+                self.function(function, true);
+            }
+            Declaration::Class(class) => {
+                self.declare(&class.name.value, Type::Any, None);
+                for method in &mut class.methods {
+                    // `init` always returns the instance
+                    let returns = method.name.value.lexeme != "init";
+                    self.function(method, returns);
+                }
+            }
+            Declaration::Struct(declaration) => {
+                self.declare(&declaration.name.value, Type::Any, None)
+            }
+            Declaration::Destructure(destructure) => {
+                self.expr(&mut destructure.initializer);
+                for field in &destructure.fields {
+                    self.declare(field, Type::Any, None);
+                }
+            }
+            Declaration::Enum(declaration) => {
+                self.declare(&declaration.name.value, Type::Any, None)
+            }
+            Declaration::Use(use_declaration) => {
+                for name in &use_declaration.names {
+                    self.declare(&name.value, Type::Any, None);
+                }
+            }
+        }
+    }
+
+    fn function(&mut self, function: &mut FunctionDeclaration, returns: bool) {
+        self.scopes.push(Vec::new());
+        for parameter in &mut function.parameters {
+            // a default can use the parameters before it
+            if let Some(default) = &mut parameter.default {
+                let found = self.expr(default);
+                if let Some(annotation) = &parameter.annotation {
+                    self.expect(annotation, default, found);
+                }
+            }
+            let kind = match &parameter.annotation {
+                Some(annotation) => annotation.kind,
+                None if parameter.rest => Type::Array,
+                None => Type::Any,
+            };
+            self.declare(&parameter.name.value, kind, parameter.annotation.clone());
+        }
+        self.returns
+            .push(function.returns.clone().filter(|_| returns));
+        self.nodes(&mut function.block.declarations);
+        self.returns.pop();
+        self.scopes.pop();
+    }
+
+    /// reports the named arguments that don't match a parameter, and puts them in order when
+    /// none are left out in between and that doesn't change what is evaluated first, otherwise
+    /// they're matched up when the call is run
+    fn order_arguments(
+        &mut self,
+        callee: &Token,
+        call: &mut Call,
+        arguments: &mut Vec<Type>,
+        named: Vec<Type>,
+        signature: &Signature,
+    ) {
+        if self.dry_run {
+            return;
+        }
+        let positional = call.parameters.len();
+        let names = &signature.names;
+        // which named argument goes to each parameter after the positional ones
+        let mut slots: Vec<Option<usize>> = vec![None; names.len().saturating_sub(positional)];
+        let mut valid = true;
+        for (index, (name, _)) in call.named.iter().enumerate() {
+            let (message, label) = match names.iter().position(|other| *other == name.lexeme) {
+                Some(parameter)
+                    if parameter >= positional && slots[parameter - positional].is_none() =>
+                {
+                    slots[parameter - positional] = Some(index);
+                    continue;
+                }
+                Some(_) => (
+                    format!("'{}' is given more than once", name.lexeme),
+                    "given already",
+                ),
+                None => (
+                    format!(
+                        "'{}' has no parameter named '{}'",
+                        callee.lexeme, name.lexeme
+                    ),
+                    "unknown argument",
+                ),
+            };
+            let diagnostic = Diagnostic::error(ErrorCode::UnknownArgument, message)
+                .with_label(name.into(), label);
+            self.diagnostics.push(diagnostic);
+            valid = false;
+        }
+        for (offset, slot) in slots.iter().enumerate() {
+            let index = positional + offset;
+            if slot.is_none() && index < signature.required {
+                let diagnostic = Diagnostic::error(
+                    ErrorCode::MissingArgument,
+                    format!(
+                        "'{}' is missing the argument '{}'",
+                        callee.lexeme, names[index]
+                    ),
+                )
+                .with_label(callee.into(), "called here");
+                self.diagnostics.push(diagnostic);
+                valid = false;
+            }
+        }
+        let given = slots
+            .iter()
+            .rposition(Option::is_some)
+            .map_or(0, |last| last + 1);
+        if !valid || slots[..given].iter().any(Option::is_none) {
+            return;
+        }
+        let in_order = slots[..given].iter().flatten().is_sorted();
+        if !in_order && !call.named.iter().all(|(_, value)| is_pure(value)) {
+            return;
+        }
+        let mut named: Vec<Option<(Expression, Type)>> = mem::take(&mut call.named)
+            .into_iter()
+            .zip(named)
+            .map(|((_, value), kind)| Some((value, kind)))
+            .collect();
+        for slot in slots.into_iter().take(given).flatten() {
+            let (value, kind) = named[slot].take().unwrap();
+            call.parameters.push(value);
+            arguments.push(kind);
+        }
+    }
+
+    fn pattern(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::Binding(name) => self.declare(name, Type::Any, None),
+            Pattern::Variant { values, .. } => values.iter().for_each(|value| self.pattern(value)),
+            Pattern::Array { elements, .. } => {
+                elements.iter().for_each(|element| self.pattern(element))
+            }
+            Pattern::Wildcard(_) | Pattern::Literal(_) => {}
+        }
+    }
+
+    fn expr(&mut self, expr: &mut Expression) -> Type {
+        match expr {
+            Expression::Literal(literal) => literal_type(literal),
+            Expression::Identifier(identifier) => self
+                .lookup(&identifier.value)
+                .map_or(Type::Any, |variable| variable.kind),
+            Expression::Binary(binary) if binary.op.kind == TokenKind::Equal => {
+                let found = self.node(&mut binary.rhs);
+                let name = match binary.lhs.as_mut() {
+                    Node::Identifier(identifier)
+                    | Node::Expression(Expression::Identifier(identifier)) => {
+                        identifier.value.clone()
+                    }
+                    // the object of a property is read, not assigned
+                    lhs => {
+                        self.node(lhs);
+                        return found;
+                    }
+                };
+                self.assigned.insert(name.lexeme.clone());
+                let Some(annotation) = self
+                    .lookup(&name)
+                    .and_then(|variable| variable.annotation.clone())
+                else {
+                    return found;
+                };
+                let mut value = mem::replace(binary.rhs.as_mut(), Node::None).to_expr();
+                self.expect(&annotation, &mut value, found);
+                *binary.rhs = Node::Expression(value);
+                annotation.kind
+            }
+            Expression::Binary(binary) => {
+                let lhs = self.node(&mut binary.lhs);
+                let rhs = self.node(&mut binary.rhs);
+                match binary.op.kind {
+                    // numbers are added and strings joined, so either operand says what the
+                    // sum is if it's run without an error
+                    TokenKind::Plus => {
+                        // the last pass decides, the first may have guessed wrong
+                        binary.operands = if lhs == rhs { lhs } else { Type::Any };
+                        match (lhs, rhs) {
+                            (Type::Number | Type::String, Type::Any) => lhs,
+                            (Type::Any, Type::Number | Type::String) => rhs,
+                            _ if lhs == rhs => lhs,
+                            _ => Type::Any,
+                        }
+                    }
+                    TokenKind::Dash | TokenKind::Star | TokenKind::Slash => Type::Number,
+                    _ => Type::Bool,
+                }
+            }
+            Expression::Grouping(inner) => self.expr(inner),
+            Expression::Not(inner) => {
+                self.expr(inner);
+                Type::Bool
+            }
+            Expression::Negate(inner) => {
+                self.expr(inner);
+                Type::Number
+            }
+            Expression::Block(block) => {
+                self.block(block);
+                Type::Any
+            }
+            Expression::If(if_expr) => {
+                self.expr(&mut if_expr.predicate);
+                self.block(&mut if_expr.then);
+                if let Some(else_block) = &mut if_expr.else_block {
+                    self.block(else_block);
+                }
+                Type::Any
+            }
+            Expression::While(while_expr) => {
+                self.expr(&mut while_expr.predicate);
+                self.block(&mut while_expr.block);
+                Type::Any
+            }
+            Expression::CallExpr(call) => {
+                let mut arguments: Vec<Type> = call
+                    .parameters
+                    .iter_mut()
+                    .map(|argument| self.expr(argument))
+                    .collect();
+                let named: Vec<Type> = call
+                    .named
+                    .iter_mut()
+                    .map(|(_, value)| self.expr(value))
+                    .collect();
+                let Expression::Identifier(callee) = call.expr.as_ref() else {
+                    self.expr(&mut call.expr);
+                    return Type::Any;
+                };
+                let callee = callee.value.clone();
+                if native_index(&callee.lexeme).is_some() {
+                    if let Some((name, _)) = call.named.first().filter(|_| !self.dry_run) {
+                        let diagnostic = Diagnostic::error(
+                            ErrorCode::UnknownArgument,
+                            format!("'{}' doesn't take named arguments", callee.lexeme),
+                        )
+                        .with_label(name.into(), "named here");
+                        self.diagnostics.push(diagnostic);
+                    }
+                    return match callee.lexeme.as_str() {
+                        "to_str" => Type::String,
+                        _ => Type::Any,
+                    };
+                }
+                let Some(signature) = self
+                    .lookup(&callee)
+                    .and_then(|variable| variable.signature.clone())
+                else {
+                    return Type::Any;
+                };
+                if !call.named.is_empty() {
+                    self.order_arguments(&callee, call, &mut arguments, named, &signature);
+                }
+                let returns = signature.returns;
+                let parameters = signature.parameters;
+                for ((argument, found), parameter) in
+                    call.parameters.iter().zip(arguments).zip(parameters)
+                {
+                    let Some(annotation) = parameter else {
+                        continue;
+                    };
+                    if !annotation.kind.accepts(found) {
+                        let token = expr_token(argument).cloned();
+                        self.mismatch(&annotation, found, token.as_ref());
+                    }
+                }
+                returns
+            }
+            Expression::Property(property) => {
+                self.expr(&mut property.object);
+                Type::Any
+            }
+            Expression::StructLiteral(literal) => {
+                for (_, value) in &mut literal.fields {
+                    self.expr(value);
+                }
+                Type::Any
+            }
+            Expression::Array(array) => {
+                for element in &mut array.elements {
+                    self.expr(element);
+                }
+                Type::Array
+            }
+            Expression::Match(match_expr) => {
+                self.expr(&mut match_expr.value);
+                for arm in &mut match_expr.arms {
+                    // the names an arm binds are only in scope in that arm
+                    self.scopes.push(Vec::new());
+                    self.pattern(&arm.pattern);
+                    if let Some(guard) = &mut arm.guard {
+                        self.expr(guard);
+                    }
+                    self.expr(&mut arm.body);
+                    self.scopes.pop();
+                }
+                Type::Any
+            }
+            Expression::Propagate(propagate) => {
+                self.expr(&mut propagate.value);
+                Type::Any
+            }
+            Expression::TypeCheck(check) => {
+                self.expr(&mut check.value);
+                check.annotation.kind
+            }
+            Expression::Lambda(lambda) => {
+                self.function(&mut lambda.function, true);
+                Type::Any
+            }
+            Expression::This(_) | Expression::Super(_) | Expression::None => Type::Any,
+        }
+    }
+}
+
+fn literal_type(literal: &Literal) -> Type {
+    match literal.0 {
+        Literals::Number(_) => Type::Number,
+        Literals::String(_) => Type::String,
+        Literals::Bool(_) => Type::Bool,
+        Literals::Void => Type::Void,
+    }
+}
+
+/// whether evaluating `expr` can't be observed, so it can be moved past other expressions
+fn is_pure(expr: &Expression) -> bool {
+    match expr {
+        Expression::Literal(_)
+        | Expression::Identifier(_)
+        | Expression::Lambda(_)
+        | Expression::This(_) => true,
+        Expression::Grouping(inner) => is_pure(inner),
+        _ => false,
+    }
+}
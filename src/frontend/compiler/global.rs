@@ -0,0 +1,19 @@
+use std::rc::Rc;
+
+use crate::{cli_helper::SourceFile, common::opcode::GlobalSlot, frontend::scanner::Token};
+
+/// the most globals a program can have, one for every `GlobalSlot`
+pub const MAX_GLOBALS: usize = GlobalSlot::MAX as usize + 1;
+
+/// a global variable, resolved to a dense slot at compile time
+#[derive(Debug, Default, Clone)]
+pub struct Global {
+    /// the first token that referred to this global
+    pub name: Token,
+    /// false until a declaration (or assignment) for the global has been compiled
+    pub defined: bool,
+    /// the symbol of the first declaration, if symbols are being collected
+    pub definition: Option<usize>,
+    /// the module it was first referred to in
+    pub file: Option<Rc<SourceFile>>,
+}
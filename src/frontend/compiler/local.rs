@@ -5,6 +5,8 @@ pub struct Local {
     pub name: Token,
     pub depth: u8,
     pub is_captured: bool,
+    /// the symbol this local was declared as, if symbols are being collected
+    pub definition: Option<usize>,
 }
 impl Local {
     pub fn new() -> Local {
@@ -12,6 +14,7 @@ impl Local {
             name: Token::default(),
             depth: 0,
             is_captured: false,
+            definition: None,
         }
     }
 }
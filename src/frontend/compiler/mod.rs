@@ -1,20 +1,36 @@
+pub mod global;
 pub mod local;
+pub mod module;
+pub mod symbols;
 
-
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, path::Path, rc::Rc};
 
 /// its so messy omg..
 use crate::{
-    cli_helper::Diagnostics,
-    common::{function::Function, opcode::OpCode},
+    cli_helper::{Diagnostic, Diagnostics, ErrorCode},
+    common::{
+        chunk::MAX_CONSTANTS,
+        function::Function,
+        opcode::{ConstantLocation, OpCode},
+        value::Value,
+    },
+    package::search_path,
+};
+
+use self::{
+    global::MAX_GLOBALS,
+    module::{Modules, Namespace},
+    symbols::Symbols,
 };
 
 use super::{
     ast::CompileToBytecode,
-    bytecode::{Bytecode},
+    bytecode::Bytecode,
+    checker::check_file,
     file::FileNode,
+    optimizer::{optimize_file, peephole},
     parser::Parser,
-    scanner::Scanner,
+    scanner::{Scanner, Span},
 };
 
 #[derive(Debug)]
@@ -34,6 +50,14 @@ pub struct Compiler<'a> {
     pub enclosing: Option<Enclosing<'a>>,
     pub diagnostics: Rc<RefCell<Diagnostics<'a>>>,
     pub bytecode: Bytecode,
+    /// runs the ast optimizer before emitting bytecode
+    pub optimize: bool,
+    /// filled in with every declaration and reference when set, used by the language server
+    pub symbols: Option<Rc<RefCell<Symbols>>>,
+    /// only set on the compiler of a file's top level, see `Compiler::namespace`
+    pub namespace: Option<Namespace>,
+    /// only filled in for the outermost compiler, see `Compiler::modules`
+    pub modules: Modules,
 }
 
 #[derive(Debug, PartialEq, Clone, Default)]
@@ -41,27 +65,43 @@ pub enum FunctionType {
     #[default]
     Script, // file
     Function,
+    Method,
+    /// `init`, which always returns the instance it was called on
+    Initializer,
 }
 
-#[derive(Debug)]
-pub enum CompileResult {
-    Error,
-}
 impl<'a> Compiler<'a> {
     pub fn new(
         diagnostics: Rc<RefCell<Diagnostics<'a>>>,
-        _function_type: FunctionType,
+        function_type: FunctionType,
     ) -> Compiler<'a> {
         Compiler {
             scanner: Scanner::default(),
             parser: Parser::default(),
             enclosing: None,
             diagnostics,
-            bytecode: Bytecode::default(),
+            bytecode: Bytecode {
+                function_type,
+                ..Default::default()
+            },
+            optimize: false,
+            symbols: None,
+            namespace: None,
+            modules: Modules::default(),
         }
     }
 
-    pub fn compile(mut self, source: String) -> Result<(Function, FileNode<'a>), CompileResult> {
+    /// fails with every diagnostic that was reported if any of them is an error,
+    /// warnings of a successful compile are left in `diagnostics`
+    pub fn compile(mut self, source: String) -> Result<(Function, FileNode<'a>), Vec<Diagnostic>> {
+        self.diagnostics.borrow_mut().source = source.clone();
+        let path = self.diagnostics.borrow().file_path;
+        self.namespace = Some(Namespace {
+            directory: path.parent().map(Path::to_path_buf).unwrap_or_default(),
+            ..Default::default()
+        });
+        self.modules.importing = vec![path.canonicalize().unwrap_or(path.to_path_buf())];
+        self.modules.search_path = search_path(path);
         let scanner = Scanner::new(source);
 
         let parser = Parser::new(
@@ -71,16 +111,92 @@ impl<'a> Compiler<'a> {
         );
         self.parser = parser;
 
-        let parsed_file = self.parser.parse_file();
+        let mut parsed_file = self.parser.parse_file();
         if self.parser.had_error {
-            return Err(CompileResult::Error);
+            return Err(self.diagnostics.borrow_mut().take());
+        }
+        let type_errors = check_file(&mut parsed_file);
+        if !type_errors.is_empty() {
+            let mut diagnostics = self.diagnostics.borrow_mut();
+            type_errors
+                .into_iter()
+                .for_each(|diagnostic| diagnostics.push(diagnostic));
+            return Err(diagnostics.take());
+        }
+        if self.optimize {
+            optimize_file(&mut parsed_file);
         }
         let function = Function::new();
         self.bytecode.function = function;
         parsed_file.to_bytecode(&mut self);
         self.bytecode.write_return_op();
+        if self.optimize {
+            peephole::optimize_chunk(&mut self.bytecode.function.chunk);
+        }
+        self.check_constant_pool();
+        self.check_globals();
+        self.resolve_global_symbols();
+        self.bytecode.function.global_names = self
+            .bytecode
+            .globals
+            .iter()
+            .map(|global| global.name.lexeme.clone())
+            .collect();
+        if self.diagnostics.borrow().has_errors() {
+            return Err(self.diagnostics.borrow_mut().take());
+        }
         Ok((self.bytecode.function, parsed_file))
     }
+
+    /// every global that was referenced has to be declared somewhere in the file, and has to
+    /// fit in a `GlobalSlot`
+    pub fn check_globals(&mut self) {
+        let mut diagnostics = self.diagnostics.borrow_mut();
+        for global in self
+            .bytecode
+            .globals
+            .iter()
+            .take(MAX_GLOBALS)
+            .filter(|global| !global.defined)
+        {
+            let diagnostic = Diagnostic::error(
+                ErrorCode::UndefinedVariable,
+                format!("Undefined variable '{}'", global.name.lexeme),
+            )
+            .with_label(Span::from(&global.name), "not found in this scope")
+            .with_help("declare it with `let` before using it")
+            .in_file(global.file.clone());
+            diagnostics.push(diagnostic);
+        }
+        if let Some(global) = self.bytecode.globals.get(MAX_GLOBALS) {
+            let diagnostic = Diagnostic::error(
+                ErrorCode::TooManyGlobals,
+                format!("Too many globals, '{}' doesn't fit", global.name.lexeme),
+            )
+            .with_label(Span::from(&global.name), "one global too many")
+            .with_note(format!("a program can have at most {MAX_GLOBALS} globals"))
+            .with_help("move some of them into functions as locals")
+            .in_file(global.file.clone());
+            diagnostics.push(diagnostic);
+        }
+    }
+
+    /// a `ConstantLocation` can't address every constant in an oversized chunk
+    pub fn check_constant_pool(&mut self) {
+        let function = &self.bytecode.function;
+        if !function.chunk.overflowed {
+            return;
+        }
+        let diagnostic = Diagnostic::error(
+            ErrorCode::TooManyConstants,
+            format!("Too many constants in '{}'", function.name),
+        )
+        .with_note(format!(
+            "a chunk can hold at most {MAX_CONSTANTS} constants"
+        ))
+        .with_help("split the code up into smaller functions");
+        self.diagnostics.borrow_mut().push(diagnostic);
+    }
 }
 impl Bytecode {
     /// # Safety
@@ -88,6 +204,15 @@ impl Bytecode {
     /// This function is unsafe because it is not guaranteed that the value is a valid OpCode
     /// + it may mess up the virtual machine
     pub fn write_byte(&mut self, byte: u8) {
-        self.function.chunk.code.push(OpCode::Byte(byte))
+        self.function.chunk.emit_op(OpCode::Byte(byte))
+    }
+    /// adds a value to the function's constant pool, once it's full this gives back 0 but
+    /// `Compiler::check_constant_pool` fails the compilation, so the chunk never runs
+    pub fn make_constant(&mut self, value: Value) -> ConstantLocation {
+        let chunk = &mut self.function.chunk;
+        chunk.emit_value(value).unwrap_or_else(|| {
+            chunk.overflowed = true;
+            0
+        })
     }
 }
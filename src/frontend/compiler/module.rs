@@ -0,0 +1,324 @@
+use std::{
+    collections::HashMap,
+    fs::read_to_string,
+    iter::once,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use crate::{
+    cli_helper::{Diagnostic, ErrorCode, Severity, SourceFile},
+    common::{opcode::OpCode, value::Value},
+    frontend::{
+        ast::{declaration::use_declaration::UseDeclaration, CompileToBytecode},
+        checker::check_file,
+        optimizer::{optimize_file, peephole},
+        parser::Parser,
+        scanner::{Scanner, Span, Token, TokenKind},
+    },
+    package::{MANIFEST, SEARCH_PATH_VAR},
+};
+
+use super::{Compiler, Enclosing, FunctionType};
+
+/// how the top level of a file names its globals
+#[derive(Debug, Default)]
+pub struct Namespace {
+    /// every global of a module is prefixed with its name, i.e `lib::area`, see
+    /// `Modules::prefixes`
+    pub prefix: Option<String>,
+    /// names brought in with `use lib::{a}`, and the global each one stands for
+    pub aliases: Vec<(String, String)>,
+    /// the modules the file imported, by the name the file uses them with, and the prefix of
+    /// their globals
+    pub modules: Vec<(String, String)>,
+    /// where the modules the file imports are looked for
+    pub directory: PathBuf,
+    /// `None` for the file being compiled
+    pub file: Option<Rc<SourceFile>>,
+}
+impl Namespace {
+    /// the name of the global a name at the top level of this file refers to
+    pub fn qualify(&self, name: &str) -> String {
+        if let Some((module, name_in_module)) = name.split_once("::") {
+            return match self.modules.iter().find(|(used, _)| used == module) {
+                Some((_, prefix)) => format!("{prefix}::{name_in_module}"),
+                None => name.to_string(),
+            };
+        }
+        if let Some((_, global)) = self.aliases.iter().find(|(alias, _)| alias == name) {
+            return global.clone();
+        }
+        match &self.prefix {
+            Some(prefix) => format!("{prefix}::{name}"),
+            None => name.to_string(),
+        }
+    }
+}
+
+/// every module a compile has seen, kept by the outermost compiler
+#[derive(Debug, Default)]
+pub struct Modules {
+    /// canonical paths of the modules that were compiled, each one is only compiled once
+    pub loaded: Vec<PathBuf>,
+    /// what the globals of each loaded module are prefixed with, its name unless a different
+    /// module with that name was loaded first, then its path
+    pub prefixes: HashMap<PathBuf, String>,
+    /// the chain of files being compiled, the file being compiled first
+    pub importing: Vec<PathBuf>,
+    /// the loaded modules that had errors
+    pub failed: Vec<PathBuf>,
+    /// where modules are looked for when they aren't next to the file importing them,
+    /// see `package::search_path`
+    pub search_path: Vec<PathBuf>,
+}
+
+impl<'a> Compiler<'a> {
+    /// the namespace of the file whose code is being compiled
+    pub fn namespace(&mut self) -> &mut Namespace {
+        if self.namespace.is_none() {
+            if let Some(enclosing) = self.enclosing.as_mut() {
+                return enclosing.get_compiler().namespace();
+            }
+        }
+        self.namespace.get_or_insert_with(Namespace::default)
+    }
+    pub fn modules(&mut self) -> &mut Modules {
+        match self.enclosing.as_mut() {
+            Some(enclosing) => enclosing.get_compiler().modules(),
+            None => &mut self.modules,
+        }
+    }
+    /// the token of the global a name refers to, see `Namespace::qualify`
+    pub fn qualify(&mut self, name: &Token) -> Token {
+        let mut qualified = name.clone();
+        qualified.lexeme = self.namespace().qualify(&name.lexeme);
+        qualified
+    }
+
+    /// compiles the module the first time it's imported, then brings the names it lists
+    /// into scope
+    pub fn import(&mut self, declaration: &UseDeclaration) {
+        let module = &declaration.module;
+        let (relative, name) = match module.kind {
+            TokenKind::String => {
+                let path = PathBuf::from(&module.lexeme);
+                let name = path
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                (path, name)
+            }
+            // `use std::math` is `std/math.mng`
+            _ => (
+                PathBuf::from(module.lexeme.replace("::", "/")).with_extension("mng"),
+                module.lexeme.rsplit("::").next().unwrap().to_string(),
+            ),
+        };
+        let directories: Vec<PathBuf> = once(self.namespace().directory.clone())
+            .chain(self.modules().search_path.iter().cloned())
+            .collect();
+        let found = directories
+            .iter()
+            .map(|directory| directory.join(&relative))
+            .find_map(|path| Some((path.canonicalize().ok()?, path)));
+        let Some((canonical, path)) = found else {
+            let looked_in: Vec<String> = directories
+                .iter()
+                .map(|directory| format!("`{}`", directory.join(&relative).display()))
+                .collect();
+            let diagnostic = Diagnostic::error(
+                ErrorCode::ModuleNotFound,
+                format!("Could not find module '{name}'"),
+            )
+            .with_label(Span::from(module), "imported here")
+            .with_note(format!("looked for {}", looked_in.join(", ")))
+            .with_help(format!(
+                "add the directory it's in to `libraries` in `{MANIFEST}`, or to `{SEARCH_PATH_VAR}`"
+            ));
+            self.diagnostics.borrow_mut().push(diagnostic);
+            return;
+        };
+
+        let modules = self.modules();
+        let prefix = match modules.prefixes.get(&canonical) {
+            Some(prefix) => prefix.clone(),
+            None => {
+                let taken = modules.prefixes.values().any(|prefix| *prefix == name);
+                let prefix = match taken {
+                    true => canonical.display().to_string(),
+                    false => name.clone(),
+                };
+                modules.prefixes.insert(canonical.clone(), prefix.clone());
+                prefix
+            }
+        };
+        let used = declaration
+            .alias
+            .as_ref()
+            .map_or(name.clone(), |alias| alias.lexeme.clone());
+        let namespace = self.namespace();
+        let collision = namespace
+            .modules
+            .iter()
+            .any(|(other, other_prefix)| *other == used && *other_prefix != prefix);
+        if collision {
+            let diagnostic = Diagnostic::error(
+                ErrorCode::ModuleNameCollision,
+                format!("Another module is imported as '{used}' already"),
+            )
+            .with_label(Span::from(module), "imported here")
+            .with_note(format!("both modules would be reached through `{used}::`"))
+            .with_help("give one of them another name with `as`");
+            self.diagnostics.borrow_mut().push(diagnostic);
+            return;
+        }
+        if !namespace.modules.iter().any(|(other, _)| *other == used) {
+            namespace.modules.push((used, prefix.clone()));
+        }
+
+        let modules = self.modules();
+        if let Some(start) = modules.importing.iter().position(|file| *file == canonical) {
+            let cycle: Vec<String> = modules.importing[start..]
+                .iter()
+                .chain(once(&canonical))
+                .map(|file| file.file_name().unwrap().to_string_lossy().into_owned())
+                .collect();
+            let diagnostic = Diagnostic::error(
+                ErrorCode::CyclicImport,
+                format!("Cyclic import of module '{name}'"),
+            )
+            .with_label(Span::from(module), "cyclic import")
+            .with_note(format!("the cycle is {}", cycle.join(" -> ")));
+            self.diagnostics.borrow_mut().push(diagnostic);
+            return;
+        }
+        if !modules.loaded.contains(&canonical) {
+            modules.loaded.push(canonical.clone());
+            let errors = self.error_count();
+            let keyword = &declaration.keyword;
+            self.compile_module(&path, canonical.clone(), &name, &prefix, keyword);
+            if self.error_count() > errors {
+                self.modules().failed.push(canonical.clone());
+            }
+        }
+
+        // a module that didn't compile would make every name look missing
+        if self.modules().failed.contains(&canonical) {
+            return;
+        }
+        for imported in &declaration.names {
+            let global = format!("{prefix}::{}", imported.value.lexeme);
+            let exported = self
+                .globals()
+                .iter()
+                .any(|other| other.defined && other.name.lexeme == global);
+            if !exported {
+                let diagnostic = Diagnostic::error(
+                    ErrorCode::UnknownImport,
+                    format!(
+                        "'{}' is not defined in module '{name}'",
+                        imported.value.lexeme
+                    ),
+                )
+                .with_label(Span::from(&imported.value), "not found in the module");
+                self.diagnostics.borrow_mut().push(diagnostic);
+                continue;
+            }
+            self.namespace()
+                .aliases
+                .push((imported.value.lexeme.clone(), global));
+        }
+    }
+
+    fn error_count(&self) -> usize {
+        let diagnostics = self.diagnostics.borrow();
+        diagnostics
+            .entries
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Error)
+            .count()
+    }
+
+    /// compiles a module's top level into a function that is called where it's imported,
+    /// so it runs exactly once
+    fn compile_module(
+        &mut self,
+        path: &Path,
+        canonical: PathBuf,
+        name: &str,
+        prefix: &str,
+        keyword: &Token,
+    ) {
+        let Ok(source) = read_to_string(path) else {
+            let diagnostic = Diagnostic::error(
+                ErrorCode::ModuleNotFound,
+                format!("Could not read module '{name}'"),
+            )
+            .with_label(Span::from(keyword), "imported here");
+            self.diagnostics.borrow_mut().push(diagnostic);
+            return;
+        };
+        let file = Rc::new(SourceFile {
+            path: path.to_path_buf(),
+            source: source.clone(),
+        });
+        let previous_file = self
+            .diagnostics
+            .borrow_mut()
+            .current_file
+            .replace(file.clone());
+        self.modules().importing.push(canonical);
+
+        let mut parser = Parser::new(
+            Scanner::new(source),
+            self.diagnostics.clone(),
+            FunctionType::Script,
+        );
+        let mut parsed_file = parser.parse_file();
+        let type_errors = match parser.had_error {
+            true => Vec::new(),
+            false => check_file(&mut parsed_file),
+        };
+        let failed = parser.had_error || !type_errors.is_empty();
+        for diagnostic in type_errors {
+            self.diagnostics.borrow_mut().push(diagnostic);
+        }
+        if !failed {
+            let mut module_compiler = Compiler::new(self.diagnostics.clone(), FunctionType::Script);
+            module_compiler.enclosing = Some(Enclosing(self));
+            module_compiler.optimize = self.optimize;
+            module_compiler.namespace = Some(Namespace {
+                prefix: Some(prefix.to_string()),
+                aliases: Vec::new(),
+                modules: Vec::new(),
+                directory: path.parent().map(Path::to_path_buf).unwrap_or_default(),
+                file: Some(file.clone()),
+            });
+            module_compiler.bytecode.function.name = name.to_string();
+            module_compiler.bytecode.function.file = Some(file);
+            if module_compiler.optimize {
+                optimize_file(&mut parsed_file);
+            }
+            parsed_file.to_bytecode(&mut module_compiler);
+            module_compiler.bytecode.write_void_op();
+            let chunk = &mut module_compiler.bytecode.function.chunk;
+            chunk.emit_op(OpCode::Return);
+            if module_compiler.optimize {
+                peephole::optimize_chunk(chunk);
+            }
+            module_compiler.check_constant_pool();
+
+            let function = Rc::new(module_compiler.bytecode.function);
+            let location = self.bytecode.make_constant(Value::Function(function));
+            self.bytecode.function.chunk.set_span(keyword);
+            self.bytecode.write_closure_op(location);
+            let chunk = &mut self.bytecode.function.chunk;
+            chunk.emit_op(OpCode::Call(0));
+            chunk.emit_op(OpCode::Pop);
+        }
+
+        self.modules().importing.pop();
+        self.diagnostics.borrow_mut().current_file = previous_file;
+    }
+}
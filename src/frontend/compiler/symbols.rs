@@ -0,0 +1,134 @@
+use crate::frontend::scanner::{Span, Token};
+
+use super::Compiler;
+
+/// every name the compiler declared or resolved in a file, recorded for editor tooling
+#[derive(Debug, Default, Clone)]
+pub struct Symbols {
+    pub definitions: Vec<Definition>,
+    pub references: Vec<Reference>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Variable,
+    Parameter,
+    Function,
+    Class,
+    Struct,
+    Enum,
+}
+
+#[derive(Debug, Clone)]
+pub struct Definition {
+    pub name: Token,
+    pub kind: SymbolKind,
+    /// how many arguments a function takes
+    pub arity: Option<u8>,
+    /// how a function was declared, i.e `func fib(n)`
+    pub signature: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Reference {
+    pub name: Token,
+    pub definition: Option<usize>,
+    /// globals are late bound, so they are only resolved once the whole file is compiled
+    pub global: Option<u16>,
+}
+
+impl Symbols {
+    /// the definition whose name, or one of whose references, covers `line:column`
+    pub fn definition_at(&self, line: usize, column: usize) -> Option<usize> {
+        if let Some(reference) = self
+            .references
+            .iter()
+            .find(|reference| covers(&reference.name, line, column))
+        {
+            return reference.definition;
+        }
+        self.definitions
+            .iter()
+            .position(|definition| covers(&definition.name, line, column))
+    }
+    /// every reference that resolved to `definition`
+    pub fn references_to(&self, definition: usize) -> impl Iterator<Item = &Reference> {
+        self.references
+            .iter()
+            .filter(move |reference| reference.definition == Some(definition))
+    }
+}
+
+fn covers(token: &Token, line: usize, column: usize) -> bool {
+    let span = Span::from(token);
+    let start = span.position.start_in_line;
+    span.position.line == line && (start..=start + span.length).contains(&column)
+}
+
+impl<'a> Compiler<'a> {
+    /// records a declaration, returning its id if symbols are being collected
+    pub fn declare_symbol(&mut self, name: &Token, kind: SymbolKind) -> Option<usize> {
+        self.declare_function_symbol(name, kind, None)
+    }
+    pub fn declare_function_symbol(
+        &mut self,
+        name: &Token,
+        kind: SymbolKind,
+        parameters: Option<&[Token]>,
+    ) -> Option<usize> {
+        let mut symbols = self.symbols.as_ref()?.borrow_mut();
+        symbols.definitions.push(Definition {
+            name: name.clone(),
+            kind,
+            arity: parameters.map(|parameters| parameters.len() as u8),
+            signature: parameters.map(|parameters| {
+                let parameters: Vec<&str> = parameters
+                    .iter()
+                    .map(|parameter| parameter.lexeme.as_str())
+                    .collect();
+                format!("func {}({})", name.lexeme, parameters.join(", "))
+            }),
+        });
+        Some(symbols.definitions.len() - 1)
+    }
+    /// records a use of `name`, resolved the same way `Identifier::to_bytecode` resolves it
+    pub fn reference_symbol(&mut self, name: &Token) {
+        if self.symbols.is_none() {
+            return;
+        }
+        let (definition, global) = match self.local_definition(name) {
+            Some(definition) => (definition, None),
+            None => (None, Some(self.resolve_global(name))),
+        };
+        if let Some(symbols) = &self.symbols {
+            symbols.borrow_mut().references.push(Reference {
+                name: name.clone(),
+                definition,
+                global,
+            });
+        }
+    }
+    /// walks the locals of this function and every enclosing one, like `resolve_up_value`.
+    /// `None` means the name isn't a local at all, so it must be a global
+    fn local_definition(&mut self, name: &Token) -> Option<Option<usize>> {
+        if let Some(index) = self.resolve_local(name) {
+            return Some(self.bytecode.locals[index].definition);
+        }
+        self.enclosing
+            .as_mut()?
+            .get_compiler()
+            .local_definition(name)
+    }
+    /// points the references to globals at wherever the global was declared
+    pub fn resolve_global_symbols(&mut self) {
+        let Some(symbols) = &self.symbols else {
+            return;
+        };
+        let mut symbols = symbols.borrow_mut();
+        for reference in symbols.references.iter_mut() {
+            if let Some(slot) = reference.global {
+                reference.definition = self.bytecode.globals[slot as usize].definition;
+            }
+        }
+    }
+}
@@ -5,6 +5,7 @@ use crate::common::value::Value;
 use super::{
     ast::{node::Node, CompileToBytecode},
     compiler::Compiler,
+    scanner::Token,
 };
 
 // for the ast
@@ -18,6 +19,8 @@ pub struct FileNode<'a> {
 #[derive(Debug, Default)]
 pub struct FileAttributes {
     pub expect_stack: Option<Vec<Value>>,
+    /// lints turned off with `#allow` or back on with `#warn`, in the order they appear
+    pub lints: Vec<(Token, bool)>,
 }
 
 impl<'a> CompileToBytecode for FileNode<'a> {
@@ -0,0 +1,442 @@
+//! pretty-prints source code canonically, working on the token stream rather than the
+//! ast so comments and blank lines survive
+
+use std::{cell::RefCell, path::Path, rc::Rc};
+
+use crate::cli_helper::{Diagnostic, Diagnostics};
+
+use super::{
+    compiler::FunctionType,
+    parser::Parser,
+    scanner::{Scanner, Token, TokenKind},
+};
+
+const INDENT: &str = "    ";
+
+/// formats a whole file, failing with the syntax errors of a file that doesn't parse
+pub fn format_source(path: &Path, source: &str) -> Result<String, Vec<Diagnostic>> {
+    // formatting broken code could change what it means, so it has to parse first
+    let diagnostics = Rc::new(RefCell::new(Diagnostics::new(path)));
+    let mut parser = Parser::new(
+        Scanner::new(source.to_owned()),
+        diagnostics.clone(),
+        FunctionType::Script,
+    );
+    parser.parse_file();
+    if parser.had_error {
+        return Err(diagnostics.borrow_mut().take());
+    }
+
+    let mut scanner = Scanner::new(source.to_owned());
+    scanner.keep_comments = true;
+    let mut tokens = Vec::new();
+    loop {
+        let token = scanner.next_token();
+        if token.kind == TokenKind::EOF {
+            break;
+        }
+        tokens.push(token);
+    }
+
+    let mut formatter = Formatter::default();
+    formatter.format(&tokens);
+    Ok(formatter.finish())
+}
+
+#[derive(Default)]
+struct Formatter {
+    out: String,
+    indent: usize,
+    /// how many `(` and `[` are open in the innermost block, statements only end outside
+    /// of them
+    depth: usize,
+    /// the depth around each open block, restored when it closes
+    outer_depths: Vec<usize>,
+    /// how much further than the block the current line is indented, for the lines an
+    /// expression continues on
+    continuation: usize,
+    line_start: bool,
+    /// the previous token was a prefix `-` or `!`
+    unary: bool,
+    /// inside an attribute, which ends with its line
+    attribute: bool,
+    /// what each open `{` is, most of them start blocks
+    braces: Vec<Brace>,
+    /// the depth of each `match` whose arms haven't started yet
+    matches: Vec<usize>,
+    /// between the `|`s around the parameters of a lambda
+    pipes: bool,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Brace {
+    Block,
+    /// the `{a, b}` of `use lib::{a, b}`
+    Import,
+    /// the fields of a struct literal or destructuring, `Point { x: 1, y: 2 }`
+    Fields,
+    /// the fields of a struct declaration or the variants of an enum, which end the statement
+    Declaration,
+    /// the arms of a `match` at the depth it's at, written like a block with an arm per line
+    Arms(usize),
+    /// the body of a lambda that's a single expression, `func(x) { x * 2 }`
+    Lambda,
+}
+
+impl Formatter {
+    fn format(&mut self, tokens: &[Token]) {
+        let mut index = 0;
+        while index < tokens.len() {
+            let token = &tokens[index];
+            let previous = index.checked_sub(1).map(|index| &tokens[index]);
+            let next = tokens.get(index + 1);
+            if let Some(previous) = previous {
+                self.separate(previous, token);
+            }
+
+            let mut newline_after = false;
+            let brace = match token.kind {
+                TokenKind::LeftBrace if self.matches.last() == Some(&self.depth) => {
+                    match brace_kind(tokens, index) {
+                        Brace::Block => Brace::Arms(self.matches.pop().unwrap()),
+                        brace => brace,
+                    }
+                }
+                TokenKind::LeftBrace => brace_kind(tokens, index),
+                _ => Brace::Block,
+            };
+            match token.kind {
+                TokenKind::LeftBrace if brace.is_inline() => {
+                    self.braces.push(brace);
+                    self.depth += 1;
+                    self.write("{");
+                }
+                TokenKind::RightBrace if self.inline_braces() => {
+                    let brace = self.braces.pop();
+                    self.depth = self.depth.saturating_sub(1);
+                    self.write("}");
+                    newline_after = brace == Some(Brace::Declaration);
+                }
+                TokenKind::LeftBrace
+                    if next.is_some_and(|next| next.kind == TokenKind::RightBrace) =>
+                {
+                    self.write("{}");
+                    // the `}` is written already, so it decides what comes after
+                    index += 1;
+                    newline_after = ends_statement_after_brace(tokens.get(index + 1));
+                }
+                TokenKind::LeftBrace => {
+                    if brace == Brace::Block {
+                        self.outer_depths.push(self.depth);
+                        self.depth = 0;
+                    }
+                    self.braces.push(brace);
+                    self.write("{");
+                    self.indent += 1;
+                    newline_after = true;
+                }
+                TokenKind::RightBrace => {
+                    if self.braces.pop() == Some(Brace::Block) {
+                        self.depth = self.outer_depths.pop().unwrap_or_default();
+                    }
+                    self.indent = self.indent.saturating_sub(1);
+                    if !self.line_start {
+                        self.newline();
+                    }
+                    self.write("}");
+                    newline_after = ends_statement_after_brace(next);
+                }
+                TokenKind::SemiColon => {
+                    self.write(";");
+                    newline_after = self.depth == 0;
+                }
+                TokenKind::Comma => {
+                    self.write(",");
+                    newline_after = self.braces.last() == Some(&Brace::Arms(self.depth));
+                }
+                TokenKind::Match => {
+                    self.matches.push(self.depth);
+                    self.write("match");
+                }
+                TokenKind::Comment => {
+                    self.write(token.lexeme.trim_end());
+                    newline_after = true;
+                }
+                TokenKind::LeftParen | TokenKind::LeftBracket => {
+                    self.depth += 1;
+                    self.write(&token.lexeme);
+                }
+                TokenKind::RightParen | TokenKind::RightBracket => {
+                    self.depth = self.depth.saturating_sub(1);
+                    self.write(&token.lexeme);
+                }
+                TokenKind::Hash => {
+                    self.attribute |= self.depth == 0;
+                    self.write("#");
+                }
+                TokenKind::Pipe => {
+                    self.pipes = !self.pipes;
+                    self.write("|");
+                }
+                TokenKind::String => self.write(&format!("\"{}\"", token.lexeme)),
+                _ => self.write(&token.lexeme),
+            }
+            self.unary = matches!(token.kind, TokenKind::Dash | TokenKind::Bang)
+                && !previous.is_some_and(|previous| ends_operand(previous.kind));
+
+            let written = &tokens[index];
+            // a comment on the same line stays there
+            let trailing_comment = tokens.get(index + 1).is_some_and(|next| {
+                next.kind == TokenKind::Comment && next.line == end_line(written)
+            });
+            if newline_after && !trailing_comment {
+                self.newline();
+            }
+            index += 1;
+        }
+    }
+
+    /// writes whatever goes between two tokens
+    fn separate(&mut self, previous: &Token, token: &Token) {
+        let gap = token.line.saturating_sub(end_line(previous));
+        if self.line_start {
+            // keeps at most one blank line, but never at the edges of a block
+            if gap > 1
+                && previous.kind != TokenKind::LeftBrace
+                && token.kind != TokenKind::RightBrace
+            {
+                self.out.push('\n');
+            }
+            return;
+        }
+        if token.kind == TokenKind::Comment {
+            if gap == 0 {
+                self.out.push(' ');
+            } else {
+                self.newline();
+                self.separate(previous, token);
+            }
+            return;
+        }
+        // lines without a `;`, like attributes, keep ending where they did
+        let line_ended = self.attribute || ends_line(previous.kind) && starts_line(token.kind);
+        if gap > 0 && self.depth == 0 && line_ended {
+            self.newline();
+            self.separate(previous, token);
+            return;
+        }
+        // an expression broken over several lines stays broken, with the lines after the
+        // first indented by how many brackets are open
+        let continues = !matches!(
+            token.kind,
+            TokenKind::SemiColon
+                | TokenKind::Comma
+                | TokenKind::LeftBrace
+                | TokenKind::Else
+                | TokenKind::Catch
+        );
+        if gap > 0 && continues {
+            let closes = matches!(token.kind, TokenKind::RightParen | TokenKind::RightBracket)
+                || token.kind == TokenKind::RightBrace && self.inline_braces();
+            self.newline();
+            self.continuation = match closes {
+                true => self.depth.saturating_sub(1),
+                false => self.depth.max(1),
+            };
+            return;
+        }
+        let tight = match token.kind {
+            TokenKind::SemiColon
+            | TokenKind::Comma
+            | TokenKind::RightParen
+            | TokenKind::RightBracket
+            | TokenKind::Dot
+            | TokenKind::Question
+            | TokenKind::Colon
+            | TokenKind::ColonColon => true,
+            TokenKind::RightBrace => self.braces.last() == Some(&Brace::Import),
+            // calls, and the parameters of a lambda
+            TokenKind::LeftParen => ends_operand(previous.kind) || previous.kind == TokenKind::Func,
+            TokenKind::Pipe => self.pipes,
+            _ => false,
+        } || matches!(
+            previous.kind,
+            TokenKind::LeftParen
+                | TokenKind::LeftBracket
+                | TokenKind::Hash
+                | TokenKind::ColonColon
+                | TokenKind::Dot
+                | TokenKind::DotDotDot
+        ) || previous.kind == TokenKind::Pipe && self.pipes
+            || previous.kind == TokenKind::LeftBrace && self.braces.last() == Some(&Brace::Import)
+            // `- -1` would be `--1`
+            || self.unary && !(previous.kind == TokenKind::Dash && token.kind == TokenKind::Dash);
+        if !tight {
+            self.out.push(' ');
+        }
+    }
+
+    /// whether the innermost `{` is written on one line rather than starting a block
+    fn inline_braces(&self) -> bool {
+        self.braces.last().is_some_and(Brace::is_inline)
+    }
+
+    fn write(&mut self, text: &str) {
+        if self.line_start {
+            for _ in 0..self.indent + self.continuation {
+                self.out.push_str(INDENT);
+            }
+            self.line_start = false;
+        }
+        self.out.push_str(text);
+    }
+
+    fn newline(&mut self) {
+        let trimmed = self.out.trim_end_matches(' ').len();
+        self.out.truncate(trimmed);
+        self.out.push('\n');
+        self.line_start = true;
+        self.attribute = false;
+        self.continuation = 0;
+    }
+
+    fn finish(mut self) -> String {
+        let trimmed = self.out.trim_end().len();
+        self.out.truncate(trimmed);
+        if !self.out.is_empty() {
+            self.out.push('\n');
+        }
+        self.out
+    }
+}
+
+impl Brace {
+    fn is_inline(&self) -> bool {
+        !matches!(self, Brace::Block | Brace::Arms(_))
+    }
+}
+
+/// what the `{` at `index` opens, going by the tokens around it
+fn brace_kind(tokens: &[Token], index: usize) -> Brace {
+    let kind = |offset: isize| {
+        let index = index.checked_add_signed(offset)?;
+        tokens.get(index).map(|token| token.kind)
+    };
+    match (kind(-2), kind(-1), kind(1), kind(2)) {
+        (_, Some(TokenKind::ColonColon), ..) => Brace::Import,
+        (Some(TokenKind::Struct | TokenKind::Enum), Some(TokenKind::Identifier), ..) => {
+            Brace::Declaration
+        }
+        (Some(TokenKind::Let), Some(TokenKind::Identifier), ..) => Brace::Fields,
+        (_, Some(TokenKind::Identifier), Some(TokenKind::Identifier), Some(TokenKind::Colon)) => {
+            Brace::Fields
+        }
+        _ if lambda_body(tokens, index) => Brace::Lambda,
+        _ => Brace::Block,
+    }
+}
+
+/// whether the `{` at `index` opens the body of a lambda that's only an expression
+fn lambda_body(tokens: &[Token], index: usize) -> bool {
+    let kind = |index: Option<usize>| index.and_then(|index| tokens.get(index)).map(|t| t.kind);
+    // skips the annotation of what it returns
+    let mut end = index.checked_sub(1);
+    if kind(end.and_then(|end| end.checked_sub(1))) == Some(TokenKind::Arrow) {
+        end = index.checked_sub(3);
+    }
+    let parameters = match kind(end) {
+        Some(TokenKind::Pipe) => true,
+        Some(TokenKind::RightParen) => {
+            let Some(end) = end else { return false };
+            let open = tokens[..end]
+                .iter()
+                .rposition(|token| token.kind == TokenKind::LeftParen);
+            open.is_some_and(|open| kind(open.checked_sub(1)) == Some(TokenKind::Func))
+        }
+        _ => false,
+    };
+    // anything that isn't a single expression is written as a block
+    parameters
+        && kind(Some(index + 1)) != Some(TokenKind::RightBrace)
+        && tokens[index + 1..]
+            .iter()
+            .take_while(|token| token.kind != TokenKind::RightBrace)
+            .all(|token| {
+                !matches!(
+                    token.kind,
+                    TokenKind::SemiColon | TokenKind::LeftBrace | TokenKind::Comment
+                )
+            })
+}
+
+/// the line a token ends on, strings can span several
+fn end_line(token: &Token) -> usize {
+    token.line + token.lexeme.matches('\n').count()
+}
+
+fn ends_statement_after_brace(next: Option<&Token>) -> bool {
+    !next.is_some_and(|next| {
+        matches!(
+            next.kind,
+            TokenKind::Else
+                | TokenKind::Catch
+                | TokenKind::SemiColon
+                | TokenKind::Comma
+                | TokenKind::RightParen
+                | TokenKind::RightBracket
+        )
+    })
+}
+
+/// whether a token can be the last one of an operand, which makes a `-` after it binary
+fn ends_operand(kind: TokenKind) -> bool {
+    matches!(
+        kind,
+        TokenKind::Identifier
+            | TokenKind::Number
+            | TokenKind::String
+            | TokenKind::True
+            | TokenKind::False
+            | TokenKind::Nil
+            | TokenKind::This
+            | TokenKind::RightParen
+            | TokenKind::RightBracket
+            | TokenKind::Question
+    )
+}
+
+fn ends_line(kind: TokenKind) -> bool {
+    ends_operand(kind)
+}
+
+fn starts_line(kind: TokenKind) -> bool {
+    !matches!(
+        kind,
+        TokenKind::LeftBrace
+            | TokenKind::Else
+            | TokenKind::Equal
+            | TokenKind::EqualEqual
+            | TokenKind::BangEqual
+            | TokenKind::Plus
+            | TokenKind::PlusEqual
+            | TokenKind::Dash
+            | TokenKind::DashEqual
+            | TokenKind::Star
+            | TokenKind::StarEqual
+            | TokenKind::Slash
+            | TokenKind::SlashEqual
+            | TokenKind::Less
+            | TokenKind::LessEqual
+            | TokenKind::Greater
+            | TokenKind::GreaterEqual
+            | TokenKind::And
+            | TokenKind::Or
+            | TokenKind::Comma
+            | TokenKind::SemiColon
+            | TokenKind::RightParen
+            | TokenKind::RightBracket
+            | TokenKind::LeftParen
+            | TokenKind::Dot
+            | TokenKind::Question
+    )
+}
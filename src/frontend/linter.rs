@@ -0,0 +1,609 @@
+/// static checks on the ast for code that is valid but probably not what was meant
+use std::{cell::RefCell, path::Path, rc::Rc};
+
+use crate::cli_helper::{Diagnostic, Diagnostics, ErrorCode};
+
+use super::{
+    ast::{
+        declaration::{function::FunctionDeclaration, Declaration},
+        expression::{
+            block::Block,
+            match_expr::{Match, Pattern},
+            Expression,
+        },
+        identifier::Identifier,
+        literal::{Literal, Literals},
+        node::Node,
+        statement::Statement,
+    },
+    compiler::{symbols::SymbolKind, FunctionType},
+    file::FileNode,
+    parser::Parser,
+    scanner::{Scanner, Span, Token, TokenKind},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lint {
+    UnusedVariables,
+    Shadowing,
+    UnreachableCode,
+    BoolComparison,
+    UndeclaredAssignment,
+    NonExhaustiveMatch,
+}
+impl Lint {
+    pub const ALL: [Lint; 6] = [
+        Lint::UnusedVariables,
+        Lint::Shadowing,
+        Lint::UnreachableCode,
+        Lint::BoolComparison,
+        Lint::UndeclaredAssignment,
+        Lint::NonExhaustiveMatch,
+    ];
+    /// what the lint is called in `#allow` and `#warn`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Lint::UnusedVariables => "unused_variables",
+            Lint::Shadowing => "shadowing",
+            Lint::UnreachableCode => "unreachable_code",
+            Lint::BoolComparison => "bool_comparison",
+            Lint::UndeclaredAssignment => "undeclared_assignment",
+            Lint::NonExhaustiveMatch => "non_exhaustive_match",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Lint> {
+        Lint::ALL.into_iter().find(|lint| lint.name() == name)
+    }
+}
+
+/// lints a whole file, failing with the syntax errors of a file that doesn't parse
+pub fn lint_source(path: &Path, source: &str) -> Result<Vec<Diagnostic>, Vec<Diagnostic>> {
+    let diagnostics = Rc::new(RefCell::new(Diagnostics::new(path)));
+    let mut parser = Parser::new(
+        Scanner::new(source.to_owned()),
+        diagnostics.clone(),
+        FunctionType::Script,
+    );
+    let file = parser.parse_file();
+    if parser.had_error {
+        return Err(diagnostics.borrow_mut().take());
+    }
+    Ok(lint_file(&file))
+}
+
+pub fn lint_file(file: &FileNode) -> Vec<Diagnostic> {
+    let mut linter = Linter {
+        enabled: Lint::ALL.to_vec(),
+        enums: vec![(String::new(), vec!["Ok".into(), "Err".into()])],
+        ..Default::default()
+    };
+    for (name, enabled) in &file.file_attributes.lints {
+        let Some(lint) = Lint::from_name(&name.lexeme) else {
+            let names: Vec<&str> = Lint::ALL.iter().map(Lint::name).collect();
+            linter.warnings.push(
+                Diagnostic::warning(
+                    ErrorCode::UnknownLint,
+                    format!("unknown lint `{}`", name.lexeme),
+                )
+                .with_label(name.into(), "not a lint")
+                .with_note(format!("the lints are {}", names.join(", "))),
+            );
+            continue;
+        };
+        linter.enabled.retain(|other| *other != lint);
+        if *enabled {
+            linter.enabled.push(lint);
+        }
+    }
+    // globals are late bound, so a function can assign one declared below it
+    for node in &file.nodes {
+        match node {
+            Node::Declaration(Declaration::VariableDeclaration(declaration)) => {
+                linter.globals.push(declaration.identifier.value.clone())
+            }
+            Node::Declaration(Declaration::FunctionDeclaration(function)) => {
+                linter.globals.push(function.name.value.clone())
+            }
+            Node::Declaration(Declaration::Class(class)) => {
+                linter.globals.push(class.name.value.clone())
+            }
+            Node::Declaration(Declaration::Use(use_declaration)) => linter
+                .globals
+                .extend(use_declaration.names.iter().map(|name| name.value.clone())),
+            Node::Declaration(Declaration::Struct(declaration)) => {
+                linter.globals.push(declaration.name.value.clone())
+            }
+            Node::Declaration(Declaration::Destructure(destructure)) => {
+                linter.globals.extend(destructure.fields.iter().cloned())
+            }
+            Node::Declaration(Declaration::Enum(declaration)) => {
+                linter.globals.push(declaration.name.value.clone())
+            }
+            _ => {}
+        }
+    }
+    linter.nodes(&file.nodes);
+    linter.warnings
+}
+
+#[derive(Default)]
+struct Linter {
+    enabled: Vec<Lint>,
+    /// the locals of every scope the linter is in, the innermost last
+    scopes: Vec<Vec<Binding>>,
+    globals: Vec<Token>,
+    /// the variants of every enum declared so far, to check matches against
+    enums: Vec<(String, Vec<String>)>,
+    warnings: Vec<Diagnostic>,
+}
+
+struct Binding {
+    name: Token,
+    kind: SymbolKind,
+    used: bool,
+}
+
+impl Linter {
+    fn warn(&mut self, lint: Lint, diagnostic: Diagnostic) {
+        if self.enabled.contains(&lint) {
+            self.warnings.push(diagnostic);
+        }
+    }
+
+    fn nodes(&mut self, nodes: &[Node]) {
+        let mut returned: Option<&Token> = None;
+        for node in nodes {
+            if let Some(keyword) = returned.take() {
+                if let Some(token) = first_token(node) {
+                    self.warn(
+                        Lint::UnreachableCode,
+                        Diagnostic::warning(ErrorCode::UnreachableCode, "unreachable code")
+                            .with_label(token.into(), "this is never run")
+                            .with_secondary_label(
+                                keyword.into(),
+                                format!("any code after this `{}` is unreachable", keyword.lexeme),
+                            ),
+                    );
+                }
+            }
+            self.node(node);
+            match node {
+                Node::Statement(Statement::Return(return_stmt)) => {
+                    returned = Some(&return_stmt.keyword)
+                }
+                Node::Statement(Statement::Throw(throw_stmt)) => {
+                    returned = Some(&throw_stmt.keyword)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn node(&mut self, node: &Node) {
+        match node {
+            Node::Expression(expr) => self.expr(expr),
+            Node::Identifier(identifier) => self.use_name(&identifier.value),
+            Node::Statement(statement) => self.statement(statement),
+            Node::Declaration(declaration) => self.declaration(declaration),
+            // attributes like `#expr` are already compiled away into closures
+            Node::Literal(_) | Node::None | Node::Empty | Node::Emit(_) => {}
+        }
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Expression(expr) => self.expr(expr),
+            Statement::Print(node) => self.node(node),
+            Statement::AssertEq(lhs, rhs) | Statement::AssertNe(lhs, rhs) => {
+                self.expr(lhs);
+                self.expr(rhs);
+            }
+            Statement::Return(return_stmt) => {
+                if let Some(expr) = &return_stmt.expr {
+                    self.expr(expr)
+                }
+            }
+            Statement::Throw(throw_stmt) => self.expr(&throw_stmt.value),
+            Statement::Try(try_stmt) => {
+                self.block(&try_stmt.block);
+                self.scopes.push(Vec::new());
+                if let Some(name) = &try_stmt.name {
+                    self.declare(name, SymbolKind::Variable);
+                }
+                self.nodes(&try_stmt.catch.declarations);
+                self.end_scope();
+            }
+        }
+    }
+
+    fn declaration(&mut self, declaration: &Declaration) {
+        match declaration {
+            Declaration::VariableDeclaration(declaration) => {
+                self.expr(&declaration.intializer);
+                self.declare(&declaration.identifier.value, SymbolKind::Variable);
+            }
+            Declaration::FunctionDeclaration(function) => {
+                // declared before the body, so it can call itself
+                self.declare(&function.name.value, SymbolKind::Function);
+                self.function(function);
+            }
+            Declaration::Class(class) => {
+                if let Some(superclass) = &class.superclass {
+                    self.use_name(&superclass.value);
+                }
+                self.declare(&class.name.value, SymbolKind::Class);
+                class
+                    .methods
+                    .iter()
+                    .for_each(|method| self.function(method));
+            }
+            Declaration::Struct(declaration) => {
+                self.declare(&declaration.name.value, SymbolKind::Struct)
+            }
+            Declaration::Destructure(destructure) => {
+                self.expr(&destructure.initializer);
+                self.use_name(&destructure.kind.value);
+                for field in &destructure.fields {
+                    self.declare(field, SymbolKind::Variable);
+                }
+            }
+            Declaration::Enum(declaration) => {
+                self.declare(&declaration.name.value, SymbolKind::Enum);
+                let variants = declaration
+                    .variants
+                    .iter()
+                    .map(|variant| variant.name.lexeme.clone())
+                    .collect();
+                self.enums
+                    .push((declaration.name.value.lexeme.clone(), variants));
+            }
+            // only allowed at the top level, where the names it brings in are globals
+            Declaration::Use(_) => {}
+        }
+    }
+
+    fn function(&mut self, function: &FunctionDeclaration) {
+        self.scopes.push(Vec::new());
+        for parameter in &function.parameters {
+            if let Some(default) = &parameter.default {
+                self.expr(default);
+            }
+            self.declare(&parameter.name.value, SymbolKind::Parameter);
+        }
+        self.nodes(&function.block.declarations);
+        self.end_scope();
+    }
+
+    fn expr(&mut self, expr: &Expression) {
+        match expr {
+            Expression::Identifier(identifier) => self.use_name(&identifier.value),
+            Expression::Binary(binary) if binary.op.kind == TokenKind::Equal => {
+                self.node(&binary.rhs);
+                match node_identifier(&binary.lhs) {
+                    Some(identifier) => self.assign(&identifier.value),
+                    // the object of a property is read, not assigned
+                    None => self.node(&binary.lhs),
+                }
+            }
+            Expression::Binary(binary) => {
+                if matches!(binary.op.kind, TokenKind::EqualEqual | TokenKind::BangEqual) {
+                    let literal = node_bool(&binary.lhs).or_else(|| node_bool(&binary.rhs));
+                    if let Some(bool) = literal {
+                        self.bool_comparison(&binary.op, bool);
+                    }
+                }
+                self.node(&binary.lhs);
+                self.node(&binary.rhs);
+            }
+            Expression::Grouping(inner) | Expression::Not(inner) | Expression::Negate(inner) => {
+                self.expr(inner)
+            }
+            Expression::Propagate(propagate) => self.expr(&propagate.value),
+            Expression::TypeCheck(check) => self.expr(&check.value),
+            Expression::Lambda(lambda) => self.function(&lambda.function),
+            Expression::Block(block) => self.block(block),
+            Expression::If(if_expr) => {
+                self.expr(&if_expr.predicate);
+                self.block(&if_expr.then);
+                if let Some(else_block) = &if_expr.else_block {
+                    self.block(else_block);
+                }
+            }
+            Expression::While(while_expr) => {
+                self.expr(&while_expr.predicate);
+                self.block(&while_expr.block);
+            }
+            Expression::CallExpr(call) => {
+                self.expr(&call.expr);
+                call.parameters
+                    .iter()
+                    .for_each(|parameter| self.expr(parameter));
+                call.named.iter().for_each(|(_, value)| self.expr(value));
+            }
+            Expression::Property(property) => self.expr(&property.object),
+            Expression::StructLiteral(literal) => {
+                self.use_name(&literal.name.value);
+                literal
+                    .fields
+                    .iter()
+                    .for_each(|(_, value)| self.expr(value));
+            }
+            Expression::Array(array) => {
+                array.elements.iter().for_each(|element| self.expr(element))
+            }
+            Expression::Match(match_expr) => {
+                self.expr(&match_expr.value);
+                for arm in &match_expr.arms {
+                    // the names an arm binds are only in scope in that arm
+                    self.scopes.push(Vec::new());
+                    self.pattern(&arm.pattern);
+                    if let Some(guard) = &arm.guard {
+                        self.expr(guard);
+                    }
+                    self.expr(&arm.body);
+                    self.end_scope();
+                }
+                self.exhaustive(match_expr);
+            }
+            Expression::Literal(_)
+            | Expression::This(_)
+            | Expression::Super(_)
+            | Expression::None => {}
+        }
+    }
+
+    fn pattern(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::Binding(name) => self.declare(name, SymbolKind::Variable),
+            Pattern::Variant { kind, values, .. } => {
+                if let Some(kind) = kind {
+                    self.use_name(&kind.value);
+                }
+                values.iter().for_each(|value| self.pattern(value));
+            }
+            Pattern::Array { elements, .. } => {
+                elements.iter().for_each(|element| self.pattern(element))
+            }
+            Pattern::Wildcard(_) | Pattern::Literal(_) => {}
+        }
+    }
+
+    /// a match without a catch-all arm has to cover every variant of an enum, or both bools
+    fn exhaustive(&mut self, match_expr: &Match) {
+        let mut kind = None;
+        let mut variants = Vec::new();
+        let mut bools = Vec::new();
+        let unguarded = match_expr.arms.iter().filter(|arm| arm.guard.is_none());
+        for arm in unguarded {
+            match &arm.pattern {
+                pattern if pattern.is_irrefutable() => return,
+                Pattern::Variant {
+                    kind: enum_name,
+                    name,
+                    values,
+                } if values.iter().all(Pattern::is_irrefutable) => {
+                    // `Ok` and `Err` belong to the built-in result, which has no name
+                    kind = Some(
+                        enum_name
+                            .as_ref()
+                            .map_or("", |name| name.value.lexeme.as_str()),
+                    );
+                    variants.push(name.lexeme.as_str());
+                }
+                Pattern::Literal(Literal(Literals::Bool(bool), _)) => bools.push(*bool),
+                _ => {}
+            }
+        }
+        if bools.contains(&true) && bools.contains(&false) {
+            return;
+        }
+        let declared = kind.and_then(|kind| self.enums.iter().rev().find(|(name, _)| name == kind));
+        let note = match declared {
+            Some((_, all)) => {
+                let missing: Vec<&str> = all
+                    .iter()
+                    .map(String::as_str)
+                    .filter(|variant| !variants.contains(variant))
+                    .collect();
+                if missing.is_empty() {
+                    return;
+                }
+                match missing.as_slice() {
+                    [variant] => format!("`{variant}` isn't covered"),
+                    _ => format!("`{}` aren't covered", missing.join("`, `")),
+                }
+            }
+            None => "only an arm that matches anything covers every value".to_owned(),
+        };
+        self.warn(
+            Lint::NonExhaustiveMatch,
+            Diagnostic::warning(ErrorCode::NonExhaustiveMatch, "non-exhaustive match")
+                .with_label((&match_expr.keyword).into(), "some values match no arm")
+                .with_note(note)
+                .with_help("add a `_` arm"),
+        );
+    }
+
+    fn block(&mut self, block: &Block) {
+        self.scopes.push(Vec::new());
+        self.nodes(&block.declarations);
+        self.end_scope();
+    }
+
+    fn end_scope(&mut self) {
+        let Some(scope) = self.scopes.pop() else {
+            return;
+        };
+        for binding in scope.iter().filter(|binding| !binding.used) {
+            let kind = match binding.kind {
+                SymbolKind::Variable => "variable",
+                SymbolKind::Parameter => "parameter",
+                SymbolKind::Function => "function",
+                SymbolKind::Class => "class",
+                SymbolKind::Struct => "struct",
+                SymbolKind::Enum => "enum",
+            };
+            self.warn(
+                Lint::UnusedVariables,
+                Diagnostic::warning(
+                    ErrorCode::UnusedVariable,
+                    format!("unused {kind} `{}`", binding.name.lexeme),
+                )
+                .with_label((&binding.name).into(), format!("this {kind} is never read"))
+                .with_help("remove it, or add `#allow unused_variables` to the file"),
+            );
+        }
+    }
+
+    fn declare(&mut self, name: &Token, kind: SymbolKind) {
+        // redeclaring a global just replaces it
+        if self.scopes.is_empty() {
+            return;
+        }
+        if let Some(earlier) = self.lookup(&name.lexeme) {
+            self.warn(
+                Lint::Shadowing,
+                Diagnostic::warning(
+                    ErrorCode::ShadowedVariable,
+                    format!("`{}` shadows an earlier declaration", name.lexeme),
+                )
+                .with_label(name.into(), "declared again here")
+                .with_secondary_label(earlier, "first declared here"),
+            );
+        }
+        self.scopes.last_mut().unwrap().push(Binding {
+            name: name.clone(),
+            kind,
+            used: false,
+        });
+    }
+
+    /// where a name that is in scope was declared
+    fn lookup(&self, name: &str) -> Option<Span> {
+        let local = self
+            .scopes
+            .iter()
+            .flatten()
+            .rev()
+            .find(|binding| binding.name.lexeme == name)
+            .map(|binding| &binding.name);
+        local
+            .or_else(|| self.globals.iter().find(|global| global.lexeme == name))
+            .map(Span::from)
+    }
+
+    fn use_name(&mut self, name: &Token) {
+        let binding = self
+            .scopes
+            .iter_mut()
+            .flatten()
+            .rev()
+            .find(|binding| binding.name.lexeme == name.lexeme);
+        if let Some(binding) = binding {
+            binding.used = true;
+        }
+    }
+
+    /// assigning a name that was never declared silently creates a global
+    fn assign(&mut self, name: &Token) {
+        // `lib::name` is checked when the module is compiled
+        if name.lexeme.contains("::") || self.lookup(&name.lexeme).is_some() {
+            return;
+        }
+        self.warn(
+            Lint::UndeclaredAssignment,
+            Diagnostic::warning(
+                ErrorCode::UndeclaredAssignment,
+                format!("assignment to undeclared variable `{}`", name.lexeme),
+            )
+            .with_label(name.into(), "never declared")
+            .with_help(format!("declare it first with `let {};`", name.lexeme)),
+        );
+    }
+
+    fn bool_comparison(&mut self, op: &Token, bool: bool) {
+        let help = if bool == (op.kind == TokenKind::EqualEqual) {
+            "use the value directly"
+        } else {
+            "negate the value with `!` instead"
+        };
+        self.warn(
+            Lint::BoolComparison,
+            Diagnostic::warning(
+                ErrorCode::BoolComparison,
+                format!("comparison against `{bool}`"),
+            )
+            .with_label(op.into(), "this comparison is redundant")
+            .with_help(help),
+        );
+    }
+}
+
+fn node_identifier(node: &Node) -> Option<&Identifier> {
+    match node {
+        Node::Identifier(identifier) | Node::Expression(Expression::Identifier(identifier)) => {
+            Some(identifier)
+        }
+        _ => None,
+    }
+}
+
+fn node_bool(node: &Node) -> Option<bool> {
+    match node {
+        Node::Literal(Literal(Literals::Bool(bool), _))
+        | Node::Expression(Expression::Literal(Literal(Literals::Bool(bool), _))) => Some(*bool),
+        _ => None,
+    }
+}
+
+/// the token a node starts at, to point diagnostics about the whole node at
+fn first_token(node: &Node) -> Option<&Token> {
+    match node {
+        Node::Expression(expr) => expr_token(expr),
+        Node::Literal(literal) => Some(&literal.1),
+        Node::Identifier(identifier) => Some(&identifier.value),
+        Node::Statement(Statement::Expression(expr)) => expr_token(expr),
+        Node::Statement(Statement::Print(node)) => first_token(node),
+        Node::Statement(Statement::AssertEq(lhs, _) | Statement::AssertNe(lhs, _)) => {
+            expr_token(lhs)
+        }
+        Node::Statement(Statement::Return(return_stmt)) => Some(&return_stmt.keyword),
+        Node::Statement(Statement::Throw(throw_stmt)) => Some(&throw_stmt.keyword),
+        Node::Statement(Statement::Try(try_stmt)) => Some(&try_stmt.keyword),
+        Node::Declaration(Declaration::VariableDeclaration(declaration)) => {
+            Some(&declaration.identifier.value)
+        }
+        Node::Declaration(Declaration::FunctionDeclaration(function)) => Some(&function.name.value),
+        Node::Declaration(Declaration::Class(class)) => Some(&class.name.value),
+        Node::Declaration(Declaration::Struct(declaration)) => Some(&declaration.name.value),
+        Node::Declaration(Declaration::Destructure(destructure)) => Some(&destructure.kind.value),
+        Node::Declaration(Declaration::Enum(declaration)) => Some(&declaration.name.value),
+        Node::Declaration(Declaration::Use(use_declaration)) => Some(&use_declaration.keyword),
+        Node::None | Node::Empty | Node::Emit(_) => None,
+    }
+}
+
+pub fn expr_token(expr: &Expression) -> Option<&Token> {
+    match expr {
+        Expression::Grouping(inner) | Expression::Not(inner) | Expression::Negate(inner) => {
+            expr_token(inner)
+        }
+        Expression::Binary(binary) => first_token(&binary.lhs),
+        Expression::Propagate(propagate) => expr_token(&propagate.value),
+        Expression::TypeCheck(check) => expr_token(&check.value),
+        Expression::Literal(literal) => Some(&literal.1),
+        Expression::Block(block) => block.declarations.first().and_then(first_token),
+        Expression::Identifier(identifier) => Some(&identifier.value),
+        Expression::If(if_expr) => expr_token(&if_expr.predicate),
+        Expression::While(while_expr) => expr_token(&while_expr.predicate),
+        Expression::CallExpr(call) => expr_token(&call.expr),
+        Expression::Property(property) => expr_token(&property.object),
+        Expression::This(keyword) => Some(keyword),
+        Expression::Super(super_expr) => Some(&super_expr.keyword),
+        Expression::StructLiteral(literal) => Some(&literal.name.value),
+        Expression::Array(array) => Some(&array.bracket),
+        Expression::Match(match_expr) => Some(&match_expr.keyword),
+        Expression::Lambda(lambda) => Some(&lambda.keyword),
+        Expression::None => None,
+    }
+}
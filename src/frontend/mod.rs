@@ -1,10 +1,14 @@
 pub mod ast;
 pub mod bytecode;
+pub mod checker;
 pub mod compiler;
 pub mod error;
 pub mod file;
+pub mod formatter;
+pub mod linter;
 pub mod fixedvec;
 pub mod location;
+pub mod optimizer;
 pub mod parser;
 pub mod scanner;
 #[derive(PartialEq, Eq, PartialOrd, Ord)]
@@ -0,0 +1,259 @@
+//! optimizations that run on the ast, between `Parser::parse_file` and `to_bytecode`
+
+use super::{
+    ast::{
+        declaration::Declaration,
+        expression::{block::Block, Expression},
+        literal::{Literal, Literals},
+        node::Node,
+        statement::Statement,
+    },
+    file::FileNode,
+    scanner::{Token, TokenKind},
+};
+
+pub mod peephole;
+
+pub fn optimize_file(file: &mut FileNode) {
+    optimize_nodes(&mut file.nodes);
+}
+
+/// folds every node, then drops anything that can never be reached
+pub fn optimize_nodes(nodes: &mut Vec<Node>) {
+    for node in nodes.iter_mut() {
+        fold_node(node);
+    }
+    eliminate_dead_code(nodes);
+}
+
+/// everything after a `return` or `throw` in the same block is unreachable
+pub fn eliminate_dead_code(nodes: &mut Vec<Node>) {
+    let returns_at = nodes.iter().position(|node| {
+        matches!(
+            node,
+            Node::Statement(Statement::Return(_) | Statement::Throw(_))
+        )
+    });
+    if let Some(index) = returns_at {
+        nodes.truncate(index + 1);
+    }
+}
+
+pub fn fold_node(node: &mut Node) {
+    match node {
+        Node::Expression(expr) => fold_expr(expr),
+        Node::Statement(statement) => fold_statement(statement),
+        Node::Declaration(Declaration::VariableDeclaration(declaration)) => {
+            fold_expr(&mut declaration.intializer)
+        }
+        Node::Declaration(Declaration::FunctionDeclaration(function)) => {
+            optimize_block(&mut function.block)
+        }
+        Node::Declaration(Declaration::Destructure(destructure)) => {
+            fold_expr(&mut destructure.initializer)
+        }
+        Node::Declaration(Declaration::Class(class)) => class
+            .methods
+            .iter_mut()
+            .for_each(|method| optimize_block(&mut method.block)),
+        _ => {}
+    }
+}
+
+fn fold_statement(statement: &mut Statement) {
+    match statement {
+        Statement::Expression(expr) => fold_expr(expr),
+        Statement::Print(node) => fold_node(node),
+        Statement::AssertEq(lhs, rhs) | Statement::AssertNe(lhs, rhs) => {
+            fold_expr(lhs);
+            fold_expr(rhs);
+        }
+        Statement::Return(return_stmt) => {
+            if let Some(expr) = &mut return_stmt.expr {
+                fold_expr(expr)
+            }
+        }
+        Statement::Throw(throw_stmt) => fold_expr(&mut throw_stmt.value),
+        Statement::Try(try_stmt) => {
+            optimize_block(&mut try_stmt.block);
+            optimize_block(&mut try_stmt.catch);
+        }
+    }
+}
+
+fn optimize_block(block: &mut Block) {
+    optimize_nodes(&mut block.declarations);
+}
+
+pub fn fold_expr(expr: &mut Expression) {
+    let folded = match expr {
+        Expression::Grouping(inner) => {
+            fold_expr(inner);
+            match inner.as_ref() {
+                Expression::Literal(literal) => Some(Expression::Literal(literal.clone())),
+                _ => None,
+            }
+        }
+        Expression::Binary(binary) => {
+            fold_node(&mut binary.rhs);
+            // the lhs of an assignment is a name, not a value
+            if binary.op.kind.eq(&TokenKind::Equal) {
+                return;
+            }
+            fold_node(&mut binary.lhs);
+            match (node_literal(&binary.lhs), node_literal(&binary.rhs)) {
+                (Some(lhs), Some(rhs)) => {
+                    fold_binary(lhs, rhs, &binary.op).map(Expression::Literal)
+                }
+                _ => None,
+            }
+        }
+        Expression::Not(inner) => {
+            fold_expr(inner);
+            match inner.as_ref() {
+                Expression::Literal(Literal(Literals::Bool(bool), token)) => Some(
+                    Expression::Literal(Literal(Literals::Bool(!bool), token.clone())),
+                ),
+                _ => None,
+            }
+        }
+        Expression::Negate(inner) => {
+            fold_expr(inner);
+            match inner.as_ref() {
+                Expression::Literal(Literal(Literals::Number(number), token)) => Some(
+                    Expression::Literal(Literal(Literals::Number(-number), token.clone())),
+                ),
+                _ => None,
+            }
+        }
+        Expression::Block(block) => {
+            optimize_block(block);
+            None
+        }
+        Expression::If(if_expr) => {
+            fold_expr(&mut if_expr.predicate);
+            optimize_block(&mut if_expr.then);
+            if let Some(else_block) = &mut if_expr.else_block {
+                optimize_block(else_block);
+            }
+            match if_expr.predicate.as_ref() {
+                Expression::Literal(Literal(Literals::Bool(true), _)) => {
+                    Some(Expression::Block(if_expr.then.clone()))
+                }
+                Expression::Literal(Literal(Literals::Bool(false), _)) => Some(Expression::Block(
+                    if_expr.else_block.clone().unwrap_or(Block {
+                        declarations: Vec::new(),
+                    }),
+                )),
+                _ => None,
+            }
+        }
+        Expression::While(while_expr) => {
+            fold_expr(&mut while_expr.predicate);
+            optimize_block(&mut while_expr.block);
+            match while_expr.predicate.as_ref() {
+                Expression::Literal(Literal(Literals::Bool(false), _)) => {
+                    Some(Expression::Block(Block {
+                        declarations: Vec::new(),
+                    }))
+                }
+                _ => None,
+            }
+        }
+        Expression::CallExpr(call) => {
+            fold_expr(&mut call.expr);
+            call.parameters.iter_mut().for_each(fold_expr);
+            call.named
+                .iter_mut()
+                .for_each(|(_, value)| fold_expr(value));
+            None
+        }
+        Expression::Property(property) => {
+            fold_expr(&mut property.object);
+            None
+        }
+        Expression::Propagate(propagate) => {
+            fold_expr(&mut propagate.value);
+            None
+        }
+        Expression::TypeCheck(check) => {
+            fold_expr(&mut check.value);
+            None
+        }
+        Expression::Lambda(lambda) => {
+            optimize_block(&mut lambda.function.block);
+            None
+        }
+        Expression::StructLiteral(literal) => {
+            literal
+                .fields
+                .iter_mut()
+                .for_each(|(_, value)| fold_expr(value));
+            None
+        }
+        Expression::Array(array) => {
+            array.elements.iter_mut().for_each(fold_expr);
+            None
+        }
+        Expression::Match(match_expr) => {
+            fold_expr(&mut match_expr.value);
+            for arm in &mut match_expr.arms {
+                if let Some(guard) = &mut arm.guard {
+                    fold_expr(guard);
+                }
+                fold_expr(&mut arm.body);
+            }
+            None
+        }
+        Expression::Literal(_)
+        | Expression::Identifier(_)
+        | Expression::This(_)
+        | Expression::Super(_)
+        | Expression::None => None,
+    };
+    if let Some(folded) = folded {
+        *expr = folded;
+    }
+}
+
+fn node_literal(node: &Node) -> Option<&Literal> {
+    match node {
+        Node::Literal(literal) | Node::Expression(Expression::Literal(literal)) => Some(literal),
+        _ => None,
+    }
+}
+
+/// evaluates `lhs op rhs` at compile time, if the operands allow it
+pub fn fold_binary(lhs: &Literal, rhs: &Literal, op: &Token) -> Option<Literal> {
+    let folded = match (&lhs.0, &rhs.0) {
+        (Literals::Number(lhs), Literals::Number(rhs)) => {
+            let (lhs, rhs) = (*lhs, *rhs);
+            match op.kind {
+                TokenKind::Plus => Literals::Number(lhs + rhs),
+                TokenKind::Dash => Literals::Number(lhs - rhs),
+                TokenKind::Star => Literals::Number(lhs * rhs),
+                TokenKind::Slash => Literals::Number(lhs / rhs),
+                TokenKind::Greater => Literals::Bool(lhs > rhs),
+                TokenKind::GreaterEqual => Literals::Bool(lhs >= rhs),
+                TokenKind::Less => Literals::Bool(lhs < rhs),
+                TokenKind::LessEqual => Literals::Bool(lhs <= rhs),
+                TokenKind::EqualEqual => Literals::Bool(lhs == rhs),
+                TokenKind::BangEqual => Literals::Bool(lhs != rhs),
+                _ => return None,
+            }
+        }
+        (Literals::String(lhs), Literals::String(rhs)) => match op.kind {
+            TokenKind::Plus => Literals::String(format!("{lhs}{rhs}")),
+            TokenKind::EqualEqual => Literals::Bool(lhs == rhs),
+            TokenKind::BangEqual => Literals::Bool(lhs != rhs),
+            _ => return None,
+        },
+        (Literals::Bool(lhs), Literals::Bool(rhs)) => match op.kind {
+            TokenKind::EqualEqual => Literals::Bool(lhs == rhs),
+            TokenKind::BangEqual => Literals::Bool(lhs != rhs),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(Literal(folded, lhs.1.clone()))
+}
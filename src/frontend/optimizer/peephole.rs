@@ -0,0 +1,147 @@
+use std::collections::HashSet;
+
+use crate::common::{chunk::Chunk, opcode::OpCode, value::Value};
+
+/// rewrites common instruction pairs in a finished chunk into cheaper ones,
+/// then re-targets every jump to account for the removed instructions
+pub fn optimize_chunk(chunk: &mut Chunk) {
+    thread_jumps(&mut chunk.code);
+    let targets = jump_targets(&chunk.code);
+
+    let len = chunk.code.len();
+    let mut code: Vec<OpCode> = Vec::with_capacity(len);
+    let mut spans = Vec::with_capacity(len);
+    // maps an old instruction index to where it lives in the new code
+    let mut relocations: Vec<usize> = vec![0; len + 1];
+    let mut ip = 0;
+    while ip < len {
+        relocations[ip] = code.len();
+        match &chunk.code[ip] {
+            OpCode::Nop => {
+                ip += 1;
+                continue;
+            }
+            // jumping to the next instruction does nothing
+            OpCode::JumpTo(offset) if *offset == ip + 1 => {
+                ip += 1;
+                continue;
+            }
+            _ => {}
+        }
+
+        // an instruction that is jumped to has to stay where it is
+        let fused = if ip + 1 < len && !targets.contains(&(ip + 1)) {
+            fuse(chunk, ip, &targets)
+        } else {
+            None
+        };
+        match fused {
+            Some(op) => {
+                relocations[ip + 1] = code.len();
+                code.push(op);
+                spans.push(chunk.spans[ip].clone());
+                ip += 2;
+            }
+            None => {
+                code.push(chunk.code[ip].clone());
+                spans.push(chunk.spans[ip].clone());
+                ip += 1;
+            }
+        }
+    }
+    relocations[len] = code.len();
+
+    for op in code.iter_mut() {
+        if let Some(offset) = jump_offset(op) {
+            *offset = relocations[*offset];
+        }
+    }
+    chunk.code = code;
+    chunk.spans = spans;
+}
+
+/// returns a single instruction that does the same as the pair at `ip` and `ip + 1`
+fn fuse(chunk: &mut Chunk, ip: usize, targets: &HashSet<usize>) -> Option<OpCode> {
+    let fused = match (&chunk.code[ip], &chunk.code[ip + 1]) {
+        (OpCode::SetLocal(index), OpCode::Pop) => OpCode::SetLocalConsumes(*index),
+        // `!` on anything but a bool is an error the jump wouldn't report the same way
+        (OpCode::Not, OpCode::PopJumpToIfFalse(offset))
+            if ip > 0 && !targets.contains(&ip) && pushes_bool(&chunk.code[ip - 1]) =>
+        {
+            OpCode::PopJumpToIfTrue(*offset)
+        }
+        (OpCode::Constant(location), OpCode::Add | OpCode::AddNumbers) => {
+            match chunk.constants[*location as usize] {
+                Value::Number(_) => OpCode::AddConst(*location),
+                _ => return None,
+            }
+        }
+        (OpCode::Constant(location), OpCode::Negate) => match chunk.constants[*location as usize] {
+            Value::Number(number) => OpCode::Constant(chunk.emit_value(Value::Number(-number))?),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(fused)
+}
+
+/// whether `op` always leaves a bool on top of the stack
+fn pushes_bool(op: &OpCode) -> bool {
+    matches!(
+        op,
+        OpCode::True
+            | OpCode::False
+            | OpCode::Not
+            | OpCode::Equal
+            | OpCode::NotEqual
+            | OpCode::Greater
+            | OpCode::GreaterEq
+            | OpCode::Less
+            | OpCode::LessEq
+    )
+}
+
+/// a jump that lands on an unconditional jump can go straight to its destination
+fn thread_jumps(code: &mut [OpCode]) {
+    for ip in 0..code.len() {
+        let Some(&mut mut offset) = jump_offset(&mut code[ip]) else {
+            continue;
+        };
+        // bounded, so a jump cycle can't hang the compiler
+        for _ in 0..code.len() {
+            match code.get(offset) {
+                Some(OpCode::JumpTo(next)) if *next != offset => offset = *next,
+                _ => break,
+            }
+        }
+        if let Some(target) = jump_offset(&mut code[ip]) {
+            *target = offset;
+        }
+    }
+}
+
+fn jump_targets(code: &[OpCode]) -> HashSet<usize> {
+    code.iter()
+        .filter_map(|op| match op {
+            OpCode::JumpTo(offset)
+            | OpCode::JumpToIfFalse(offset)
+            | OpCode::PopJumpToIfFalse(offset)
+            | OpCode::PopJumpToIfTrue(offset)
+            | OpCode::Try(offset)
+            | OpCode::JumpToIfGiven(_, offset) => Some(*offset),
+            _ => None,
+        })
+        .collect()
+}
+
+fn jump_offset(op: &mut OpCode) -> Option<&mut usize> {
+    match op {
+        OpCode::JumpTo(offset)
+        | OpCode::JumpToIfFalse(offset)
+        | OpCode::PopJumpToIfFalse(offset)
+        | OpCode::PopJumpToIfTrue(offset)
+        | OpCode::Try(offset)
+        | OpCode::JumpToIfGiven(_, offset) => Some(offset),
+        _ => None,
+    }
+}
@@ -1,32 +1,53 @@
 /// the parser will make an ast
-use std::{cell::RefCell, mem::transmute, ops::Range, rc::Rc};
-
-use colored::Colorize;
+use std::{cell::RefCell, mem::transmute, rc::Rc};
 
 use crate::backend::vm::natives::MACROS::*;
 use crate::frontend::{
     ast::{
+        annotation::Annotation,
         declaration::{
-            function::FunctionDeclaration, variable_declaration::VariableDeclaration, AsDeclaration,
+            class::ClassDeclaration,
+            destructure::Destructure,
+            enum_declaration::{EnumDeclaration, VariantDeclaration},
+            function::{FunctionDeclaration, Parameter},
+            struct_declaration::StructDeclaration,
+            use_declaration::UseDeclaration,
+            variable_declaration::VariableDeclaration,
+            AsDeclaration,
         },
         expression::{
-            binary_expr::BinaryExpr, block::Block, call_expr::Call, if_expr::If, while_expr::While,
+            array::ArrayLiteral,
+            binary_expr::BinaryExpr,
+            block::Block,
+            call_expr::Call,
+            if_expr::If,
+            lambda::Lambda,
+            match_expr::{Match, MatchArm, Pattern},
+            propagate::Propagate,
+            property::Property,
+            struct_literal::StructLiteral,
+            super_expr::Super,
+            while_expr::While,
             AsExpr, Expression,
         },
         identifier::Identifier,
         literal::Literal,
         node::{AsNode, EmitFn, Node},
-        statement::{return_stmt::ReturnStmt, Statement},
+        statement::{return_stmt::ReturnStmt, throw_stmt::ThrowStmt, try_stmt::TryStmt, Statement},
     },
     compiler::{Compiler, FunctionType},
-    file::FileNode,
-    scanner::{Position, Scanner, Token, TokenKind},
+    file::{FileAttributes, FileNode},
+    scanner::{Position, Scanner, Span, Token, TokenKind},
     Precedence,
 };
-use crate::{cli_helper::Diagnostics, common::opcode::OpCode, frontend::ast::CompileToBytecode};
+use crate::{
+    cli_helper::{Diagnostic, Diagnostics, ErrorCode},
+    common::{opcode::OpCode, types::Type},
+    frontend::ast::CompileToBytecode,
+};
 
 use super::ast::literal::Literals;
-use super::error::ParseResult;
+use super::error::{ParseError, ParseResult};
 
 #[derive(Debug)]
 pub struct CompilerRef<'a>(pub *const Compiler<'a>);
@@ -44,10 +65,16 @@ pub struct Parser<'a> {
     pub diagnostics: Rc<RefCell<Diagnostics<'a>>>,
     pub scanner: Scanner,
     pub had_error: bool,
-    pub panic_mode: bool,
     pub scope_depth: usize,
     pub function_type: FunctionType,
+    /// the class bodies the parser is in, and whether each has a superclass,
+    /// `this` is only allowed inside one and `super` inside a subclass
+    pub classes: Vec<bool>,
+    /// the scope depths of the lambda bodies the parser is in, the last expression
+    /// of one is returned when it has no `;`
+    pub lambdas: Vec<usize>,
     pub token_state: TokenState,
+    pub file_attributes: FileAttributes,
 }
 
 #[derive(Debug, Default)]
@@ -59,8 +86,8 @@ pub struct TokenState {
 }
 pub struct Rule<'a> {
     pub precedence: Precedence,
-    pub prefix: Option<fn(&mut Parser<'a>, can_assign: bool) -> Node>,
-    pub infix: Option<fn(&mut Parser<'a>, previous: Node) -> Node>,
+    pub prefix: Option<fn(&mut Parser<'a>, can_assign: bool) -> ParseResult<Node>>,
+    pub infix: Option<fn(&mut Parser<'a>, previous: Node) -> ParseResult<Node>>,
 }
 
 impl<'a> Parser<'a> {
@@ -72,12 +99,15 @@ impl<'a> Parser<'a> {
                     parser.advance();
                     let ident = parser.previous();
                     if ident.lexeme.eq("void") {
-                        return EmitFn(Box::new(|compiler| {
+                        return Ok(EmitFn(Box::new(|compiler| {
                             compiler.bytecode.write_void_op();
                         }))
-                        .into();
+                        .into());
                     }
-                    panic!()
+                    Err(parser.error(
+                        ErrorCode::UnknownAttribute,
+                        "Only `#void` can be used as an expression",
+                    ))
                 }),
                 infix: None,
             },
@@ -85,12 +115,9 @@ impl<'a> Parser<'a> {
                 precedence: Precedence::Grouping,
                 prefix: Some(|parser: &mut Parser, _can_assign: bool| {
                     let expr =
-                        Expression::Grouping(Box::new(parser.expression().unwrap().to_expr()))
-                            .to_node();
-                    parser
-                        .consume(TokenKind::RightParen, "expected right parenthesis to close")
-                        .expect("msg");
-                    expr
+                        Expression::Grouping(Box::new(parser.expression()?.to_expr())).to_node();
+                    parser.consume(TokenKind::RightParen, "Expected ')' to close the group")?;
+                    Ok(expr)
                 }),
                 infix: Some(Self::call_expr),
             },
@@ -99,6 +126,26 @@ impl<'a> Parser<'a> {
                 prefix: Some(Self::while_expr),
                 infix: None,
             },
+            TokenKind::Dot => Rule {
+                precedence: Precedence::Grouping,
+                prefix: None,
+                infix: Some(Self::property),
+            },
+            TokenKind::Question => Rule {
+                precedence: Precedence::Grouping,
+                prefix: None,
+                infix: Some(Self::propagate),
+            },
+            TokenKind::This => Rule {
+                precedence: Precedence::None,
+                prefix: Some(Self::this),
+                infix: None,
+            },
+            TokenKind::Super => Rule {
+                precedence: Precedence::None,
+                prefix: Some(Self::super_expr),
+                infix: None,
+            },
             TokenKind::Equal => Rule {
                 precedence: Precedence::Assignment,
                 prefix: None,
@@ -124,14 +171,33 @@ impl<'a> Parser<'a> {
                 prefix: Some(Self::block),
                 infix: None,
             },
+            TokenKind::LeftBracket => Rule {
+                precedence: Precedence::None,
+                prefix: Some(Self::array),
+                infix: None,
+            },
+            TokenKind::Match => Rule {
+                precedence: Precedence::None,
+                prefix: Some(Self::match_expr),
+                infix: None,
+            },
+            TokenKind::Func | TokenKind::Pipe => Rule {
+                precedence: Precedence::None,
+                prefix: Some(Self::lambda),
+                infix: None,
+            },
             TokenKind::True => Rule {
                 precedence: Precedence::None,
-                prefix: Some(|p, _| Literal(Literals::Bool(true), p.previous().clone()).as_node()),
+                prefix: Some(|p, _| {
+                    Ok(Literal(Literals::Bool(true), p.previous().clone()).as_node())
+                }),
                 infix: None,
             },
             TokenKind::False => Rule {
                 precedence: Precedence::None,
-                prefix: Some(|p, _| Literal(Literals::Bool(false), p.previous().clone()).as_node()),
+                prefix: Some(|p, _| {
+                    Ok(Literal(Literals::Bool(false), p.previous().clone()).as_node())
+                }),
                 infix: None,
             },
             TokenKind::Identifier => Rule {
@@ -157,20 +223,16 @@ impl<'a> Parser<'a> {
             TokenKind::Dash => Rule {
                 infix: Some(Self::binary),
                 prefix: Some(|parser, _can_assign| {
-                    Expression::Negate(Box::new(
-                        parser.precedence(Precedence::Unary).unwrap().to_expr(),
-                    ))
-                    .to_node()
+                    let expr = parser.precedence(Precedence::Unary)?.to_expr();
+                    Ok(Expression::Negate(Box::new(expr)).to_node())
                 }),
                 precedence: Precedence::Term,
             },
             TokenKind::Bang => Rule {
                 precedence: Precedence::Unary,
                 prefix: Some(|parser, _can_assign| {
-                    Expression::Not(Box::new(
-                        parser.precedence(Precedence::Unary).unwrap().to_expr(),
-                    ))
-                    .to_node()
+                    let expr = parser.precedence(Precedence::Unary)?.to_expr();
+                    Ok(Expression::Not(Box::new(expr)).to_node())
                 }),
                 infix: None,
             },
@@ -197,28 +259,15 @@ impl<'a> Parser<'a> {
     pub fn at_end(&mut self) -> bool {
         self.current().kind.eq(&TokenKind::EOF)
     }
-    pub fn precedence(&mut self, prec: Precedence) -> Result<Node, String> {
+    pub fn precedence(&mut self, prec: Precedence) -> ParseResult<Node> {
         self.advance();
-        let _path = {
-            let binding = self.diagnostics.borrow();
-            binding.file_path.to_str().unwrap()
-        };
         let previous = self.previous();
         let rule = Self::get_rule(previous.kind);
         let can_assign: bool = prec <= Precedence::Assignment;
-        #[allow(unused_assignments)]
-        let mut expression: Node = Node::None;
-        if rule.prefix.is_some() {
-            expression = rule.prefix.unwrap()(self, can_assign);
-        } else {
-            return Err(format!(
-                "{} no expr {}:{}:{}",
-                previous.kind,
-                _path,
-                previous.position.line + 1,
-                previous.position.start_in_line + 1,
-            ));
-        }
+        let Some(prefix) = rule.prefix else {
+            return Err(self.error(ErrorCode::ExpectedExpression, "Expected an expression"));
+        };
+        let mut expression = prefix(self, can_assign)?;
 
         loop {
             if self.at_end() {
@@ -243,12 +292,12 @@ impl<'a> Parser<'a> {
             match Self::get_rule(previous.kind).infix {
                 None => {}
                 Some(infix) => {
-                    expression = infix(self, expression);
+                    expression = infix(self, expression)?;
                 }
             }
         }
     }
-    pub fn expression(&mut self) -> Result<Node, String> {
+    pub fn expression(&mut self) -> ParseResult<Node> {
         self.precedence(Precedence::None)
     }
     pub fn parse_file(&mut self) -> FileNode<'a> {
@@ -265,92 +314,121 @@ impl<'a> Parser<'a> {
                 file.nodes.push(node);
             }
         }
+        file.file_attributes = std::mem::take(&mut self.file_attributes);
         file
     }
 
+    /// parses a node, reporting any syntax error in it and skipping ahead to the
+    /// next statement, so one mistake doesn't hide the ones after it
     pub fn node(&mut self) -> Node {
-        let node = match self.current().kind {
+        let index = self.token_state.index;
+        match self.try_node() {
+            Ok(node) => node,
+            Err(ParseError(diagnostic)) => {
+                self.had_error = true;
+                self.diagnostics.borrow_mut().push(diagnostic);
+                // always make progress, or the same error would be reported forever
+                if self.token_state.index == index {
+                    self.advance();
+                }
+                self.synchronize();
+                Node::Empty
+            }
+        }
+    }
+    fn try_node(&mut self) -> ParseResult<Node> {
+        match self.current().kind {
             TokenKind::Hash => {
                 self.advance();
-                if self.match_token(TokenKind::Identifier) {
-                    match self.previous().lexeme.as_str() {
-                        "void" => {
-                            return EmitFn(Box::new(|compiler| {
-                                compiler.bytecode.function.chunk.emit_op(OpCode::Void);
-                            }))
-                            .into()
-                        }
-                        "expr" => {
-                            let expr = self.expression().unwrap();
-                            return EmitFn(Box::new(move |compiler| expr.to_bytecode(compiler)))
-                                .into();
-                        }
-                        "pop" => {
-                            return EmitFn(Box::new(|compiler| {
-                                compiler.bytecode.function.chunk.emit_op(OpCode::Pop);
-                            }))
-                            .into()
-                        }
-                        "debug_stack" => {
-                            return EmitFn(Box::new(|compiler| {
-                                compiler
-                                    .bytecode
-                                    .function
-                                    .chunk
-                                    .emit_op(OpCode::CallNative(idx_debug_stack!() as u16))
-                            }))
-                            .into()
-                        }
-                        "assert_stack" => {
-                            self.consume(TokenKind::LeftBracket, "expected left bracket to close");
-                            let mut exprs = Vec::new();
-                            loop {
-                                if self.match_token(TokenKind::RightBracket) {
-                                    break;
-                                }
-                                exprs.push(self.expression().unwrap());
-                                if !self.match_token(TokenKind::Comma) {
-                                    self.advance();
-                                    break;
-                                }
+                self.consume(
+                    TokenKind::Identifier,
+                    "Expected an attribute name after '#'",
+                )?;
+                match self.previous().lexeme.as_str() {
+                    "void" => Ok(EmitFn(Box::new(|compiler| {
+                        compiler.bytecode.function.chunk.emit_op(OpCode::Void);
+                    }))
+                    .into()),
+                    "expr" => {
+                        let expr = self.expression()?;
+                        Ok(EmitFn(Box::new(move |compiler| expr.to_bytecode(compiler))).into())
+                    }
+                    "pop" => Ok(EmitFn(Box::new(|compiler| {
+                        compiler.bytecode.function.chunk.emit_op(OpCode::Pop);
+                    }))
+                    .into()),
+                    "debug_stack" => Ok(EmitFn(Box::new(|compiler| {
+                        compiler
+                            .bytecode
+                            .function
+                            .chunk
+                            .emit_op(OpCode::CallNative(idx_debug_stack!() as u16))
+                    }))
+                    .into()),
+                    "assert_stack" => {
+                        self.consume(TokenKind::LeftBracket, "Expected '[' after #assert_stack")?;
+                        let mut exprs = Vec::new();
+                        loop {
+                            if self.match_token(TokenKind::RightBracket) {
+                                break;
+                            }
+                            exprs.push(self.expression()?);
+                            if !self.match_token(TokenKind::Comma) {
+                                self.consume(
+                                    TokenKind::RightBracket,
+                                    "Expected ']' to close the stack",
+                                )?;
+                                break;
                             }
-                            return EmitFn(Box::new(move |compiler| {
-                                exprs.iter().for_each(|expr| expr.to_bytecode(compiler));
-                                compiler.bytecode.write_call_fn_arg_ptr_op(
-                                    idx_assert_stack!() as u8,
-                                    exprs.len() as u8,
-                                );
-                            }))
-                            .into();
                         }
-                        _ => return self.node(),
+                        Ok(EmitFn(Box::new(move |compiler| {
+                            exprs.iter().for_each(|expr| expr.to_bytecode(compiler));
+                            compiler.bytecode.write_call_fn_arg_ptr_op(
+                                idx_assert_stack!() as u8,
+                                exprs.len() as u8,
+                            );
+                        }))
+                        .into())
+                    }
+                    // `#allow unused_variables` and `#warn unused_variables` toggle lints
+                    "allow" | "warn" => {
+                        let enabled = self.previous().lexeme == "warn";
+                        let lint = self.token_as_identifier("Expected a lint name")?;
+                        self.file_attributes.lints.push((lint.value, enabled));
+                        Ok(Node::Empty)
                     }
+                    // attributes the compiler doesn't know about apply to the next node
+                    _ => self.try_node(),
                 }
-                panic!()
             }
             _ => self.statement(),
-        };
-        if self.panic_mode {
-            println!("synchronizing");
-            self.synchronize();
         }
-        node
-    }
-    pub fn expression_statement(&mut self) -> Node {
-        let expr = self.expression().unwrap().to_expr();
-        self.consume(
-            TokenKind::SemiColon,
-            format!("Expected ';' after expression {}:{}", file!(), line!()).as_str(),
-        );
-        Statement::Expression(expr).to_node()
     }
-    pub fn token_as_identifier(&mut self) -> Identifier {
-        self.advance();
-        Identifier {
-            value: self.previous().clone(),
+    pub fn expression_statement(&mut self) -> ParseResult<Node> {
+        let start = self.current().clone();
+        let expr = self.expression()?.to_expr();
+        // `func(x) { x * 2 }` returns the expression that ends its body
+        if self.lambdas.last() == Some(&self.scope_depth) && self.check(TokenKind::RightBrace) {
+            let keyword = Token {
+                kind: TokenKind::Return,
+                lexeme: String::from("return"),
+                ..start
+            };
+            return Ok(Statement::Return(ReturnStmt {
+                expr: Some(expr),
+                keyword,
+            })
+            .to_node());
         }
+        self.consume(TokenKind::SemiColon, "Expected ';' after expression")?;
+        Ok(Statement::Expression(expr).to_node())
     }
-    pub fn statement(&mut self) -> Node {
+    pub fn token_as_identifier(&mut self, err: &str) -> ParseResult<Identifier> {
+        self.consume(TokenKind::Identifier, err)?;
+        let value = self.previous().clone();
+        Ok(Identifier { value })
+    }
+    pub fn statement(&mut self) -> ParseResult<Node> {
         match self.current().kind {
             TokenKind::If => {
                 self.advance();
@@ -364,98 +442,277 @@ impl<'a> Parser<'a> {
                 self.advance();
                 self.block(false)
             }
+            // like `if`, a match on its own doesn't need a `;`
+            TokenKind::Match => {
+                self.advance();
+                let match_expr = self.match_expr(false)?.to_expr();
+                self.match_token(TokenKind::SemiColon);
+                Ok(Statement::Expression(match_expr).to_node())
+            }
+            TokenKind::Try => {
+                self.advance();
+                let keyword = self.previous().clone();
+                self.consume(TokenKind::LeftBrace, "Expected '{' after 'try'")?;
+                let block = self.block_body()?;
+                self.consume(TokenKind::Catch, "Expected 'catch' after the try block")?;
+                let name = match self.match_token(TokenKind::Identifier) {
+                    true => Some(self.previous().clone()),
+                    false => None,
+                };
+                self.consume(TokenKind::LeftBrace, "Expected '{' after 'catch'")?;
+                let catch = self.block_body()?;
+                Ok(Statement::Try(TryStmt {
+                    keyword,
+                    block,
+                    name,
+                    catch,
+                })
+                .to_node())
+            }
+            TokenKind::Throw => {
+                self.advance();
+                let keyword = self.previous().clone();
+                let value = self.expression()?.to_expr();
+                self.consume(TokenKind::SemiColon, "Expected ';' after the thrown value")?;
+                Ok(Statement::Throw(ThrowStmt { keyword, value }).to_node())
+            }
             TokenKind::Print => {
                 self.advance();
-                let node = Statement::Print(Box::new(self.expression().unwrap())).to_node();
-                self.consume(TokenKind::SemiColon, "Expected ';' ");
-                node
+                let node = Statement::Print(Box::new(self.expression()?)).to_node();
+                self.consume(TokenKind::SemiColon, "Expected ';' after print statement")?;
+                Ok(node)
             }
             TokenKind::AssertEq => {
                 self.advance();
-                let lhs = self.expression().unwrap().to_expr();
-                self.consume(TokenKind::Comma, "Expected ','' to seperate lhs and rhs");
-                let rhs = self.expression().unwrap().to_expr();
-                self.consume(TokenKind::SemiColon, "Expected ';'");
-
-                let node = Statement::AssertEq(lhs, rhs);
-                node.to_node()
+                let (lhs, rhs) = self.assertion()?;
+                Ok(Statement::AssertEq(lhs, rhs).to_node())
             }
             TokenKind::AssertNe => {
                 self.advance();
-                let lhs = self.expression().unwrap().to_expr();
-                self.consume(TokenKind::Comma, "Expected ','' to seperate lhs and rhs");
-                let rhs = self.expression().unwrap().to_expr();
-                self.consume(TokenKind::SemiColon, "Expected ';'");
-
-                let node = Statement::AssertNe(lhs, rhs);
-                node.to_node()
+                let (lhs, rhs) = self.assertion()?;
+                Ok(Statement::AssertNe(lhs, rhs).to_node())
             }
             TokenKind::Let => {
                 self.advance();
-                let identifier = self.token_as_identifier();
-                if !self.check(TokenKind::Equal) {
+                let identifier =
+                    self.token_as_identifier("Expected a variable name after 'let'")?;
+                if self.match_token(TokenKind::LeftBrace) {
+                    let fields = self.field_names()?;
+                    self.consume(TokenKind::Equal, "Expected '=' after the fields")?;
+                    let initializer = self.expression()?.to_expr();
                     self.consume(
                         TokenKind::SemiColon,
-                        "Expected 'n' after variable declaration",
-                    );
-                    return VariableDeclaration {
+                        "Expected ';' after variable declaration",
+                    )?;
+                    return Ok(Destructure {
+                        kind: identifier,
+                        fields,
+                        initializer,
+                    }
+                    .to_declaration()
+                    .to_node());
+                }
+                let annotation = match self.match_token(TokenKind::Colon) {
+                    true => Some(self.annotation()?),
+                    false => None,
+                };
+                // anything but a `;` after the name means the `=` is missing, as in `let b 2;`
+                if self.match_token(TokenKind::SemiColon) {
+                    return Ok(VariableDeclaration {
                         intializer: Expression::None,
                         identifier,
+                        annotation,
                     }
-                    .to_node();
+                    .to_node());
                 }
-                self.consume(TokenKind::Equal, "Expected '=' after variable name");
-                let initializer = self.expression().unwrap().to_expr();
+                self.consume(TokenKind::Equal, "Expected '=' after variable name")?;
+                let initializer = self.expression()?.to_expr();
                 self.consume(
                     TokenKind::SemiColon,
                     "Expected ';' after variable declaration",
-                );
+                )?;
 
-                VariableDeclaration {
+                Ok(VariableDeclaration {
                     intializer: initializer,
                     identifier,
+                    annotation,
                 }
-                .to_node()
+                .to_node())
             }
             TokenKind::Func => {
+                // `func(` starts a lambda rather than a declaration
+                if self.scanner.lookahead(1)[0].kind == TokenKind::LeftParen {
+                    return self.expression_statement();
+                }
                 self.advance();
-                let identifier = self.token_as_identifier();
-                let mut parameters: Vec<Identifier> = Vec::new();
-                self.consume(TokenKind::LeftParen, "err").unwrap();
-                loop {
-                    if self.match_token(TokenKind::RightParen) {
-                        break;
+                Ok(self.function()?.to_declaration().to_node())
+            }
+            TokenKind::Class => {
+                self.advance();
+                let name = self.token_as_identifier("Expected a class name after 'class'")?;
+                let superclass = if self.match_token(TokenKind::Less) {
+                    let superclass =
+                        self.token_as_identifier("Expected a superclass name after '<'")?;
+                    // reported without giving up on the class, its body is still fine
+                    if superclass.value.lexeme == name.value.lexeme {
+                        let ParseError(diagnostic) = self.error(
+                            ErrorCode::InheritsFromItself,
+                            "A class can't inherit from itself",
+                        );
+                        self.had_error = true;
+                        self.diagnostics.borrow_mut().push(diagnostic);
+                    }
+                    Some(superclass)
+                } else {
+                    None
+                };
+                self.consume(TokenKind::LeftBrace, "Expected '{' before the class body")?;
+                self.classes.push(superclass.is_some());
+                let methods = self.methods();
+                self.classes.pop();
+                let methods = methods?;
+                self.consume(TokenKind::RightBrace, "Expected '}' after the class body")?;
+                Ok(ClassDeclaration {
+                    name,
+                    superclass,
+                    methods,
+                }
+                .to_declaration()
+                .to_node())
+            }
+            TokenKind::Struct => {
+                self.advance();
+                let name = self.token_as_identifier("Expected a struct name after 'struct'")?;
+                self.consume(TokenKind::LeftBrace, "Expected '{' before the fields")?;
+                let fields = self.field_names()?;
+                if fields.is_empty() {
+                    return Err(self.error(
+                        ErrorCode::ExpectedToken,
+                        "Expected a struct to have at least one field",
+                    ));
+                }
+                Ok(StructDeclaration { name, fields }
+                    .to_declaration()
+                    .to_node())
+            }
+            TokenKind::Enum => {
+                self.advance();
+                let name = self.token_as_identifier("Expected an enum name after 'enum'")?;
+                self.consume(TokenKind::LeftBrace, "Expected '{' before the variants")?;
+                let mut variants: Vec<VariantDeclaration> = Vec::new();
+                while !self.match_token(TokenKind::RightBrace) {
+                    let variant = self.token_as_identifier("Expected a variant name")?.value;
+                    if variants
+                        .iter()
+                        .any(|other| other.name.lexeme == variant.lexeme)
+                    {
+                        let ParseError(diagnostic) = self.error(
+                            ErrorCode::DuplicateField,
+                            &format!("Variant '{}' is declared more than once", variant.lexeme),
+                        );
+                        self.had_error = true;
+                        self.diagnostics.borrow_mut().push(diagnostic);
                     }
-                    parameters.push(self.expression().unwrap().as_identifier());
+                    let fields = if self.match_token(TokenKind::LeftParen) {
+                        self.variant_fields()?
+                    } else {
+                        Vec::new()
+                    };
+                    variants.push(VariantDeclaration {
+                        name: variant,
+                        fields,
+                    });
                     if !self.match_token(TokenKind::Comma) {
-                        self.advance();
+                        self.consume(TokenKind::RightBrace, "Expected '}' after the variants")?;
                         break;
                     }
                 }
-                self.consume(TokenKind::LeftBrace, "Expected '{'");
-                FunctionDeclaration {
-                    parameters,
-                    name: identifier,
-                    block: self.block(false).to_expr().as_block(),
+                if variants.is_empty() {
+                    return Err(self.error(
+                        ErrorCode::ExpectedToken,
+                        "Expected an enum to have at least one variant",
+                    ));
+                }
+                Ok(EnumDeclaration { name, variants }
+                    .to_declaration()
+                    .to_node())
+            }
+            TokenKind::Use => {
+                self.advance();
+                let keyword = self.previous().clone();
+                if self.scope_depth > 0 {
+                    return Err(self.error_at(
+                        &keyword,
+                        ErrorCode::UseOutsideTopLevel,
+                        "Modules can only be imported at the top level of a file",
+                    ));
+                }
+                let module = if self.match_token(TokenKind::String) {
+                    self.previous().clone()
+                } else {
+                    self.token_as_identifier("Expected a module path or name after 'use'")?
+                        .value
+                };
+                let mut names = Vec::new();
+                if module.kind == TokenKind::Identifier && self.match_token(TokenKind::ColonColon) {
+                    self.consume(TokenKind::LeftBrace, "Expected '{' after '::'")?;
+                    loop {
+                        if self.match_token(TokenKind::RightBrace) {
+                            break;
+                        }
+                        names.push(self.token_as_identifier("Expected a name to import")?);
+                        if !self.match_token(TokenKind::Comma) {
+                            self.consume(
+                                TokenKind::RightBrace,
+                                "Expected '}' after the imported names",
+                            )?;
+                            break;
+                        }
+                    }
+                }
+                // `as` is only a keyword here, so it can still name variables
+                let mut alias = None;
+                if self.check(TokenKind::Identifier) && self.current().lexeme == "as" {
+                    self.advance();
+                    alias = Some(
+                        self.token_as_identifier("Expected a name for the module after 'as'")?
+                            .value,
+                    );
+                }
+                self.consume(TokenKind::SemiColon, "Expected ';' after use declaration")?;
+                Ok(UseDeclaration {
+                    keyword,
+                    module,
+                    names,
+                    alias,
                 }
                 .to_declaration()
-                .to_node()
+                .to_node())
             }
             TokenKind::Return => {
                 self.advance();
+                let keyword = self.previous().clone();
                 if self.scope_depth == 0 && self.function_type.eq(&FunctionType::Script) {
-                    self.error("Cannot return from the top level of a script")
+                    return Err(self.error(
+                        ErrorCode::ReturnOutsideFunction,
+                        "Cannot return from the top level of a script",
+                    ));
                 }
-                if let Ok(expr) = self.expression() {
-                    let expr = expr.to_expr();
-                    self.consume(
-                        TokenKind::SemiColon,
-                        format!("Expected ';' after expression {}:{}", file!(), line!()).as_str(),
-                    );
-                    Statement::Return(ReturnStmt { expr: Some(expr) }).to_node()
+                if !self.check(TokenKind::SemiColon) {
+                    let expr = self.expression()?.to_expr();
+                    self.consume(TokenKind::SemiColon, "Expected ';' after expression")?;
+                    Ok(Statement::Return(ReturnStmt {
+                        expr: Some(expr),
+                        keyword,
+                    })
+                    .to_node())
                 } else {
-                    self.consume(TokenKind::SemiColon, "Expected ';' after return statement");
-                    Statement::Return(ReturnStmt { expr: None }).to_node()
+                    self.consume(TokenKind::SemiColon, "Expected ';' after return statement")?;
+                    Ok(Statement::Return(ReturnStmt {
+                        expr: None,
+                        keyword,
+                    })
+                    .to_node())
                 }
             }
             _ => self.expression_statement(),
@@ -463,147 +720,602 @@ impl<'a> Parser<'a> {
     }
 }
 impl Parser<'_> {
-    pub fn identifier(&mut self, can_assign: bool) -> Node {
+    /// parses the rest of a function after `func`, methods are declared the same way
+    fn function(&mut self) -> ParseResult<FunctionDeclaration> {
+        let identifier = self.token_as_identifier("Expected a function name after 'func'")?;
+        self.consume(TokenKind::LeftParen, "Expected '(' after the function name")?;
+        let parameters = self.parameters(TokenKind::RightParen)?;
+        let returns = match self.match_token(TokenKind::Arrow) {
+            true => Some(self.annotation()?),
+            false => None,
+        };
+        self.consume(
+            TokenKind::LeftBrace,
+            "Expected '{' before the function body",
+        )?;
+        Ok(FunctionDeclaration {
+            parameters,
+            returns,
+            name: identifier,
+            block: self.block_body()?,
+        })
+    }
+    /// the parameters of a function or lambda, up to and including `closing`
+    fn parameters(&mut self, closing: TokenKind) -> ParseResult<Vec<Parameter>> {
+        let mut parameters: Vec<Parameter> = Vec::new();
+        loop {
+            if self.match_token(closing) {
+                break;
+            }
+            let rest = self.match_token(TokenKind::DotDotDot);
+            let name = self.token_as_identifier("Expected a parameter name")?;
+            let annotation = match self.match_token(TokenKind::Colon) {
+                true => Some(self.annotation()?),
+                false => None,
+            };
+            let default = match self.match_token(TokenKind::Equal) {
+                true => Some(self.expression()?.to_expr()),
+                false => None,
+            };
+            let parameter = Parameter {
+                name,
+                annotation,
+                default,
+                rest,
+            };
+            self.check_parameter(&parameters, &parameter);
+            parameters.push(parameter);
+            if !self.match_token(TokenKind::Comma) {
+                self.consume(closing, "Expected the parameters to be closed")?;
+                break;
+            }
+        }
+        Ok(parameters)
+    }
+    /// reported without giving up on the function, the rest of it is still fine
+    fn check_parameter(&mut self, previous: &[Parameter], parameter: &Parameter) {
+        let message = if previous.last().is_some_and(|last| last.rest) {
+            "The rest parameter has to be the last one"
+        } else if parameter.rest && parameter.default.is_some() {
+            "The rest parameter can't have a default"
+        } else if !parameter.rest
+            && parameter.default.is_none()
+            && previous.iter().any(|previous| previous.default.is_some())
+        {
+            "A parameter without a default can't follow one with a default"
+        } else {
+            return;
+        };
+        let ParseError(diagnostic) =
+            self.error_at(&parameter.name.value, ErrorCode::InvalidParameter, message);
+        self.had_error = true;
+        self.diagnostics.borrow_mut().push(diagnostic);
+    }
+    /// `func(x) { x * 2 }` or `|x| x * 2`, after the `func` or the first `|`
+    fn lambda(&mut self, _can_assign: bool) -> ParseResult<Node> {
+        let keyword = self.previous().clone();
+        let parameters = match keyword.kind {
+            TokenKind::Func => {
+                self.consume(TokenKind::LeftParen, "Expected '(' after 'func'")?;
+                self.parameters(TokenKind::RightParen)?
+            }
+            _ => self.parameters(TokenKind::Pipe)?,
+        };
+        let returns = match self.match_token(TokenKind::Arrow) {
+            true => Some(self.annotation()?),
+            false => None,
+        };
+        let block = if keyword.kind == TokenKind::Func || self.check(TokenKind::LeftBrace) {
+            self.consume(TokenKind::LeftBrace, "Expected '{' before the lambda body")?;
+            self.lambdas.push(self.scope_depth + 1);
+            let block = self.block_body();
+            self.lambdas.pop();
+            block?
+        } else {
+            // `|x| x * 2` is the same as `|x| { x * 2 }`
+            let start = self.current().clone();
+            let expr = self.expression()?.to_expr();
+            let keyword = Token {
+                kind: TokenKind::Return,
+                lexeme: String::from("return"),
+                ..start
+            };
+            Block {
+                declarations: vec![Statement::Return(ReturnStmt {
+                    expr: Some(expr),
+                    keyword,
+                })
+                .to_node()],
+            }
+        };
+        let name = Identifier {
+            value: Token {
+                kind: TokenKind::Identifier,
+                lexeme: String::from("lambda"),
+                ..keyword.clone()
+            },
+        };
+        Ok(Lambda {
+            keyword,
+            function: FunctionDeclaration {
+                name,
+                parameters,
+                returns,
+                block,
+            },
+        }
+        .to_expr()
+        .to_node())
+    }
+    /// the type after a `:` or `->`
+    fn annotation(&mut self) -> ParseResult<Annotation> {
+        let name = self.token_as_identifier("Expected a type")?.value;
+        let Some(kind) = Type::from_name(&name.lexeme) else {
+            let names: Vec<&str> = Type::ALL.iter().map(Type::name).collect();
+            let ParseError(diagnostic) = self.error(
+                ErrorCode::UnknownType,
+                &format!("Unknown type '{}'", name.lexeme),
+            );
+            return Err(ParseError(
+                diagnostic.with_note(format!("the types are {}", names.join(", "))),
+            ));
+        };
+        Ok(Annotation { name, kind })
+    }
+    fn methods(&mut self) -> ParseResult<Vec<FunctionDeclaration>> {
+        let mut methods = Vec::new();
+        while !self.check(TokenKind::RightBrace) && !self.check(TokenKind::EOF) {
+            self.consume(TokenKind::Func, "Expected a method in the class body")?;
+            methods.push(self.function()?);
+        }
+        Ok(methods)
+    }
+    /// the `x, y }` of a struct declaration or destructuring, which can't repeat a name
+    fn field_names(&mut self) -> ParseResult<Vec<Token>> {
+        let mut fields: Vec<Token> = Vec::new();
+        while !self.match_token(TokenKind::RightBrace) {
+            let field = self.token_as_identifier("Expected a field name")?.value;
+            self.unique_field(&fields, &field);
+            fields.push(field);
+            if !self.match_token(TokenKind::Comma) {
+                self.consume(TokenKind::RightBrace, "Expected '}' after the fields")?;
+                break;
+            }
+        }
+        Ok(fields)
+    }
+    /// reported without giving up on the rest of the fields
+    fn unique_field(&mut self, fields: &[Token], field: &Token) {
+        if fields.iter().any(|other| other.lexeme == field.lexeme) {
+            let ParseError(diagnostic) = self.error_at(
+                field,
+                ErrorCode::DuplicateField,
+                &format!("Field '{}' is given more than once", field.lexeme),
+            );
+            self.had_error = true;
+            self.diagnostics.borrow_mut().push(diagnostic);
+        }
+    }
+    /// the `radius, angle)` of a variant that holds values
+    fn variant_fields(&mut self) -> ParseResult<Vec<Token>> {
+        let mut fields: Vec<Token> = Vec::new();
+        while !self.match_token(TokenKind::RightParen) {
+            let field = self.token_as_identifier("Expected a field name")?.value;
+            self.unique_field(&fields, &field);
+            fields.push(field);
+            if !self.match_token(TokenKind::Comma) {
+                self.consume(TokenKind::RightParen, "Expected ')' after the fields")?;
+                break;
+            }
+        }
+        if fields.is_empty() {
+            return Err(self.error(
+                ErrorCode::ExpectedToken,
+                "Expected a field, a variant without values has no parentheses",
+            ));
+        }
+        Ok(fields)
+    }
+    /// the operands of `assert_eq` and `assert_ne`
+    fn assertion(&mut self) -> ParseResult<(Expression, Expression)> {
+        let lhs = self.expression()?.to_expr();
+        self.consume(TokenKind::Comma, "Expected ',' to seperate lhs and rhs")?;
+        let rhs = self.expression()?.to_expr();
+        self.consume(TokenKind::SemiColon, "Expected ';' after assertion")?;
+        Ok((lhs, rhs))
+    }
+    pub fn identifier(&mut self, can_assign: bool) -> ParseResult<Node> {
         let token = self.previous().clone();
-        let _is_global = self.scope_depth == 0;
         if can_assign && self.match_token(TokenKind::Equal) {
-            return BinaryExpr {
+            return Ok(BinaryExpr {
                 lhs: Box::new(Identifier { value: token }.to_node()),
                 op: self.previous().clone(),
-                rhs: Box::new(self.expression().unwrap()),
+                rhs: Box::new(self.expression()?),
+                operands: Type::Any,
             }
             .to_expr()
-            .to_node();
+            .to_node());
+        }
+        // `{` starts a struct literal only if a field follows, otherwise it's the block
+        // of an `if` or `while`
+        if self.check(TokenKind::LeftBrace) {
+            let next = self.scanner.lookahead(2);
+            if next[0].kind == TokenKind::Identifier && next[1].kind == TokenKind::Colon {
+                self.advance();
+                return self.struct_literal(Identifier { value: token });
+            }
+        }
+        Ok(Identifier { value: token }.to_node())
+    }
+    fn struct_literal(&mut self, name: Identifier) -> ParseResult<Node> {
+        let mut fields: Vec<(Token, Expression)> = Vec::new();
+        while !self.match_token(TokenKind::RightBrace) {
+            let field = self.token_as_identifier("Expected a field name")?.value;
+            let names: Vec<Token> = fields.iter().map(|(name, _)| name.clone()).collect();
+            self.unique_field(&names, &field);
+            self.consume(TokenKind::Colon, "Expected ':' after the field name")?;
+            fields.push((field, self.expression()?.to_expr()));
+            if !self.match_token(TokenKind::Comma) {
+                self.consume(TokenKind::RightBrace, "Expected '}' after the fields")?;
+                break;
+            }
+        }
+        Ok(StructLiteral { name, fields }.to_expr().to_node())
+    }
+    pub fn property(&mut self, lhs: Node) -> ParseResult<Node> {
+        let name = self.token_as_identifier("Expected a property name after '.'")?;
+        Ok(Property {
+            object: Box::new(lhs.to_expr()),
+            name: name.value,
+        }
+        .to_expr()
+        .to_node())
+    }
+    pub fn propagate(&mut self, lhs: Node) -> ParseResult<Node> {
+        Ok(Propagate {
+            value: Box::new(lhs.to_expr()),
+            operator: self.previous().clone(),
+        }
+        .to_expr()
+        .to_node())
+    }
+    pub fn this(&mut self, _can_assign: bool) -> ParseResult<Node> {
+        if self.classes.is_empty() {
+            return Err(self.error(
+                ErrorCode::ThisOutsideClass,
+                "Cannot use 'this' outside of a method",
+            ));
+        }
+        Ok(Expression::This(self.previous().clone()).to_node())
+    }
+    pub fn super_expr(&mut self, _can_assign: bool) -> ParseResult<Node> {
+        let keyword = self.previous().clone();
+        match self.classes.last() {
+            None => {
+                return Err(self.error(
+                    ErrorCode::SuperOutsideSubclass,
+                    "Cannot use 'super' outside of a method",
+                ))
+            }
+            Some(false) => {
+                return Err(self.error(
+                    ErrorCode::SuperOutsideSubclass,
+                    "Cannot use 'super' in a class without a superclass",
+                ))
+            }
+            Some(true) => {}
         }
-        Identifier { value: token }.to_node()
+        self.consume(TokenKind::Dot, "Expected '.' after 'super'")?;
+        let method = self.token_as_identifier("Expected a method name after 'super.'")?;
+        Ok(Super {
+            keyword,
+            method: method.value,
+        }
+        .to_expr()
+        .to_node())
     }
-    pub fn call_expr(&mut self, lhs: Node) -> Node {
+    pub fn call_expr(&mut self, lhs: Node) -> ParseResult<Node> {
         let expr = lhs.to_expr();
         let mut parameters: Vec<Expression> = Vec::new();
+        let mut named: Vec<(Token, Expression)> = Vec::new();
         loop {
             if self.match_token(TokenKind::RightParen) {
                 break;
             }
 
-            let parameter = self.expression();
-            parameters.push(parameter.unwrap().to_expr());
+            let is_named = self.check(TokenKind::Identifier)
+                && self.scanner.lookahead(1)[0].kind == TokenKind::Colon;
+            if is_named {
+                let name = self.token_as_identifier("Expected an argument name")?.value;
+                self.consume(TokenKind::Colon, "Expected ':' after the argument name")?;
+                named.push((name, self.expression()?.to_expr()));
+            } else if !named.is_empty() {
+                return Err(self.error_at_current(
+                    ErrorCode::ExpectedToken,
+                    "Expected a named argument, positional ones come first",
+                ));
+            } else {
+                let parameter = self.expression()?;
+                parameters.push(parameter.to_expr());
+            }
             if !self.match_token(TokenKind::Comma) {
-                self.advance();
+                self.consume(TokenKind::RightParen, "Expected ')' after arguments")?;
                 break;
             }
         }
-        Call {
+        Ok(Call {
             parameters: Box::new(parameters),
             expr: Box::new(expr),
+            named,
         }
         .to_expr()
-        .to_node()
+        .to_node())
     }
-    pub fn while_expr(&mut self, _can_assign: bool) -> Node {
-        let condition = self.expression().unwrap().to_expr();
-        let block = self.expression().unwrap().to_expr().as_block();
+    pub fn while_expr(&mut self, _can_assign: bool) -> ParseResult<Node> {
+        let condition = self.expression()?.to_expr();
+        self.consume(TokenKind::LeftBrace, "Expected '{' after the condition")?;
+        let block = self.block_body()?;
 
-        While {
+        Ok(While {
             predicate: Box::new(condition),
             block,
         }
         .to_expr()
-        .to_node()
+        .to_node())
     }
-    pub fn if_expr(&mut self, _can_assign: bool) -> Node {
-        let condition = self.expression().unwrap().to_expr();
-        let then = self.expression().unwrap().to_expr().as_block();
-        #[allow(unused_mut)]
+    pub fn if_expr(&mut self, _can_assign: bool) -> ParseResult<Node> {
+        let condition = self.expression()?.to_expr();
+        self.consume(TokenKind::LeftBrace, "Expected '{' after the condition")?;
+        let then = self.block_body()?;
         let mut else_block = None;
         if self.match_token(TokenKind::Else) {
-            let block = self.expression().unwrap().to_expr().as_block();
-            else_block = Some(block)
+            self.consume(TokenKind::LeftBrace, "Expected '{' after 'else'")?;
+            else_block = Some(self.block_body()?)
         }
-        If {
+        Ok(If {
             predicate: Box::new(condition),
             then,
             else_block,
         }
         .to_expr()
-        .to_node()
+        .to_node())
     }
-    pub fn block(&mut self, _can_assign: bool) -> Node {
+    pub fn array(&mut self, _can_assign: bool) -> ParseResult<Node> {
+        let bracket = self.previous().clone();
+        let mut elements = Vec::new();
+        while !self.match_token(TokenKind::RightBracket) {
+            elements.push(self.expression()?.to_expr());
+            if !self.match_token(TokenKind::Comma) {
+                self.consume(TokenKind::RightBracket, "Expected ']' after the elements")?;
+                break;
+            }
+        }
+        Ok(ArrayLiteral { bracket, elements }.to_expr().to_node())
+    }
+    pub fn match_expr(&mut self, _can_assign: bool) -> ParseResult<Node> {
+        let keyword = self.previous().clone();
+        let value = self.expression()?.to_expr();
+        self.consume(TokenKind::LeftBrace, "Expected '{' after the matched value")?;
+        let mut arms = Vec::new();
+        while !self.match_token(TokenKind::RightBrace) {
+            let pattern = self.pattern()?;
+            let guard = match self.match_token(TokenKind::If) {
+                true => Some(self.expression()?.to_expr()),
+                false => None,
+            };
+            self.consume(TokenKind::FatArrow, "Expected '=>' after the pattern")?;
+            // a block ends the arm by itself, anything else needs a `,` after it
+            let block = self.match_token(TokenKind::LeftBrace);
+            let body = match block {
+                true => Expression::Block(self.block_body()?),
+                false => self.expression()?.to_expr(),
+            };
+            arms.push(MatchArm {
+                pattern,
+                guard,
+                body,
+            });
+            if !self.match_token(TokenKind::Comma) && !block {
+                self.consume(TokenKind::RightBrace, "Expected '}' after the arms")?;
+                break;
+            }
+        }
+        Ok(Match {
+            keyword,
+            value: Box::new(value),
+            arms,
+        }
+        .to_expr()
+        .to_node())
+    }
+    /// the patterns for the values of a variant, if it has any
+    fn variant_patterns(&mut self) -> ParseResult<Vec<Pattern>> {
+        let mut values = Vec::new();
+        if self.match_token(TokenKind::LeftParen) {
+            while !self.match_token(TokenKind::RightParen) {
+                values.push(self.pattern()?);
+                if !self.match_token(TokenKind::Comma) {
+                    self.consume(TokenKind::RightParen, "Expected ')' after the values")?;
+                    break;
+                }
+            }
+        }
+        Ok(values)
+    }
+    fn pattern(&mut self) -> ParseResult<Pattern> {
+        self.advance();
+        let token = self.previous().clone();
+        let pattern = match token.kind {
+            TokenKind::Identifier if token.lexeme == "_" => Pattern::Wildcard(token),
+            TokenKind::Identifier if self.match_token(TokenKind::Dot) => {
+                let name = self.token_as_identifier("Expected a variant name after '.'")?;
+                Pattern::Variant {
+                    kind: Some(Identifier { value: token }),
+                    name: name.value,
+                    values: self.variant_patterns()?,
+                }
+            }
+            TokenKind::Identifier
+                if matches!(token.lexeme.as_str(), "Ok" | "Err")
+                    && self.check(TokenKind::LeftParen) =>
+            {
+                Pattern::Variant {
+                    kind: None,
+                    name: token,
+                    values: self.variant_patterns()?,
+                }
+            }
+            TokenKind::Identifier => Pattern::Binding(token),
+            TokenKind::LeftBracket => {
+                let mut elements = Vec::new();
+                let mut rest = false;
+                while !self.match_token(TokenKind::RightBracket) {
+                    // `..` can only come last
+                    if self.match_token(TokenKind::DotDot) {
+                        rest = true;
+                        self.consume(TokenKind::RightBracket, "Expected ']' after '..'")?;
+                        break;
+                    }
+                    elements.push(self.pattern()?);
+                    if !self.match_token(TokenKind::Comma) {
+                        self.consume(TokenKind::RightBracket, "Expected ']' after the elements")?;
+                        break;
+                    }
+                }
+                Pattern::Array {
+                    bracket: token,
+                    elements,
+                    rest,
+                }
+            }
+            TokenKind::Dash => {
+                self.consume(TokenKind::Number, "Expected a number after '-'")?;
+                let Literal(Literals::Number(number), _) = self.literal_pattern()? else {
+                    unreachable!()
+                };
+                Pattern::Literal(Literal(Literals::Number(-number), token))
+            }
+            TokenKind::Number | TokenKind::String | TokenKind::True | TokenKind::False => {
+                Pattern::Literal(self.literal_pattern()?)
+            }
+            _ => return Err(self.error(ErrorCode::ExpectedExpression, "Expected a pattern")),
+        };
+        Ok(pattern)
+    }
+    /// the literal that was just advanced past
+    fn literal_pattern(&mut self) -> ParseResult<Literal> {
+        let token = self.previous().clone();
+        let literal = match token.kind {
+            TokenKind::True => Literals::Bool(true),
+            TokenKind::False => Literals::Bool(false),
+            TokenKind::String => Literals::String(token.lexeme.clone()),
+            _ => match token.lexeme.parse::<f64>() {
+                Ok(number) => Literals::Number(number),
+                Err(_) => return Err(self.error(ErrorCode::InvalidToken, "Invalid number")),
+            },
+        };
+        Ok(Literal(literal, token))
+    }
+    pub fn block(&mut self, _can_assign: bool) -> ParseResult<Node> {
+        Ok(self.block_body()?.to_node())
+    }
+    /// parses the rest of a block after its `{`
+    pub fn block_body(&mut self) -> ParseResult<Block> {
         self.begin_scope();
         let mut block = Block {
             declarations: Vec::new(),
         };
         loop {
             if !self.check(TokenKind::RightBrace) && !self.check(TokenKind::EOF) {
-                block.declarations.push(self.node())
+                let node = self.node();
+                if node.ne(&Node::Empty) {
+                    block.declarations.push(node)
+                }
             } else {
                 break;
             }
         }
-        self.consume(TokenKind::RightBrace, "Expected '}' after block to close")
-            .unwrap();
         self.end_scope();
-        block.to_node()
+        self.consume(TokenKind::RightBrace, "Expected '}' after block to close")?;
+        Ok(block)
     }
-    pub fn string(&mut self, _can_assign: bool) -> Node {
-        Literal(
+    pub fn string(&mut self, _can_assign: bool) -> ParseResult<Node> {
+        Ok(Literal(
             Literals::String(self.previous().lexeme.clone()),
             self.previous().clone(),
         )
-        .as_node()
+        .as_node())
     }
-    pub fn binary(&mut self, lhs: Node) -> Node {
+    pub fn binary(&mut self, lhs: Node) -> ParseResult<Node> {
         let rule = Self::get_rule(self.previous().kind);
         let op = self.previous().clone();
+        let assignable = matches!(
+            lhs,
+            Node::Identifier(_) | Node::Expression(Expression::Property(_))
+        );
+        if op.kind == TokenKind::Equal && !assignable {
+            return Err(self.error(
+                ErrorCode::InvalidAssignmentTarget,
+                "Only variables and properties can be assigned to",
+            ));
+        }
         // the precedence is +1 so it'll compile it as the rhs
         #[allow(unsafe_code)]
         let prec: Precedence = unsafe { transmute((rule.precedence as u8) + 1) };
-        let rhs = self.precedence(prec).unwrap();
+        let rhs = self.precedence(prec)?;
 
-        Expression::Binary(BinaryExpr {
+        Ok(Expression::Binary(BinaryExpr {
             lhs: Box::new(lhs),
             rhs: Box::new(rhs),
             op,
+            operands: Type::Any,
         })
-        .to_node()
+        .to_node())
     }
-    pub fn number(&mut self, _can_assign: bool) -> Node {
+    pub fn number(&mut self, _can_assign: bool) -> ParseResult<Node> {
         let token = self.previous().clone();
-        let number = token.lexeme.parse::<f64>().unwrap();
-        Literal(Literals::Number(number), token).as_node()
+        let Ok(number) = token.lexeme.parse::<f64>() else {
+            return Err(self.error(ErrorCode::InvalidToken, "Invalid number"));
+        };
+        Ok(Literal(Literals::Number(number), token).as_node())
     }
-    pub fn error_at_current(&mut self, msg: &str) {
-        self.had_error = true;
-        let current = self.current().to_owned();
-        self.error_at(&current, msg);
+    pub fn error_at_current(&self, code: ErrorCode, msg: &str) -> ParseError {
+        self.error_at(self.current(), code, msg)
     }
-    pub fn error_at(&mut self, token: &Token, msg: &str) {
-        self.panic_mode = true;
-        let mut diagnostics = self.diagnostics.borrow_mut();
-
-        match token.kind {
-            TokenKind::EOF => {
-                diagnostics.log(
-                    Some(&token.position),
-                    "Compiler",
-                    "Error at EOF: ".to_string(),
-                );
-            }
-
+    pub fn error_at(&self, token: &Token, code: ErrorCode, msg: &str) -> ParseError {
+        // the scanner's errors are more specific than whatever was expected instead
+        let (code, msg) = match token.kind {
+            TokenKind::Error => (ErrorCode::InvalidToken, "Invalid token"),
+            _ => (code, msg),
+        };
+        let label = match token.kind {
+            TokenKind::EOF => "unexpected end of file".to_string(),
+            TokenKind::Error => token.lexeme.clone(),
             _ => {
-                let range: Range<usize> = (token.position.start_in_source as usize)
-                    ..(token.position.start_in_source as usize + token.length);
-                diagnostics.log(
-                    Some(&token.position),
-                    "Compiler",
-                    format!("Error at `{}`: ", &self.scanner.source[range]),
-                );
-            }
-        }
-        println!("{}", msg.red());
-        println!(
-            "{}",
-            format!("(location:  {}:{})", file!(), line!()).black()
-        );
+                let start = token.position.start_in_source;
+                let lexeme = self
+                    .scanner
+                    .source
+                    .get(start..start + token.length)
+                    .unwrap_or(&token.lexeme);
+                format!("found `{lexeme}`")
+            }
+        };
+        let span = match token.kind {
+            // points just past the last token, rather than at wherever the file ends
+            TokenKind::EOF if self.previous().kind != TokenKind::EOF => self.after_previous(),
+            _ => Span::from(token),
+        };
+        ParseError(Diagnostic::error(code, msg).with_label(span, label))
+    }
+    /// the spot right after the previous token, where a missing token belongs
+    fn after_previous(&self) -> Span {
+        let previous = self.previous();
+        let mut span = Span::from(previous);
+        span.position.start_in_line += previous.length;
+        span.position.start_in_source += previous.length;
+        span.length = 1;
+        span
     }
 }
 const EOF: &Token = &Token {
@@ -617,45 +1329,6 @@ const EOF: &Token = &Token {
         start_in_line: 0,
     },
 };
-macro_rules! error_at_current {
-    ($parser:expr, $msg:expr) => {{
-        $parser.had_error = true;
-        let current = $parser.current().to_owned();
-        error_at!($parser, &current, $msg);
-    }};
-}
-
-macro_rules! error_at {
-    ($parser:expr, $token:expr, $msg:expr) => {{
-        $parser.panic_mode = true;
-        let mut diagnostics = $parser.diagnostics.borrow_mut();
-
-        match $token.kind {
-            TokenKind::EOF => {
-                diagnostics.log(
-                    Some(&$token.position),
-                    "Compiler",
-                    "Error at EOF: ".to_string(),
-                );
-            }
-
-            _ => {
-                let range: Range<usize> = ($token.position.start_in_source as usize)
-                    ..($token.position.start_in_source as usize + $token.length);
-                diagnostics.log(
-                    Some(&$token.position),
-                    "Compiler",
-                    format!("Error at `{}`: ", &$parser.scanner.source[range]),
-                );
-            }
-        }
-        println!("{}", $msg.red());
-        println!(
-            "{}",
-            format!("(location:  {}:{})", file!(), line!()).black()
-        );
-    }};
-}
 impl<'a> Parser<'a> {
     pub fn begin_scope(&mut self) {
         self.scope_depth += 1;
@@ -663,29 +1336,34 @@ impl<'a> Parser<'a> {
     pub fn end_scope(&mut self) {
         self.scope_depth -= 1;
     }
-    pub fn error(&mut self, msg: &str) {
-        self.had_error = true;
-
-        let previous = self.previous().to_owned();
-        error_at!(self, &previous, msg);
+    pub fn error(&self, code: ErrorCode, msg: &str) -> ParseError {
+        self.error_at(self.previous(), code, msg)
     }
 
+    /// skips to the start of the next statement
     pub fn synchronize(&mut self) {
-        self.panic_mode = false;
         loop {
-            if self.current().kind.eq(&TokenKind::EOF) {
+            // the end of the enclosing block is a boundary too
+            if self.current().kind.eq(&TokenKind::EOF)
+                || self.current().kind.eq(&TokenKind::RightBrace)
+            {
                 return;
             }
             if self.previous().kind.eq(&TokenKind::SemiColon) {
                 return;
             };
-            println!("{}", self.previous().kind);
-            match self.previous().kind {
+            match self.current().kind {
                 TokenKind::Return
                 | TokenKind::If
                 | TokenKind::While
                 | TokenKind::Print
                 | TokenKind::Func
+                | TokenKind::Class
+                | TokenKind::Struct
+                | TokenKind::Enum
+                | TokenKind::Match
+                | TokenKind::Try
+                | TokenKind::Throw
                 | TokenKind::Let => {
                     return;
                 }
@@ -705,9 +1383,11 @@ impl<'a> Parser<'a> {
             diagnostics,
             scanner,
             had_error: false,
-            panic_mode: false,
             scope_depth: 0,
             function_type,
+            classes: Vec::new(),
+            lambdas: Vec::new(),
+            file_attributes: FileAttributes::default(),
             token_state: TokenState {
                 current: EOF.to_owned(),
                 previous: Rc::new(EOF.to_owned()),
@@ -738,8 +1418,13 @@ impl<'a> Parser<'a> {
     pub fn consume(&mut self, kind: TokenKind, err: &str) -> ParseResult<&Token> {
         let current = self.current().kind;
         if current.ne(&kind) {
-            error_at_current!(self, err);
-            return Ok(self.current());
+            // a missing `;` is reported where it should be, not on the next line
+            if kind == TokenKind::SemiColon && self.previous().kind != TokenKind::EOF {
+                let diagnostic = Diagnostic::error(ErrorCode::ExpectedToken, err)
+                    .with_label(self.after_previous(), "expected `;`");
+                return Err(ParseError(diagnostic));
+            }
+            return Err(self.error_at_current(ErrorCode::ExpectedToken, err));
         }
 
         Ok(self.advance())
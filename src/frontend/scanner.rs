@@ -8,6 +8,8 @@ pub struct Scanner {
     pub line: usize,
     pub tokens: Vec<Token>,
     pub line_info: LineInfo,
+    /// emits `//` comments as `Comment` tokens instead of skipping them, for the formatter
+    pub keep_comments: bool,
 }
 macro_rules! token {
     ($self:ident, Error, $reason:expr) => {{
@@ -19,7 +21,7 @@ macro_rules! token {
             position: Position {
                 line: $self.line,
                 start_in_line: $self.line_info.start,
-                start_in_source: ($self.start as u16),
+                start_in_source: $self.start,
             },
         }
     }};
@@ -32,7 +34,7 @@ macro_rules! token {
             position: Position {
                 line: $self.line,
                 start_in_line: $self.line_info.start,
-                start_in_source: ($self.start as u16),
+                start_in_source: $self.start,
             },
         }
     }};
@@ -45,7 +47,7 @@ macro_rules! token {
             position: Position {
                 line: $self.line,
                 start_in_line: $self.line_info.start,
-                start_in_source: ($self.start as u16),
+                start_in_source: $self.start,
             },
         }
     }};
@@ -61,8 +63,22 @@ pub struct Token {
 #[derive(Clone, PartialEq, Debug, Default)]
 pub struct Position {
     pub line: usize,
-    pub start_in_line: u16,
-    pub start_in_source: u16,
+    pub start_in_line: usize,
+    pub start_in_source: usize,
+}
+/// a piece of source code that a diagnostic or an instruction can point back to
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Span {
+    pub position: Position,
+    pub length: usize,
+}
+impl From<&Token> for Span {
+    fn from(token: &Token) -> Self {
+        Span {
+            position: token.position.clone(),
+            length: token.length,
+        }
+    }
 }
 impl fmt::Display for Token {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -90,6 +106,15 @@ pub enum TokenKind {
     False,
     True,
     Func,
+    Class,
+    Struct,
+    Enum,
+    Match,
+    Try,
+    Catch,
+    Throw,
+    This,
+    Super,
     RightBracket,
     LeftBracket,
     RightBrace,
@@ -99,6 +124,10 @@ pub enum TokenKind {
 
     Equal,
     EqualEqual,
+    /// `=>`, between a pattern and its arm
+    FatArrow,
+    /// `->`, before the return type of a function
+    Arrow,
 
     Number,
     String,
@@ -120,7 +149,19 @@ pub enum TokenKind {
 
     SemiColon,
     Comma,
+    Dot,
+    DotDot,
+    /// before the rest parameter of a function, `...rest`
+    DotDotDot,
+    ColonColon,
+    Colon,
+    /// the postfix `?` that returns an `Err` early
+    Question,
+    /// around the parameters of a lambda, `|x| x * 2`
+    Pipe,
 
+    /// only produced when `Scanner::keep_comments` is set
+    Comment,
     Error,
     #[default]
     WHITESPACE,
@@ -134,8 +175,8 @@ impl fmt::Display for TokenKind {
 }
 #[derive(Debug, Clone, PartialEq, Default)]
 pub struct LineInfo {
-    pub current: u16,
-    pub start: u16,
+    pub current: usize,
+    pub start: usize,
 }
 impl Scanner {
     pub fn scan_thru(&mut self) {
@@ -157,6 +198,7 @@ impl Scanner {
                 current: 0,
                 start: 0,
             },
+            keep_comments: false,
         }
     }
     pub fn reset(&mut self, source: String) {
@@ -168,16 +210,32 @@ impl Scanner {
         self.line_info.start = 0;
         self.tokens.clear()
     }
+    /// scans the next `count` tokens without consuming them
+    pub fn lookahead(&mut self, count: usize) -> Vec<Token> {
+        let (start, current, line) = (self.start, self.current, self.line);
+        let (line_info, scanned) = (self.line_info.clone(), self.tokens.len());
+        let tokens = (0..count).map(|_| self.next_token()).collect();
+        (self.start, self.current, self.line) = (start, current, line);
+        self.line_info = line_info;
+        self.tokens.truncate(scanned);
+        tokens
+    }
     pub fn next_token(&mut self) -> Token {
         self.ignore_whitespace();
 
+        self.start = self.current;
+        self.line_info.start = self.line_info.current;
         if self.at_end() {
             return token!(self, EOF);
         }
-        self.start = self.current;
-        self.line_info.start = self.line_info.current;
+        if self.keep_comments && self.peek() == '/' && self.peek_next() == '/' {
+            while !self.at_end() && self.peek() != '\n' {
+                self.advance();
+            }
+            return token!(self, Comment);
+        }
         let char = self.advance();
-        if char.is_alphabetic() {
+        if char.is_alphabetic() || char == '_' {
             let token = self.identifier();
             self.tokens.push(token.clone());
             return token;
@@ -203,6 +261,9 @@ impl Scanner {
                 if self.matches('=') {
                     return token!(self, EqualEqual);
                 }
+                if self.matches('>') {
+                    return token!(self, FatArrow);
+                }
                 token!(self, Equal)
             }
             '*' => token!(self, Star),
@@ -223,6 +284,9 @@ impl Scanner {
                 if self.matches('=') {
                     return token!(self, DashEqual);
                 }
+                if self.matches('>') {
+                    return token!(self, Arrow);
+                }
                 token!(self, Dash)
             }
             '>' => {
@@ -242,7 +306,16 @@ impl Scanner {
             ')' => token!(self, RightParen),
             '}' => token!(self, RightBrace),
             ';' => token!(self, SemiColon),
+            '|' => token!(self, Pipe),
             ',' => token!(self, Comma),
+            '.' if self.matches('.') => match self.matches('.') {
+                true => token!(self, DotDotDot),
+                false => token!(self, DotDot),
+            },
+            '.' => token!(self, Dot),
+            ':' if self.matches(':') => token!(self, ColonColon),
+            ':' => token!(self, Colon),
+            '?' => token!(self, Question),
             '!' => {
                 if self.matches('=') {
                     return token!(self, BangEqual);
@@ -264,11 +337,14 @@ impl Scanner {
     fn peek(&self) -> char {
         self.source.as_bytes()[self.current] as char
     }
+    fn previous(&self) -> char {
+        self.source.as_bytes()[self.current - 1] as char
+    }
     fn peek_next(&self) -> char {
         if self.current + 1 >= self.source.len() {
             '\0'
         } else {
-            return self.source.as_bytes()[self.current + 1] as char;
+            self.source.as_bytes()[self.current + 1] as char
         }
     }
     pub fn at_end(&self) -> bool {
@@ -281,7 +357,7 @@ impl Scanner {
         if self.at_end() || self.peek() != to {
             false
         } else {
-            self.current += 1;
+            self.advance();
             true
         }
     }
@@ -301,13 +377,9 @@ impl Scanner {
                     self.line_info.current = 0;
                     self.line_info.start = 0;
                 }
-                '/' => {
-                    if self.peek_next() == '/' {
-                        while !self.at_end() && self.peek() != '\n' {
-                            self.advance();
-                        }
-                    } else {
-                        break;
+                '/' if self.peek_next() == '/' && !self.keep_comments => {
+                    while !self.at_end() && self.peek() != '\n' {
+                        self.advance();
                     }
                 }
                 _x => {
@@ -317,25 +389,33 @@ impl Scanner {
         }
     }
     fn string(&mut self) -> Token {
+        // a string can span lines, but it's reported where it starts
+        let line = self.line;
+        let start_in_line = self.line_info.start;
         while !self.at_end() && self.peek() != '"' {
-            if self.peek() == '\n' {
-                self.line += 1
-            }
             self.advance();
+            if self.previous() == '\n' {
+                self.line += 1;
+                self.line_info.current = 0;
+            }
         }
         if self.at_end() {
-            return token!(self, Error, String::from("unterminated string"));
+            let mut token = token!(self, Error, String::from("unterminated string"));
+            token.position.line = line;
+            token.position.start_in_line = start_in_line;
+            token.length = 1;
+            return token;
         }
         self.advance();
         Token {
             kind: TokenKind::String,
             lexeme: self.source[self.start + 1..self.current - 1].to_string(),
-            line: self.line,
+            line,
             length: self.current - self.start,
             position: Position {
-                line: self.line,
-                start_in_line: self.line_info.start,
-                start_in_source: self.start as u16,
+                line,
+                start_in_line,
+                start_in_source: self.start,
             },
         }
     }
@@ -366,6 +446,15 @@ impl Scanner {
             "false" => TokenKind::False,
             "true" => TokenKind::True,
             "func" => TokenKind::Func,
+            "class" => TokenKind::Class,
+            "struct" => TokenKind::Struct,
+            "enum" => TokenKind::Enum,
+            "match" => TokenKind::Match,
+            "try" => TokenKind::Try,
+            "catch" => TokenKind::Catch,
+            "throw" => TokenKind::Throw,
+            "this" => TokenKind::This,
+            "super" => TokenKind::Super,
             "return" => TokenKind::Return,
             "print" => TokenKind::Print,
             "assert_eq" => TokenKind::AssertEq,
@@ -374,10 +463,22 @@ impl Scanner {
         }
     }
     fn identifier(&mut self) -> Token {
-        while !self.at_end() && {
-            let char = self.peek();
-            char.is_alphanumeric() || char == '_'
-        } {
+        loop {
+            while !self.at_end() && {
+                let char = self.peek();
+                char.is_alphanumeric() || char == '_'
+            } {
+                self.advance();
+            }
+            // `lib::name` is one qualified name, the `::` of `use lib::{a, b}` is its own token
+            let qualified = self.remaining() >= 3
+                && self.peek() == ':'
+                && self.peek_next() == ':'
+                && (self.source.as_bytes()[self.current + 2] as char).is_alphabetic();
+            if !qualified {
+                break;
+            }
+            self.advance();
             self.advance();
         }
         token!(self, Identifier)
@@ -3,5 +3,7 @@ pub mod backend;
 pub mod cli_helper;
 pub mod common;
 pub mod frontend;
+pub mod lsp;
+pub mod package;
 pub mod prelude;
 pub mod rust_bindings;
@@ -0,0 +1,419 @@
+//! a language server for `.mng` files, spoken over stdio by `limesherbet lsp`
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    error::Error,
+    panic::{catch_unwind, AssertUnwindSafe},
+    path::PathBuf,
+    rc::Rc,
+};
+
+use lsp_server::{Connection, ExtractError, Message, Notification, Request, RequestId, Response};
+use lsp_types::{
+    notification::{
+        DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _,
+        PublishDiagnostics,
+    },
+    request::{
+        DocumentSymbolRequest, GotoDefinition, HoverRequest, Initialize, References, Request as _,
+    },
+    DiagnosticSeverity, DocumentSymbol, DocumentSymbolResponse, GotoDefinitionResponse, Hover,
+    HoverContents, HoverProviderCapability, InitializeParams, InitializeResult, Location,
+    MarkupContent, MarkupKind, NumberOrString, OneOf, Position, PositionEncodingKind,
+    PublishDiagnosticsParams, Range, ServerCapabilities, SymbolKind as LspKind,
+    TextDocumentPositionParams, TextDocumentSyncCapability, TextDocumentSyncKind, Url,
+};
+
+use crate::{
+    cli_helper::{Diagnostic, Diagnostics, Severity},
+    frontend::{
+        compiler::{
+            symbols::{Definition, SymbolKind, Symbols},
+            Compiler, FunctionType,
+        },
+        scanner::{Span, Token},
+    },
+};
+
+pub type LspResult<T> = Result<T, Box<dyn Error + Sync + Send>>;
+
+/// runs the server until the client asks it to shut down
+pub fn run() -> LspResult<()> {
+    let (connection, io_threads) = Connection::stdio();
+    let mut server = Server::default();
+    let (id, params) = connection.initialize_start()?;
+    let response = server.handle_request(Request::new(id, Initialize::METHOD.to_owned(), params));
+    if let Some(error) = response.error {
+        return Err(error.message.into());
+    }
+    connection.initialize_finish(response.id, response.result.unwrap_or_default())?;
+
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection.handle_shutdown(&request)? {
+                    break;
+                }
+                let response = server.handle_request(request);
+                connection.sender.send(Message::Response(response))?;
+            }
+            Message::Notification(notification) => {
+                if let Some(uri) = server.handle_notification(notification) {
+                    let publish = server.publish_diagnostics(uri);
+                    connection.sender.send(Message::Notification(publish))?;
+                }
+            }
+            Message::Response(_) => {}
+        }
+    }
+    // the writer thread only finishes once every sender is gone
+    drop(connection);
+    io_threads.join()?;
+    Ok(())
+}
+
+#[derive(Default)]
+pub struct Server {
+    documents: HashMap<Url, Document>,
+    /// whether the client counts columns in bytes like spans do, rather than in the
+    /// UTF-16 code units the protocol defaults to. agreed on in `initialize`
+    utf8: bool,
+}
+
+pub struct Document {
+    pub source: String,
+    pub diagnostics: Vec<Diagnostic>,
+    /// kept from the last compile that got far enough to resolve names, so navigation
+    /// keeps working while a syntax error is being typed
+    pub symbols: Symbols,
+}
+
+impl Server {
+    /// returns the document that changed, so its diagnostics can be published
+    pub fn handle_notification(&mut self, notification: Notification) -> Option<Url> {
+        match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params = cast_notification::<DidOpenTextDocument>(notification)?;
+                let document = params.text_document;
+                self.update(document.uri.clone(), document.text);
+                Some(document.uri)
+            }
+            DidChangeTextDocument::METHOD => {
+                let mut params = cast_notification::<DidChangeTextDocument>(notification)?;
+                // only full syncs are advertised, so the last change is the whole file
+                let change = params.content_changes.pop()?;
+                self.update(params.text_document.uri.clone(), change.text);
+                Some(params.text_document.uri)
+            }
+            DidCloseTextDocument::METHOD => {
+                let params = cast_notification::<DidCloseTextDocument>(notification)?;
+                self.documents.remove(&params.text_document.uri);
+                None
+            }
+            _ => None,
+        }
+    }
+
+    pub fn handle_request(&mut self, request: Request) -> Response {
+        let id = request.id.clone();
+        let result = match request.method.as_str() {
+            Initialize::METHOD => cast_request::<Initialize>(request)
+                .map(|params| serde_json::to_value(self.initialize(params))),
+            GotoDefinition::METHOD => cast_request::<GotoDefinition>(request).map(|params| {
+                serde_json::to_value(self.definition(params.text_document_position_params))
+            }),
+            References::METHOD => cast_request::<References>(request).map(|params| {
+                serde_json::to_value(self.references(
+                    params.text_document_position,
+                    params.context.include_declaration,
+                ))
+            }),
+            HoverRequest::METHOD => cast_request::<HoverRequest>(request).map(|params| {
+                serde_json::to_value(self.hover(params.text_document_position_params))
+            }),
+            DocumentSymbolRequest::METHOD => {
+                cast_request::<DocumentSymbolRequest>(request).map(|params| {
+                    serde_json::to_value(self.document_symbols(&params.text_document.uri))
+                })
+            }
+            _ => {
+                return Response::new_err(
+                    id,
+                    lsp_server::ErrorCode::MethodNotFound as i32,
+                    format!("unhandled method {}", request.method),
+                )
+            }
+        };
+        match result {
+            Some(Ok(value)) => Response::new_ok(id, value),
+            Some(Err(error)) => invalid_params(id, error.to_string()),
+            None => invalid_params(id, "malformed params".to_string()),
+        }
+    }
+
+    fn initialize(&mut self, params: InitializeParams) -> InitializeResult {
+        self.utf8 = params
+            .capabilities
+            .general
+            .and_then(|general| general.position_encodings)
+            .is_some_and(|encodings| encodings.contains(&PositionEncodingKind::UTF8));
+        let encoding = match self.utf8 {
+            true => PositionEncodingKind::UTF8,
+            false => PositionEncodingKind::UTF16,
+        };
+        InitializeResult {
+            capabilities: ServerCapabilities {
+                position_encoding: Some(encoding),
+                text_document_sync: Some(TextDocumentSyncCapability::Kind(
+                    TextDocumentSyncKind::FULL,
+                )),
+                definition_provider: Some(OneOf::Left(true)),
+                references_provider: Some(OneOf::Left(true)),
+                hover_provider: Some(HoverProviderCapability::Simple(true)),
+                document_symbol_provider: Some(OneOf::Left(true)),
+                ..Default::default()
+            },
+            server_info: None,
+        }
+    }
+
+    fn update(&mut self, uri: Url, source: String) {
+        let path = uri
+            .to_file_path()
+            .unwrap_or_else(|_| PathBuf::from(uri.path()));
+        let (diagnostics, symbols) = analyze(&path, &source);
+        let symbols = match (symbols, self.documents.remove(&uri)) {
+            (Some(symbols), _) => symbols,
+            (None, Some(previous)) => previous.symbols,
+            (None, None) => Symbols::default(),
+        };
+        self.documents.insert(
+            uri,
+            Document {
+                source,
+                diagnostics,
+                symbols,
+            },
+        );
+    }
+
+    pub fn publish_diagnostics(&self, uri: Url) -> Notification {
+        let diagnostics = self
+            .documents
+            .get(&uri)
+            .map(|document| {
+                document
+                    .diagnostics
+                    .iter()
+                    .map(|diagnostic| to_lsp_diagnostic(diagnostic, document, self.utf8))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Notification::new(
+            PublishDiagnostics::METHOD.to_owned(),
+            PublishDiagnosticsParams {
+                uri,
+                diagnostics,
+                version: None,
+            },
+        )
+    }
+
+    /// the document and the definition of the name under the cursor
+    fn lookup(&self, position: &TextDocumentPositionParams) -> Option<(&Document, usize)> {
+        let document = self.documents.get(&position.text_document.uri)?;
+        let line = position.position.line as usize;
+        let column = document.byte_column(line, position.position.character, self.utf8);
+        let definition = document.symbols.definition_at(line, column)?;
+        Some((document, definition))
+    }
+
+    fn definition(&self, position: TextDocumentPositionParams) -> Option<GotoDefinitionResponse> {
+        let (document, definition) = self.lookup(&position)?;
+        let name = &document.symbols.definitions[definition].name;
+        Some(GotoDefinitionResponse::Scalar(document.location(
+            &position.text_document.uri,
+            name,
+            self.utf8,
+        )))
+    }
+
+    fn references(
+        &self,
+        position: TextDocumentPositionParams,
+        include_declaration: bool,
+    ) -> Option<Vec<Location>> {
+        let (document, definition) = self.lookup(&position)?;
+        let uri = &position.text_document.uri;
+        let mut locations = Vec::new();
+        if include_declaration {
+            let name = &document.symbols.definitions[definition].name;
+            locations.push(document.location(uri, name, self.utf8));
+        }
+        locations.extend(
+            document
+                .symbols
+                .references_to(definition)
+                .map(|reference| document.location(uri, &reference.name, self.utf8)),
+        );
+        Some(locations)
+    }
+
+    fn hover(&self, position: TextDocumentPositionParams) -> Option<Hover> {
+        let (document, definition) = self.lookup(&position)?;
+        let definition = &document.symbols.definitions[definition];
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: describe(definition),
+            }),
+            range: None,
+        })
+    }
+
+    fn document_symbols(&self, uri: &Url) -> Option<DocumentSymbolResponse> {
+        let document = self.documents.get(uri)?;
+        #[allow(deprecated)]
+        let symbols = document
+            .symbols
+            .definitions
+            .iter()
+            .filter_map(|definition| match definition.kind {
+                SymbolKind::Function => Some((definition, LspKind::FUNCTION)),
+                SymbolKind::Class => Some((definition, LspKind::CLASS)),
+                SymbolKind::Struct => Some((definition, LspKind::STRUCT)),
+                SymbolKind::Enum => Some((definition, LspKind::ENUM)),
+                _ => None,
+            })
+            .map(|(definition, kind)| DocumentSymbol {
+                name: definition.name.lexeme.clone(),
+                detail: definition.signature.clone(),
+                kind,
+                tags: None,
+                deprecated: None,
+                range: document.range(&Span::from(&definition.name), self.utf8),
+                selection_range: document.range(&Span::from(&definition.name), self.utf8),
+                children: None,
+            })
+            .collect();
+        Some(DocumentSymbolResponse::Nested(symbols))
+    }
+}
+
+/// compiles a document without running it. the symbols are `None` if it never got
+/// past parsing, since nothing was resolved
+pub fn analyze(path: &std::path::Path, source: &str) -> (Vec<Diagnostic>, Option<Symbols>) {
+    let diagnostics = Rc::new(RefCell::new(Diagnostics::new(path)));
+    let symbols = Rc::new(RefCell::new(Symbols::default()));
+    let mut compiler = Compiler::new(diagnostics.clone(), FunctionType::Script);
+    compiler.symbols = Some(symbols.clone());
+    // a bug in the compiler shouldn't take the whole server down with it
+    let compiled = catch_unwind(AssertUnwindSafe(|| compiler.compile(source.to_owned())));
+    let mut reported = match compiled {
+        Ok(Ok(_)) => diagnostics.borrow_mut().take(),
+        Ok(Err(errors)) => errors,
+        Err(_) => return (Vec::new(), None),
+    };
+    // errors in imported modules are published for those files when they are opened
+    reported.retain(|diagnostic| diagnostic.file.is_none());
+    let symbols = symbols.take();
+    let resolved = !symbols.definitions.is_empty() || !symbols.references.is_empty();
+    (reported, resolved.then_some(symbols))
+}
+
+fn describe(definition: &Definition) -> String {
+    let name = &definition.name.lexeme;
+    match (definition.kind, &definition.signature, definition.arity) {
+        (SymbolKind::Function, Some(signature), Some(arity)) => {
+            let plural = if arity == 1 { "" } else { "s" };
+            format!("```limesherbet\n{signature}\n```\ntakes {arity} argument{plural}")
+        }
+        (SymbolKind::Parameter, ..) => format!("```limesherbet\n{name}\n```\nparameter"),
+        (SymbolKind::Class, ..) => format!("```limesherbet\nclass {name}\n```"),
+        (SymbolKind::Struct, ..) => format!("```limesherbet\nstruct {name}\n```"),
+        (SymbolKind::Enum, ..) => format!("```limesherbet\nenum {name}\n```"),
+        _ => format!("```limesherbet\nlet {name}\n```"),
+    }
+}
+
+fn to_lsp_diagnostic(
+    diagnostic: &Diagnostic,
+    document: &Document,
+    utf8: bool,
+) -> lsp_types::Diagnostic {
+    let severity = match diagnostic.severity {
+        Severity::Error => DiagnosticSeverity::ERROR,
+        Severity::Warning => DiagnosticSeverity::WARNING,
+        Severity::Note => DiagnosticSeverity::INFORMATION,
+    };
+    let mut message = diagnostic.message.clone();
+    for note in diagnostic.notes.iter().chain(&diagnostic.help) {
+        message.push('\n');
+        message.push_str(note);
+    }
+    lsp_types::Diagnostic {
+        range: diagnostic
+            .primary_span()
+            .map(|span| document.range(span, utf8))
+            .unwrap_or_default(),
+        severity: Some(severity),
+        code: Some(NumberOrString::String(diagnostic.code.code().to_owned())),
+        source: Some("limesherbet".to_owned()),
+        message,
+        ..Default::default()
+    }
+}
+
+impl Document {
+    /// spans count columns in bytes, the client in UTF-16 code units unless it asked for `utf8`
+    fn character(&self, line: usize, column: usize, utf8: bool) -> u32 {
+        let text = self.source.lines().nth(line).unwrap_or_default();
+        match text.get(..column) {
+            Some(before) if !utf8 => before.encode_utf16().count() as u32,
+            _ => column as u32,
+        }
+    }
+    /// the byte column a position the client sent points at
+    fn byte_column(&self, line: usize, character: u32, utf8: bool) -> usize {
+        if utf8 {
+            return character as usize;
+        }
+        let text = self.source.lines().nth(line).unwrap_or_default();
+        let mut units = 0;
+        for (column, char) in text.char_indices() {
+            if units >= character as usize {
+                return column;
+            }
+            units += char.len_utf16();
+        }
+        text.len()
+    }
+    fn range(&self, span: &Span, utf8: bool) -> Range {
+        let line = span.position.line;
+        let start = span.position.start_in_line;
+        Range::new(
+            Position::new(line as u32, self.character(line, start, utf8)),
+            Position::new(line as u32, self.character(line, start + span.length, utf8)),
+        )
+    }
+    fn location(&self, uri: &Url, token: &Token, utf8: bool) -> Location {
+        Location::new(uri.clone(), self.range(&Span::from(token), utf8))
+    }
+}
+
+fn cast_request<R: lsp_types::request::Request>(request: Request) -> Option<R::Params> {
+    match request.extract::<R::Params>(R::METHOD) {
+        Ok((_, params)) => Some(params),
+        Err(ExtractError::JsonError { .. } | ExtractError::MethodMismatch(_)) => None,
+    }
+}
+
+fn cast_notification<N: lsp_types::notification::Notification>(
+    notification: Notification,
+) -> Option<N::Params> {
+    notification.extract::<N::Params>(N::METHOD).ok()
+}
+
+fn invalid_params(id: RequestId, message: String) -> Response {
+    Response::new_err(id, lsp_server::ErrorCode::InvalidParams as i32, message)
+}
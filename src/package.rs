@@ -0,0 +1,116 @@
+//! projects described by a `limesherbet.toml`, and where imported modules are looked for
+
+use std::{
+    env,
+    fs::read_to_string,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+pub const MANIFEST: &str = "limesherbet.toml";
+/// a list of directories, separated like `PATH`, that modules are looked for in
+pub const SEARCH_PATH_VAR: &str = "LIMESHERBET_PATH";
+/// the directory the standard library is in, when it isn't installed next to the interpreter
+pub const STDLIB_VAR: &str = "LIMESHERBET_STDLIB";
+
+/// ```toml
+/// [package]
+/// name = "shapes"
+/// entry = "src/main.mng"
+/// libraries = ["lib", "vendor"]
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    pub package: Package,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Package {
+    pub name: String,
+    /// the script `limesherbet run` runs, relative to the manifest
+    #[serde(default = "default_entry")]
+    pub entry: PathBuf,
+    /// directories modules are looked for in, relative to the manifest
+    #[serde(default)]
+    pub libraries: Vec<PathBuf>,
+}
+
+fn default_entry() -> PathBuf {
+    PathBuf::from("main.mng")
+}
+
+#[derive(Debug)]
+pub struct Project {
+    /// the directory the manifest is in
+    pub root: PathBuf,
+    pub manifest: Manifest,
+}
+impl Project {
+    /// looks for a manifest in `directory` and then in every directory above it
+    pub fn find(directory: &Path) -> Result<Option<Project>, String> {
+        let directory = directory
+            .canonicalize()
+            .unwrap_or_else(|_| directory.to_path_buf());
+        for root in directory.ancestors() {
+            let path = root.join(MANIFEST);
+            if !path.is_file() {
+                continue;
+            }
+            let source = read_to_string(&path)
+                .map_err(|error| format!("could not read `{}`: {error}", path.display()))?;
+            let manifest = toml::from_str(&source)
+                .map_err(|error| format!("invalid `{}`: {}", path.display(), error.message()))?;
+            return Ok(Some(Project {
+                root: root.to_path_buf(),
+                manifest,
+            }));
+        }
+        Ok(None)
+    }
+    pub fn entry(&self) -> PathBuf {
+        self.root.join(&self.manifest.package.entry)
+    }
+    pub fn libraries(&self) -> impl Iterator<Item = PathBuf> + '_ {
+        self.manifest
+            .package
+            .libraries
+            .iter()
+            .map(|library| self.root.join(library))
+    }
+}
+
+/// the `.mng` files that ship with the interpreter, imported with `use std::math`. they're
+/// looked for in `LIMESHERBET_STDLIB`, or in the closest `lib/` to the interpreter that has a
+/// `std/` in it, i.e. `lib/` of the install prefix or of the checkout it was built in
+pub fn stdlib_dir() -> PathBuf {
+    if let Some(directory) = env::var_os(STDLIB_VAR) {
+        return PathBuf::from(directory);
+    }
+    let executable = env::current_exe().unwrap_or_default();
+    let directory = executable.parent().unwrap_or(Path::new("."));
+    directory
+        .ancestors()
+        .map(|ancestor| ancestor.join("lib"))
+        .find(|candidate| candidate.join("std").is_dir())
+        .unwrap_or_else(|| directory.join("lib"))
+}
+
+/// where modules a script imports are looked for after the script's own directory: the
+/// libraries of its project, then `LIMESHERBET_PATH`, then the standard library
+pub fn search_path(script: &Path) -> Vec<PathBuf> {
+    let directory = match script.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    // a broken manifest is reported by whoever found the script, not by every import
+    let project = Project::find(directory).ok().flatten();
+    let mut search_path: Vec<PathBuf> = project.iter().flat_map(Project::libraries).collect();
+    if let Some(paths) = env::var_os(SEARCH_PATH_VAR) {
+        search_path.extend(env::split_paths(&paths).filter(|path| !path.as_os_str().is_empty()));
+    }
+    search_path.push(stdlib_dir());
+    search_path
+}
@@ -2,3 +2,458 @@ extern crate macros;
 use macros::make_tests;
 
 make_tests!();
+
+/// formatting twice has to give the same output as formatting once
+#[test]
+fn format_is_idempotent() {
+    use limesherbet::frontend::formatter::format_source;
+    use std::{fs, path::Path};
+
+    fn visit(dir: &Path) {
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                if !path.ends_with("errors") {
+                    visit(&path);
+                }
+                continue;
+            }
+            if path.extension().is_none_or(|extension| extension != "mng") {
+                continue;
+            }
+            let source = fs::read_to_string(&path).unwrap();
+            let formatted = format_source(&path, &source).unwrap();
+            let again = format_source(&path, &formatted).unwrap();
+            assert_eq!(
+                formatted,
+                again,
+                "{} is not formatted stably",
+                path.display()
+            );
+        }
+    }
+    visit(Path::new("tests/scripts"));
+}
+
+/// every `tests/format/<name>.input.mng` has to format to `<name>.output.mng`, which has to
+/// stay as it is
+#[test]
+fn format_matches_golden_files() {
+    use limesherbet::frontend::formatter::format_source;
+    use std::{fs, path::Path};
+
+    for entry in fs::read_dir("tests/format").unwrap() {
+        let input = entry.unwrap().path();
+        let Some(name) = input.to_str().unwrap().strip_suffix(".input.mng") else {
+            continue;
+        };
+        let output = Path::new(name).with_extension("output.mng");
+        let expected = fs::read_to_string(&output).unwrap();
+        let formatted = format_source(&input, &fs::read_to_string(&input).unwrap()).unwrap();
+        assert_eq!(
+            formatted,
+            expected,
+            "{} is formatted differently",
+            input.display()
+        );
+        let again = format_source(&output, &expected).unwrap();
+        assert_eq!(again, expected, "{} isn't formatted", output.display());
+    }
+}
+
+/// compiles `source` as a script, with or without the optimizer
+fn compile(source: &str, optimize: bool) -> limesherbet::common::function::Function {
+    use limesherbet::{
+        cli_helper::Diagnostics,
+        frontend::compiler::{Compiler, FunctionType},
+    };
+    use std::{cell::RefCell, path::Path, rc::Rc};
+
+    let diagnostics = Rc::new(RefCell::new(Diagnostics::new(Path::new("test.mng"))));
+    let mut compiler = Compiler::new(diagnostics, FunctionType::Script);
+    compiler.optimize = optimize;
+    let (function, _) = compiler.compile(source.to_string()).unwrap();
+    function
+}
+
+/// `!` is only fused into the jump when its operand is known to be a bool
+#[test]
+fn not_fuses_into_jump_only_on_bools() {
+    use limesherbet::common::opcode::OpCode;
+
+    let code = compile("let x = 1; if !(x < 1) { print x; }", true)
+        .chunk
+        .code;
+    assert!(code
+        .iter()
+        .any(|op| matches!(op, OpCode::PopJumpToIfTrue(_))));
+    assert!(!code.contains(&OpCode::Not));
+
+    let code = compile("let x = 1; if !x { print x; }", true).chunk.code;
+    assert!(!code
+        .iter()
+        .any(|op| matches!(op, OpCode::PopJumpToIfTrue(_))));
+    assert!(code.contains(&OpCode::Not));
+}
+
+/// the messages of the diagnostics compiling `source` fails with
+fn compile_errors(source: &str) -> Vec<String> {
+    use limesherbet::{
+        cli_helper::Diagnostics,
+        frontend::compiler::{Compiler, FunctionType},
+    };
+    use std::{cell::RefCell, path::Path, rc::Rc};
+
+    let diagnostics = Rc::new(RefCell::new(Diagnostics::new(Path::new("test.mng"))));
+    let compiler = Compiler::new(diagnostics, FunctionType::Script);
+    match compiler.compile(source.to_string()) {
+        Ok(_) => Vec::new(),
+        Err(errors) => errors.into_iter().map(|error| error.message).collect(),
+    }
+}
+
+#[test]
+fn let_without_equal_expects_equal() {
+    assert_eq!(
+        compile_errors("let b 2;\nlet c;"),
+        ["Expected '=' after variable name"]
+    );
+}
+
+/// a literal that is used again points at the slot it got the first time
+#[test]
+fn repeated_literals_share_a_constant() {
+    use limesherbet::common::value::Value;
+
+    let function = compile(r#"let a = 7; let b = 7; print "x"; print "x";"#, false);
+    let constants = &function.chunk.constants;
+    let sevens = constants
+        .iter()
+        .filter(|constant| matches!(constant, Value::Number(number) if *number == 7.0))
+        .count();
+    assert_eq!(sevens, 1);
+    let strings = constants
+        .iter()
+        .filter(|constant| matches!(constant, Value::String(_)))
+        .count();
+    assert_eq!(strings, 1);
+}
+
+#[test]
+fn too_many_constants_is_an_error() {
+    use limesherbet::common::chunk::MAX_CONSTANTS;
+
+    // every literal is a different constant, one more than a chunk can hold
+    let literals = |count: usize| (0..count).map(|n| format!("{n};\n")).collect::<String>();
+    assert!(compile_errors(&literals(MAX_CONSTANTS - 1)).is_empty());
+    let errors = compile_errors(&literals(MAX_CONSTANTS + 1));
+    assert!(errors
+        .iter()
+        .any(|error| error.starts_with("Too many constants")));
+}
+
+#[test]
+fn too_many_globals_is_an_error() {
+    use limesherbet::frontend::compiler::global::MAX_GLOBALS;
+
+    let globals = |count: usize| {
+        (0..count)
+            .map(|n| format!("let g{n} = 0;\n"))
+            .collect::<String>()
+    };
+    let errors = compile_errors(&globals(MAX_GLOBALS + 1));
+    assert_eq!(
+        errors,
+        [format!("Too many globals, 'g{MAX_GLOBALS}' doesn't fit")]
+    );
+}
+
+/// a language server that has seen `initialize` with `capabilities`, and `didOpen` of `source`
+fn open_document(
+    capabilities: serde_json::Value,
+    source: &str,
+) -> (
+    limesherbet::lsp::Server,
+    serde_json::Value,
+    serde_json::Value,
+) {
+    use lsp_server::{Notification, Request, RequestId};
+    use serde_json::json;
+
+    let mut server = limesherbet::lsp::Server::default();
+    let initialize = Request::new(
+        RequestId::from(1),
+        "initialize".to_owned(),
+        json!({ "capabilities": capabilities }),
+    );
+    let initialized = server.handle_request(initialize).result.unwrap();
+    let open = Notification::new(
+        "textDocument/didOpen".to_owned(),
+        json!({
+            "textDocument": {
+                "uri": "file:///test.mng",
+                "languageId": "limesherbet",
+                "version": 1,
+                "text": source,
+            }
+        }),
+    );
+    let uri = server.handle_notification(open).unwrap();
+    let published = server.publish_diagnostics(uri).params;
+    (server, initialized, published)
+}
+
+/// every column the server sends or is sent counts UTF-16 code units, unless the client
+/// can take bytes
+#[test]
+fn lsp_positions_are_utf16() {
+    use lsp_server::{Request, RequestId};
+    use serde_json::json;
+
+    let source = "let greeting = \"😀\"; print missing;\nfunc add(a, b) {\n    return a + b;\n}\nprint [\"😀\", add(1, 2)];\n";
+    let (mut server, initialized, published) = open_document(json!({}), source);
+    assert_eq!(initialized["capabilities"]["positionEncoding"], "utf-16");
+    let diagnostic = &published["diagnostics"][0];
+    assert_eq!(diagnostic["code"], "E0100");
+    assert_eq!(
+        diagnostic["range"],
+        json!({
+            "start": { "line": 0, "character": 27 },
+            "end": { "line": 0, "character": 34 },
+        })
+    );
+
+    // `add` is 15 bytes but 13 code units into its line
+    let position = json!({
+        "textDocument": { "uri": "file:///test.mng" },
+        "position": { "line": 4, "character": 13 },
+    });
+    let hover = Request::new(
+        RequestId::from(2),
+        "textDocument/hover".to_owned(),
+        position.clone(),
+    );
+    let hover = server.handle_request(hover).result.unwrap();
+    assert!(hover["contents"]["value"]
+        .as_str()
+        .unwrap()
+        .contains("takes 2 arguments"));
+    let definition = Request::new(
+        RequestId::from(3),
+        "textDocument/definition".to_owned(),
+        position,
+    );
+    let definition = server.handle_request(definition).result.unwrap();
+    assert_eq!(
+        definition,
+        json!({
+            "uri": "file:///test.mng",
+            "range": {
+                "start": { "line": 1, "character": 5 },
+                "end": { "line": 1, "character": 8 },
+            },
+        })
+    );
+}
+
+#[test]
+fn lsp_positions_are_bytes_when_the_client_asks() {
+    use serde_json::json;
+
+    let capabilities = json!({ "general": { "positionEncodings": ["utf-8", "utf-16"] } });
+    let source = "let greeting = \"😀\"; print missing;\n";
+    let (_, initialized, published) = open_document(capabilities, source);
+    assert_eq!(initialized["capabilities"]["positionEncoding"], "utf-8");
+    assert_eq!(
+        published["diagnostics"][0]["range"]["start"],
+        json!({ "line": 0, "character": 29 })
+    );
+}
+
+/// a directory under the temp dir that only `test` uses, even with other runs of the tests
+fn temp_dir(test: &str) -> std::path::PathBuf {
+    let directory = std::env::temp_dir().join(format!("limesherbet_{test}_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).unwrap();
+    directory
+}
+
+/// `check --message-format=json` prints one diagnostic per line, with byte offsets that
+/// don't wrap in files over 64 KiB
+#[test]
+fn check_prints_json_diagnostics() {
+    use serde_json::{json, Value};
+    use std::{fs, process::Command};
+
+    let padding = "// padding\n".repeat(7000);
+    let source = format!("{padding}let a = 1;\nprint a + missing;\n");
+    let directory = temp_dir("check_prints_json");
+    let path = directory.join("script.mng");
+    fs::write(&path, &source).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_limesherbet"))
+        .args(["check", "--message-format=json"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    fs::remove_dir_all(&directory).unwrap();
+    assert_eq!(output.status.code(), Some(1));
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let diagnostics: Vec<Value> = stdout
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let start = source.find("missing").unwrap();
+    assert!(start > u16::MAX as usize);
+    let span = json!({
+        "byte_start": start,
+        "byte_end": start + "missing".len(),
+        "line": 7002,
+        "column": 11,
+    });
+    assert_eq!(
+        diagnostics,
+        [json!({
+            "file": path.to_str().unwrap(),
+            "severity": "error",
+            "code": "E0100",
+            "message": "Undefined variable 'missing'",
+            "span": span,
+            "labels": [{
+                "span": span,
+                "message": "not found in this scope",
+                "primary": true,
+            }],
+            "notes": [],
+            "help": ["declare it with `let` before using it"],
+        })]
+    );
+}
+
+/// whether one of `constants` is the string `text`
+fn has_string(constants: &[limesherbet::common::value::Value], text: &str) -> bool {
+    use limesherbet::common::value::Value;
+
+    constants
+        .iter()
+        .any(|constant| matches!(constant, Value::String(_)) && constant.to_string() == text)
+}
+
+/// `-O` computes constant expressions when compiling
+#[test]
+fn optimizer_folds_constants() {
+    use limesherbet::common::{opcode::OpCode, value::Value};
+
+    let source = "print 2 * 3 + 1;";
+    let unoptimized = compile(source, false);
+    assert!(unoptimized.chunk.code.contains(&OpCode::Mul));
+
+    let optimized = compile(source, true);
+    let arithmetic = [OpCode::Mul, OpCode::Add, OpCode::AddNumbers];
+    assert!(!optimized
+        .chunk
+        .code
+        .iter()
+        .any(|op| arithmetic.contains(op)));
+    assert_eq!(optimized.chunk.constants, [Value::Number(7.0)]);
+}
+
+/// `-O` drops the branches a constant condition never takes, and the code after a `return`
+#[test]
+fn optimizer_removes_dead_code() {
+    use limesherbet::common::{opcode::OpCode, value::Value};
+
+    let source = r#"
+        let x = "start";
+        if 1 > 2 { x = "then"; } else { x = "else"; }
+        while false { x = "loop"; }
+        func early() { return 1; print "unreachable"; }
+    "#;
+    let unoptimized = compile(source, false);
+    assert!(has_string(&unoptimized.chunk.constants, "then"));
+
+    let optimized = compile(source, true);
+    let constants = &optimized.chunk.constants;
+    assert!(has_string(constants, "else"));
+    assert!(!has_string(constants, "then"));
+    assert!(!has_string(constants, "loop"));
+    assert!(!optimized.chunk.code.iter().any(|op| matches!(
+        op,
+        OpCode::PopJumpToIfFalse(_) | OpCode::PopJumpToIfTrue(_) | OpCode::JumpTo(_)
+    )));
+    let early = constants
+        .iter()
+        .find_map(|constant| match constant {
+            Value::Function(function) => Some(function),
+            _ => None,
+        })
+        .unwrap();
+    assert!(!has_string(&early.chunk.constants, "unreachable"));
+}
+
+#[test]
+fn run_without_a_script_runs_the_project_entry() {
+    use std::{fs, process::Command};
+
+    let root = temp_dir("run_without_a_script");
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::create_dir_all(root.join("deps")).unwrap();
+    fs::write(
+        root.join("limesherbet.toml"),
+        "[package]\nname = \"greeter\"\nentry = \"src/main.mng\"\nlibraries = [\"deps\"]\n",
+    )
+    .unwrap();
+    fs::write(
+        root.join("src/main.mng"),
+        "use greet::{hello};\nprint hello(\"you\");\n",
+    )
+    .unwrap();
+    fs::write(
+        root.join("deps/greet.mng"),
+        "func hello(name) {\n    return \"hello \" + name;\n}\n",
+    )
+    .unwrap();
+    // the manifest is found from a directory below it too
+    let output = Command::new(env!("CARGO_BIN_EXE_limesherbet"))
+        .arg("run")
+        .current_dir(root.join("src"))
+        .output()
+        .unwrap();
+    fs::remove_dir_all(&root).unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "hello you\n");
+}
+
+#[test]
+fn modules_are_looked_for_in_the_search_path() {
+    use limesherbet::package::SEARCH_PATH_VAR;
+    use std::{fs, process::Command};
+
+    let root = temp_dir("search_path");
+    fs::create_dir_all(root.join("vendor")).unwrap();
+    fs::write(
+        root.join("script.mng"),
+        "use extra::{twice};\nprint twice(2);\n",
+    )
+    .unwrap();
+    fs::write(
+        root.join("vendor/extra.mng"),
+        "func twice(x) {\n    return x * 2;\n}\n",
+    )
+    .unwrap();
+    let run = |search_path: Option<&std::path::Path>| {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_limesherbet"));
+        command.arg("run").arg(root.join("script.mng"));
+        match search_path {
+            Some(search_path) => command.env(SEARCH_PATH_VAR, search_path),
+            None => command.env_remove(SEARCH_PATH_VAR),
+        };
+        command.output().unwrap()
+    };
+    let found = run(Some(&root.join("vendor")));
+    let missing = run(None);
+    fs::remove_dir_all(&root).unwrap();
+    assert!(found.status.success());
+    assert_eq!(String::from_utf8(found.stdout).unwrap(), "4\n");
+    assert_eq!(missing.status.code(), Some(1));
+}